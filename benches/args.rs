@@ -0,0 +1,39 @@
+//! Building the arguments is cheap compared to starting yt-dlp (a Python program), so there
+//! is no need to cache them between invocations. Compare `effective_args` with `spawn_true`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use youtube_dl::YoutubeDl;
+
+fn builder() -> YoutubeDl {
+    let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=VFbhKZFzbzk");
+    builder
+        .format("bv*+ba/b")
+        .socket_timeout("15")
+        .cookies("cookies.txt")
+        .user_agent("Mozilla/5.0 (X11; Linux x86_64)")
+        .output_template("%(title)s-%(id)s.%(ext)s")
+        .output_directory("downloads")
+        .playlist_items(1)
+        .extra_arg_pair("--audio-format", "mp3")
+        .extra_arg("--embed-metadata");
+    builder
+}
+
+fn bench_args(c: &mut Criterion) {
+    let builder = builder();
+    c.bench_function("effective_args", |b| {
+        b.iter(|| black_box(&builder).effective_args())
+    });
+    c.bench_function("clone_builder", |b| b.iter(|| black_box(&builder).clone()));
+
+    // The fastest possible process, as a lower bound for running yt-dlp.
+    #[cfg(unix)]
+    c.bench_function("spawn_true", |b| {
+        b.iter(|| std::process::Command::new("true").status().unwrap())
+    });
+}
+
+criterion_group!(benches, bench_args);
+criterion_main!(benches);
@@ -0,0 +1,263 @@
+//! Adds the fields documented by yt-dlp to `SingleVideo` and `Format` in `src/model.rs`.
+//!
+//! yt-dlp documents the keys of its info dicts in the docstring of `InfoExtractor` in
+//! `yt_dlp/extractor/common.py`. This reads that docstring and rewrites the two structs with all
+//! documented keys, sorted by name. Existing fields keep their types and attributes, new fields
+//! are added as `Option<Value>` and can be given a better type by hand afterwards.
+//!
+//! ```text
+//! cargo run --example generate_model -- path/to/yt_dlp/extractor/common.py
+//! cargo fmt
+//! ```
+
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::process;
+
+const MODEL_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/model.rs");
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where",
+    "while",
+];
+
+/// The keys documented by yt-dlp.
+#[derive(Debug, Default, PartialEq)]
+struct Documented {
+    video: BTreeSet<String>,
+    format: BTreeSet<String>,
+}
+
+/// A field of a struct, with its doc comments and attributes.
+#[derive(Debug)]
+struct Field {
+    name: String,
+    lines: Vec<String>,
+}
+
+fn is_identifier(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_lowercase())
+        && s.chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// The docstring of `InfoExtractor`.
+fn docstring(source: &str) -> Option<&str> {
+    let (_, class) = source.split_once("class InfoExtractor")?;
+    let (_, doc) = class.split_once("\"\"\"")?;
+    let (doc, _) = doc.split_once("\"\"\"")?;
+    Some(doc)
+}
+
+/// Top level keys are indented by four spaces and followed by a colon. The keys of formats are
+/// listed as `* key` in the description of `formats`.
+fn parse_docstring(doc: &str) -> Documented {
+    let mut documented = Documented::default();
+    let mut current = String::new();
+    let mut item_indent = None;
+
+    for line in doc.lines() {
+        let trimmed = line.trim_start();
+        // The rest of the docstring describes playlists and extractor internals.
+        if trimmed.starts_with("_type \"playlist\"") {
+            break;
+        }
+        let indent = line.len() - trimmed.len();
+
+        if indent == 4 {
+            if let Some((key, _)) = trimmed.split_once(':') {
+                if is_identifier(key) {
+                    current = key.to_string();
+                    item_indent = None;
+                    documented.video.insert(current.clone());
+                }
+            }
+        } else if current == "formats" {
+            if let Some(item) = trimmed.strip_prefix("* ") {
+                // Nested lists (like the fields of fragments) are indented further.
+                if *item_indent.get_or_insert(indent) != indent {
+                    continue;
+                }
+                let key = item.split_whitespace().next().unwrap_or_default();
+                if is_identifier(key) {
+                    documented.format.insert(key.to_string());
+                }
+            }
+        }
+    }
+    documented
+}
+
+/// The fields of `pub struct <name>`, and the byte range of its body.
+fn parse_struct(model: &str, name: &str) -> Result<(Vec<Field>, usize, usize), String> {
+    let header = format!("pub struct {} {{\n", name);
+    let start = model
+        .find(&header)
+        .ok_or_else(|| format!("struct {} not found", name))?
+        + header.len();
+    let end = start
+        + model[start..]
+            .find("\n}\n")
+            .ok_or_else(|| format!("end of struct {} not found", name))?
+        + 1;
+
+    let mut fields = vec![];
+    let mut lines = vec![];
+    for line in model[start..end].lines() {
+        lines.push(line.to_string());
+        let trimmed = line.trim();
+        if trimmed.starts_with("///") || trimmed.starts_with("#[") || !trimmed.ends_with(',') {
+            continue;
+        }
+        let declaration = lines
+            .iter()
+            .map(|line| line.trim())
+            .find(|line| line.starts_with("pub "))
+            .ok_or_else(|| format!("unexpected line in struct {}: {}", name, line))?;
+        let name = declaration["pub ".len()..]
+            .split(':')
+            .next()
+            .unwrap_or_default()
+            .trim_start_matches("r#")
+            .to_string();
+        fields.push(Field {
+            name,
+            lines: std::mem::take(&mut lines),
+        });
+    }
+    Ok((fields, start, end))
+}
+
+/// Add the missing keys to the struct, returning the new model and the added keys.
+fn merge(
+    model: &str,
+    name: &str,
+    keys: &BTreeSet<String>,
+) -> Result<(String, Vec<String>), String> {
+    let (mut fields, start, end) = parse_struct(model, name)?;
+    let mut added = vec![];
+    for key in keys {
+        if fields.iter().any(|field| &field.name == key) {
+            continue;
+        }
+        let ident = if KEYWORDS.contains(&key.as_str()) {
+            format!("r#{}", key)
+        } else {
+            key.clone()
+        };
+        fields.push(Field {
+            name: key.clone(),
+            lines: vec![format!("    pub {}: Option<Value>,", ident)],
+        });
+        added.push(key.clone());
+    }
+    fields.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut body = String::new();
+    for field in fields {
+        for line in field.lines {
+            body.push_str(&line);
+            body.push('\n');
+        }
+    }
+    Ok((
+        format!("{}{}{}", &model[..start], body, &model[end..]),
+        added,
+    ))
+}
+
+fn run(common_py: &str) -> Result<(), String> {
+    let source = fs::read_to_string(common_py)
+        .map_err(|err| format!("failed to read {}: {}", common_py, err))?;
+    let doc = docstring(&source).ok_or("docstring of InfoExtractor not found")?;
+    let documented = parse_docstring(doc);
+    if documented.video.is_empty() || documented.format.is_empty() {
+        return Err("no fields found in the docstring of InfoExtractor".into());
+    }
+
+    let model = fs::read_to_string(MODEL_PATH)
+        .map_err(|err| format!("failed to read {}: {}", MODEL_PATH, err))?;
+    let (model, added_video) = merge(&model, "SingleVideo", &documented.video)?;
+    let (model, added_format) = merge(&model, "Format", &documented.format)?;
+    fs::write(MODEL_PATH, model)
+        .map_err(|err| format!("failed to write {}: {}", MODEL_PATH, err))?;
+
+    println!("SingleVideo: added {:?}", added_video);
+    println!("Format: added {:?}", added_format);
+    Ok(())
+}
+
+fn main() {
+    let common_py = match env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: generate_model <path to yt_dlp/extractor/common.py>");
+            process::exit(2);
+        }
+    };
+    if let Err(err) = run(&common_py) {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{docstring, merge, parse_docstring};
+
+    const COMMON_PY: &str = r#"
+class InfoExtractor:
+    """Information Extractor class.
+
+    id:             Video identifier.
+    title:          Video title, unescaped.
+
+    formats:        A list of dictionaries for each format available.
+
+                    Potential fields:
+                    * url        The mandatory URL representing the media
+                    * ext        Will be calculated from URL if missing
+                    * fragments  A list of fragments of a fragmented media.
+                                 * "url" - fragment's URL
+                                 * duration (optional, int or float)
+                    * audio_channels  Number of audio channels
+    type:           A made up key.
+
+    Unless mentioned otherwise, the fields should be Unicode strings.
+
+    _type "playlist" indicates multiple videos.
+    entries:        Not a video field.
+    """
+"#;
+
+    #[test]
+    fn test_parse_docstring() {
+        let documented = parse_docstring(docstring(COMMON_PY).unwrap());
+        assert_eq!(
+            documented.video.into_iter().collect::<Vec<_>>(),
+            ["formats", "id", "title", "type"]
+        );
+        assert_eq!(
+            documented.format.into_iter().collect::<Vec<_>>(),
+            ["audio_channels", "ext", "fragments", "url"]
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let model = "pub struct Format {\n    #[serde(default)]\n    pub ext: Option<String>,\n    pub url: Option<String>,\n}\n";
+        let keys = ["audio_channels", "ext", "type"]
+            .iter()
+            .map(|key| key.to_string())
+            .collect();
+        let (model, added) = merge(model, "Format", &keys).unwrap();
+        assert_eq!(added, ["audio_channels", "type"]);
+        assert_eq!(
+            model,
+            "pub struct Format {\n    pub audio_channels: Option<Value>,\n    #[serde(default)]\n    pub ext: Option<String>,\n    pub r#type: Option<Value>,\n    pub url: Option<String>,\n}\n"
+        );
+    }
+}
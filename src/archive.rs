@@ -0,0 +1,172 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::Error;
+
+/// A download archive stored in SQLite, which stays fast for millions of entries where yt-dlp's
+/// text archive (`--download-archive`) has to be scanned completely.
+///
+/// Entries are identified like in yt-dlp's archive, by the extractor key in lowercase (e.g.
+/// `youtube`) and the video ID.
+///
+/// This is only a store to import from and export to: `download_to` and the other methods of
+/// `YoutubeDl` don't use it, since yt-dlp only reads text archives. To skip archived videos,
+/// export a text archive for yt-dlp with `export_text`, pass it to `resume_download` (or
+/// `--download-archive`) and record what was downloaded with `import_text` afterwards.
+///
+/// ```rust,no_run
+/// # fn example() -> Result<(), youtube_dl::Error> {
+/// use youtube_dl::archive::SqliteArchive;
+/// use youtube_dl::YoutubeDl;
+///
+/// let archive = SqliteArchive::open("archive.sqlite")?;
+/// archive.export_text("archive.txt")?;
+/// YoutubeDl::new("https://www.youtube.com/watch?v=VFbhKZFzbzk")
+///     .resume_download("archive.txt")
+///     .download_to("videos")?;
+/// archive.import_text("archive.txt")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SqliteArchive {
+    connection: Connection,
+}
+
+impl SqliteArchive {
+    /// Open the archive at the path, creating it if it doesn't exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// An archive that is only kept in memory, e.g. for tests.
+    pub fn in_memory() -> Result<Self, Error> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(connection: Connection) -> Result<Self, Error> {
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS archive (
+                extractor TEXT NOT NULL,
+                id TEXT NOT NULL,
+                PRIMARY KEY (extractor, id)
+            ) WITHOUT ROWID;",
+        )?;
+        Ok(SqliteArchive { connection })
+    }
+
+    /// Whether the video is recorded in the archive.
+    pub fn contains(&self, extractor: &str, id: &str) -> Result<bool, Error> {
+        let found = self
+            .connection
+            .prepare_cached("SELECT 1 FROM archive WHERE extractor = ?1 AND id = ?2")?
+            .query_row(params![extractor.to_lowercase(), id], |_| Ok(()))
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    /// Record the video, returning `false` if it was already recorded.
+    pub fn insert(&self, extractor: &str, id: &str) -> Result<bool, Error> {
+        let inserted = self
+            .connection
+            .prepare_cached("INSERT OR IGNORE INTO archive (extractor, id) VALUES (?1, ?2)")?
+            .execute(params![extractor.to_lowercase(), id])?;
+        Ok(inserted > 0)
+    }
+
+    /// Remove the video from the archive, returning `false` if it was not recorded.
+    pub fn remove(&self, extractor: &str, id: &str) -> Result<bool, Error> {
+        let removed = self
+            .connection
+            .prepare_cached("DELETE FROM archive WHERE extractor = ?1 AND id = ?2")?
+            .execute(params![extractor.to_lowercase(), id])?;
+        Ok(removed > 0)
+    }
+
+    /// The number of recorded videos.
+    pub fn len(&self) -> Result<u64, Error> {
+        let count: i64 = self
+            .connection
+            .query_row("SELECT COUNT(*) FROM archive", [], |row| row.get(0))?;
+        Ok(count as u64)
+    }
+
+    /// Whether no videos are recorded.
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Add all entries of a yt-dlp text archive (lines of `<extractor> <id>`), returning how
+    /// many were not recorded yet.
+    pub fn import_text(&self, path: impl AsRef<Path>) -> Result<u64, Error> {
+        let reader = BufReader::new(File::open(path)?);
+        let transaction = self.connection.unchecked_transaction()?;
+        let mut imported = 0;
+        for line in reader.lines() {
+            let line = line?;
+            if let Some((extractor, id)) = line.trim().split_once(' ') {
+                if self.insert(extractor, id.trim())? {
+                    imported += 1;
+                }
+            }
+        }
+        transaction.commit()?;
+        Ok(imported)
+    }
+
+    /// Write all entries as a yt-dlp text archive, e.g. to pass it to `--download-archive`.
+    /// Returns the number of entries written.
+    pub fn export_text(&self, path: impl AsRef<Path>) -> Result<u64, Error> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let mut statement = self
+            .connection
+            .prepare("SELECT extractor, id FROM archive ORDER BY extractor, id")?;
+        let mut rows = statement.query([])?;
+        let mut exported = 0;
+        while let Some(row) = rows.next()? {
+            let extractor: String = row.get(0)?;
+            let id: String = row.get(1)?;
+            writeln!(writer, "{} {}", extractor, id)?;
+            exported += 1;
+        }
+        writer.flush()?;
+        Ok(exported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SqliteArchive;
+
+    #[test]
+    fn test_archive() {
+        let archive = SqliteArchive::in_memory().unwrap();
+        assert!(archive.insert("Youtube", "abc").unwrap());
+        assert!(!archive.insert("youtube", "abc").unwrap());
+        assert!(archive.contains("youtube", "abc").unwrap());
+        assert!(!archive.contains("vimeo", "abc").unwrap());
+        assert!(archive.remove("youtube", "abc").unwrap());
+        assert!(archive.is_empty().unwrap());
+    }
+
+    #[test]
+    fn test_import_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = dir.path().join("archive.txt");
+        std::fs::write(&text, "youtube abc\nvimeo 123\n\nyoutube abc\n").unwrap();
+
+        let archive = SqliteArchive::in_memory().unwrap();
+        assert_eq!(archive.import_text(&text).unwrap(), 2);
+        assert!(archive.contains("vimeo", "123").unwrap());
+
+        let exported = dir.path().join("exported.txt");
+        assert_eq!(archive.export_text(&exported).unwrap(), 2);
+        assert_eq!(
+            std::fs::read_to_string(exported).unwrap(),
+            "vimeo 123\nyoutube abc\n"
+        );
+    }
+}
@@ -0,0 +1,532 @@
+//! Assembly of the command line arguments passed to yt-dlp.
+
+/// Options that can be applied to a `YoutubeDl` with `apply_args`, for option types defined
+/// outside of this crate (e.g. the extractor arguments of a specific site).
+///
+/// ```rust
+/// use youtube_dl::{IntoYtDlpArgs, YoutubeDl};
+///
+/// struct YoutubeExtractorArgs {
+///     player_client: String,
+/// }
+///
+/// impl IntoYtDlpArgs for YoutubeExtractorArgs {
+///     fn into_args(self) -> Vec<String> {
+///         vec![
+///             "--extractor-args".into(),
+///             format!("youtube:player_client={}", self.player_client),
+///         ]
+///     }
+/// }
+///
+/// let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=VFbhKZFzbzk");
+/// builder.apply_args(YoutubeExtractorArgs {
+///     player_client: "web".into(),
+/// });
+/// ```
+pub trait IntoYtDlpArgs {
+    /// The command line arguments for these options, each flag and value as a separate item.
+    fn into_args(self) -> Vec<String>;
+}
+
+/// Options that take exactly one value, where a later value replaces an earlier one.
+const SINGLE_VALUED: &[&str] = &[
+    "-f",
+    "-o",
+    "-P",
+    "-u",
+    "-p",
+    "--socket-timeout",
+    "--http-chunk-size",
+    "--buffer-size",
+    "--cookies",
+    "--cookies-from-browser",
+    "--user-agent",
+    "--referer",
+    "--compat-options",
+    "--playlist-items",
+    "--max-downloads",
+    "--date",
+    "--dateafter",
+    "--datebefore",
+];
+
+/// Options the crate may pass that only exist in yt-dlp, which are left out for youtube-dl.
+const YT_DLP_ONLY: &[&str] = &[
+    "-P",
+    "--allow-unplayable-formats",
+    "--audio-multistreams",
+    "--video-multistreams",
+    "--check-formats",
+    "--check-all-formats",
+    "--no-check-formats",
+    "--compat-options",
+    "--cookies-from-browser",
+    "--download-sections",
+    "--extractor-args",
+    "--force-overwrites",
+    "--ignore-no-formats-error",
+    "--plugin-dirs",
+    "--no-plugin-dirs",
+    "--no-quiet",
+    "--no-simulate",
+    "--print",
+    "--print-to-file",
+    "--progress-template",
+    "--throttled-rate",
+    "--use-postprocessor",
+    "--write-link",
+    "--write-url-link",
+    "--write-webloc-link",
+    "--write-desktop-link",
+];
+
+/// Options whose value is a secret.
+const SECRET_OPTIONS: &[&str] = &[
+    "-p",
+    "--password",
+    "--video-password",
+    "--ap-password",
+    "-2",
+    "--twofactor",
+    "--client-certificate-password",
+    "--cookies",
+];
+
+/// An argument added with `YoutubeDl::extra_arg` or `YoutubeDl::extra_arg_pair`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ExtraArg {
+    /// A single argument, an option or the value of the option before it
+    Single(String),
+    /// An option with its value, which are always kept together
+    Pair(String, String),
+}
+
+/// The extra arguments as they are passed to yt-dlp, e.g. for redacting them.
+pub(crate) fn flatten(extra_args: &[ExtraArg]) -> Vec<&str> {
+    extra_args
+        .iter()
+        .flat_map(|arg| match arg {
+            ExtraArg::Single(arg) => vec![arg.as_str()],
+            ExtraArg::Pair(name, value) => vec![name.as_str(), value.as_str()],
+        })
+        .collect()
+}
+
+/// Replaces secret values in logs and `Debug` output.
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// Replace the values of secret options and the values of `--add-header` with `REDACTED`.
+pub(crate) fn redact<S: AsRef<str>>(args: &[S]) -> Vec<String> {
+    let mut redacted = Vec::with_capacity(args.len());
+    let mut args = args.iter().map(AsRef::as_ref);
+    while let Some(arg) = args.next() {
+        if let Some((name, value)) = arg.split_once('=') {
+            if name.starts_with("--") && SECRET_OPTIONS.contains(&name) {
+                redacted.push(format!("{}={}", name, REDACTED));
+                continue;
+            } else if name == "--add-header" {
+                redacted.push(format!("{}={}", name, redact_header(value)));
+                continue;
+            }
+        }
+        redacted.push(arg.to_string());
+        if SECRET_OPTIONS.contains(&arg) {
+            if args.next().is_some() {
+                redacted.push(REDACTED.to_string());
+            }
+        } else if arg == "--add-header" {
+            redacted.extend(args.next().map(redact_header));
+        }
+    }
+    redacted
+}
+
+fn redact_header(header: &str) -> String {
+    match header.split_once(':') {
+        Some((name, _)) => format!("{}:{}", name, REDACTED),
+        None => REDACTED.to_string(),
+    }
+}
+
+/// Maps long aliases to the short form used by the crate.
+fn canonical(name: &str) -> &str {
+    match name {
+        "--format" => "-f",
+        "--output" => "-o",
+        "--paths" => "-P",
+        "--username" => "-u",
+        "--password" => "-p",
+        "--dump-single-json" => "-J",
+        "--verbose" => "-v",
+        "-x" => "--extract-audio",
+        "-i" => "--ignore-errors",
+        name => name,
+    }
+}
+
+/// The type prefix of `-P` and `-o` values (e.g. `temp:` in `-P temp:/tmp`), which may be given
+/// once per type. Drive letters like `C:` are not types.
+fn value_type<'a>(name: &str, value: &'a str) -> Option<&'a str> {
+    if name != "-P" && name != "-o" {
+        return None;
+    }
+    let (prefix, _) = value.split_once(':')?;
+    let is_type = prefix.len() > 1 && prefix.chars().all(|c| c.is_ascii_lowercase() || c == '_');
+    is_type.then_some(prefix)
+}
+
+#[derive(Clone, Debug)]
+struct Arg<'a> {
+    name: &'a str,
+    value: Option<&'a str>,
+    /// The second value of options like `--print-to-file`
+    second: Option<&'a str>,
+    single: bool,
+}
+
+impl Arg<'_> {
+    fn same_option(&self, name: &str, value: &str) -> bool {
+        self.single
+            && canonical(self.name) == canonical(name)
+            && value_type(canonical(name), self.value.unwrap_or_default())
+                == value_type(canonical(name), value)
+    }
+}
+
+/// The arguments of a yt-dlp invocation. Flags are only added once, options with a single value
+/// keep their first position but take the last value, and positional arguments (the URL) always
+/// come last, so the result only depends on the options and not on how often they are set.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ArgSet<'a> {
+    args: Vec<Arg<'a>>,
+    positional: Vec<&'a str>,
+    legacy: bool,
+}
+
+impl<'a> ArgSet<'a> {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Leave out the options that only exist in yt-dlp (`YT_DLP_ONLY`), for youtube-dl.
+    pub(crate) fn legacy(&mut self) {
+        self.legacy = true;
+    }
+
+    /// Add a flag without a value, unless it is already set.
+    pub(crate) fn flag(&mut self, name: &'a str) {
+        let exists = self
+            .args
+            .iter()
+            .any(|arg| arg.value.is_none() && canonical(arg.name) == canonical(name));
+        if !exists {
+            self.args.push(Arg {
+                name,
+                value: None,
+                second: None,
+                single: false,
+            });
+        }
+    }
+
+    /// Set an option that takes a single value, replacing an earlier value.
+    pub(crate) fn option(&mut self, name: &'a str, value: &'a str) {
+        match self
+            .args
+            .iter_mut()
+            .find(|arg| arg.same_option(name, value))
+        {
+            Some(arg) => arg.value = Some(value),
+            None => self.args.push(Arg {
+                name,
+                value: Some(value),
+                second: None,
+                single: true,
+            }),
+        }
+    }
+
+    /// Add an option that may be given multiple times (e.g. `--plugin-dirs`), unless the same
+    /// value is already set.
+    pub(crate) fn repeated(&mut self, name: &'a str, value: &'a str) {
+        let exists = self
+            .args
+            .iter()
+            .any(|arg| arg.name == name && arg.value == Some(value));
+        if !exists {
+            self.args.push(Arg {
+                name,
+                value: Some(value),
+                second: None,
+                single: false,
+            });
+        }
+    }
+
+    /// Add an option with two values (e.g. `--print-to-file TEMPLATE FILE`), unless the same
+    /// values are already set.
+    pub(crate) fn repeated_pair(&mut self, name: &'a str, first: &'a str, second: &'a str) {
+        let exists = self
+            .args
+            .iter()
+            .any(|arg| arg.name == name && arg.value == Some(first) && arg.second == Some(second));
+        if !exists {
+            self.args.push(Arg {
+                name,
+                value: Some(first),
+                second: Some(second),
+                single: false,
+            });
+        }
+    }
+
+    /// Add a positional argument, which is placed after all options.
+    pub(crate) fn positional(&mut self, value: &'a str) {
+        self.positional.push(value);
+    }
+
+    /// Add user supplied arguments, in order. Options with a single value replace the value set
+    /// by the builder and flags the builder already set are skipped. Everything else is passed
+    /// through unchanged, so options can be repeated and share values.
+    pub(crate) fn extra(&mut self, extra_args: &'a [ExtraArg]) {
+        let builder_args = self.args.len();
+        let mut extra_args = extra_args.iter().peekable();
+        while let Some(arg) = extra_args.next() {
+            let arg = match arg {
+                ExtraArg::Pair(name, value) if SINGLE_VALUED.contains(&canonical(name)) => {
+                    self.option(name, value);
+                    continue;
+                }
+                ExtraArg::Pair(name, value) => {
+                    self.args.push(Arg {
+                        name,
+                        value: Some(value),
+                        second: None,
+                        single: false,
+                    });
+                    continue;
+                }
+                ExtraArg::Single(arg) => arg.as_str(),
+            };
+            if let Some((name, value)) = arg.split_once('=') {
+                if name.starts_with("--") && SINGLE_VALUED.contains(&canonical(name)) {
+                    self.option(name, value);
+                    continue;
+                }
+            }
+            if SINGLE_VALUED.contains(&canonical(arg)) {
+                let value = extra_args.next_if(|next| matches!(next, ExtraArg::Single(_)));
+                if let Some(ExtraArg::Single(value)) = value {
+                    self.option(arg, value);
+                    continue;
+                }
+            }
+            let set_by_builder = self.args[..builder_args]
+                .iter()
+                .any(|set| set.value.is_none() && canonical(set.name) == canonical(arg));
+            if !set_by_builder {
+                self.args.push(Arg {
+                    name: arg,
+                    value: None,
+                    second: None,
+                    single: false,
+                });
+            }
+        }
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<&'a str> {
+        let mut args = vec![];
+        for arg in self.args {
+            let name = arg.name.split_once('=').map_or(arg.name, |(name, _)| name);
+            if self.legacy && YT_DLP_ONLY.contains(&canonical(name)) {
+                log::warn!("youtube-dl doesn't support {}, leaving it out", name);
+                continue;
+            }
+            args.push(arg.name);
+            args.extend(arg.value);
+            args.extend(arg.second);
+        }
+        args.extend(self.positional);
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{redact, ArgSet, ExtraArg};
+
+    fn singles(args: &[&str]) -> Vec<ExtraArg> {
+        args.iter()
+            .map(|arg| ExtraArg::Single(arg.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_dedupe_and_order() {
+        let mut args = ArgSet::new();
+        args.flag("--flat-playlist");
+        args.option("-f", "best");
+        args.option("-P", "out");
+        args.option("-P", "temp:/tmp");
+        args.repeated("--plugin-dirs", "a");
+        args.repeated("--plugin-dirs", "b");
+        args.positional("https://example.com/video");
+        args.flag("--flat-playlist");
+        args.option("-P", "downloads");
+        args.repeated("--plugin-dirs", "a");
+        args.repeated_pair("--print-to-file", "%(id)s", "ids.txt");
+        args.repeated_pair("--print-to-file", "%(id)s", "ids.txt");
+
+        assert_eq!(
+            args.into_vec(),
+            [
+                "--flat-playlist",
+                "-f",
+                "best",
+                "-P",
+                "downloads",
+                "-P",
+                "temp:/tmp",
+                "--plugin-dirs",
+                "a",
+                "--plugin-dirs",
+                "b",
+                "--print-to-file",
+                "%(id)s",
+                "ids.txt",
+                "https://example.com/video",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extra_args_override() {
+        let extra = singles(&[
+            "--format",
+            "bv+ba",
+            "--referer=https://example.com",
+            "-i",
+            "--embed-subs",
+        ]);
+
+        let mut args = ArgSet::new();
+        args.option("-f", "best");
+        args.flag("--ignore-errors");
+        args.extra(&extra);
+
+        assert_eq!(
+            args.into_vec(),
+            [
+                "-f",
+                "bv+ba",
+                "--ignore-errors",
+                "--referer",
+                "https://example.com",
+                "--embed-subs",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extra_args_verbatim() {
+        let verbatim = [
+            "--print",
+            "title",
+            "--print",
+            "id",
+            "--audio-format",
+            "mp3",
+            "--remux-video",
+            "mp3",
+        ];
+        let extra = singles(&verbatim);
+
+        let mut args = ArgSet::new();
+        args.flag("-J");
+        args.extra(&extra);
+        args.positional("https://example.com/video");
+
+        let mut expected = vec!["-J"];
+        expected.extend(verbatim);
+        expected.push("https://example.com/video");
+        assert_eq!(args.into_vec(), expected);
+    }
+
+    #[test]
+    fn test_extra_arg_pairs() {
+        let extra = [
+            ExtraArg::Pair("--extractor-args".into(), "youtube:player_client=tv".into()),
+            ExtraArg::Pair(
+                "--extractor-args".into(),
+                "vimeo:original_format_policy=never".into(),
+            ),
+            ExtraArg::Pair("--format".into(), "bv+ba".into()),
+        ];
+
+        let mut args = ArgSet::new();
+        args.option("-f", "best");
+        args.extra(&extra);
+        args.positional("https://example.com/video");
+        assert_eq!(
+            args.into_vec(),
+            [
+                "-f",
+                "bv+ba",
+                "--extractor-args",
+                "youtube:player_client=tv",
+                "--extractor-args",
+                "vimeo:original_format_policy=never",
+                "https://example.com/video",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_redact() {
+        let args = [
+            "-u",
+            "user",
+            "-p",
+            "hunter2",
+            "--add-header",
+            "Authorization:Bearer abc",
+            "--video-password=letmein",
+            "-J",
+        ];
+        assert_eq!(
+            redact(&args),
+            [
+                "-u",
+                "user",
+                "-p",
+                "<redacted>",
+                "--add-header",
+                "Authorization:<redacted>",
+                "--video-password=<redacted>",
+                "-J",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_drive_letter_is_not_a_type() {
+        let mut args = ArgSet::new();
+        args.option("-P", "C:\\Videos");
+        args.option("-P", "D:\\Videos");
+        assert_eq!(args.into_vec(), ["-P", "D:\\Videos"]);
+    }
+
+    #[test]
+    fn test_legacy_leaves_out_yt_dlp_options() {
+        let mut args = ArgSet::new();
+        args.legacy();
+        args.option("--compat-options", "no-live-chat");
+        args.flag("--check-formats");
+        args.option("--paths", "/tmp");
+        args.option("-f", "best");
+        args.flag("--no-simulate");
+        assert_eq!(args.into_vec(), ["-f", "best"]);
+    }
+}
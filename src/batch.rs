@@ -0,0 +1,324 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+
+use crate::{Error, Playlist, SingleVideo, YoutubeDl, YoutubeDlOutput};
+
+/// How many entries `Playlist::resolve_entries` extracts at the same time.
+const RESOLVE_CONCURRENCY: usize = 4;
+
+/// The error for a single entry of a batch (or playlist), together with the URL it belongs to.
+#[derive(Debug)]
+pub struct EntryError {
+    /// URL of the failed entry (for `YoutubeDl::stream_entries`, the URL of the playlist)
+    pub url: String,
+    /// ID of the failed entry, if known
+    pub id: Option<String>,
+    /// What went wrong
+    pub error: Error,
+    /// The lines yt-dlp printed to stderr for this entry, when streaming with
+    /// `YoutubeDl::stream_entries`
+    pub stderr: Vec<String>,
+}
+
+impl fmt::Display for EntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.url, self.error)
+    }
+}
+
+impl StdError for EntryError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Result of `YoutubeDl::run_batch`: the output or the error for every input URL, in input order.
+#[derive(Debug, Default)]
+pub struct BatchOutput {
+    results: Vec<(String, Result<YoutubeDlOutput, EntryError>)>,
+}
+
+impl BatchOutput {
+    /// The result for the given URL (the first one, if the URL was passed multiple times).
+    pub fn get(&self, url: &str) -> Option<&Result<YoutubeDlOutput, EntryError>> {
+        self.results
+            .iter()
+            .find(|(u, _)| u == url)
+            .map(|(_, result)| result)
+    }
+
+    /// All URLs with their results, in input order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Result<YoutubeDlOutput, EntryError>)> {
+        self.results
+            .iter()
+            .map(|(url, result)| (url.as_str(), result))
+    }
+
+    /// The outputs of all URLs that succeeded.
+    pub fn successes(&self) -> impl Iterator<Item = (&str, &YoutubeDlOutput)> {
+        self.iter()
+            .filter_map(|(url, result)| result.as_ref().ok().map(|output| (url, output)))
+    }
+
+    /// The errors of all URLs that failed.
+    pub fn failures(&self) -> impl Iterator<Item = &EntryError> {
+        self.results
+            .iter()
+            .filter_map(|(_, result)| result.as_ref().err())
+    }
+
+    /// Number of input URLs.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Whether no URLs were passed.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Consume the batch, returning all URLs with their results.
+    pub fn into_results(self) -> Vec<(String, Result<YoutubeDlOutput, EntryError>)> {
+        self.results
+    }
+}
+
+/// Read URLs from a batch file in yt-dlp's format (one URL per line, lines starting
+/// with `#`, `;` or `]` are comments).
+pub fn read_batch_file(path: impl AsRef<Path>) -> Result<Vec<String>, Error> {
+    let content = fs::read_to_string(path)?;
+    let urls = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(['#', ';', ']']))
+        .map(String::from)
+        .collect();
+    Ok(urls)
+}
+
+impl YoutubeDl {
+    /// Run yt-dlp separately for each of the URLs with the options of this builder (the URL
+    /// passed to `new` is ignored), so that one failing URL does not affect the others.
+    pub fn run_batch<I, S>(&self, urls: I) -> BatchOutput
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let results = urls
+            .into_iter()
+            .map(|url| {
+                let url = url.into();
+                let result = self.with_url(&url).run().map_err(|error| EntryError {
+                    url: url.clone(),
+                    id: None,
+                    error,
+                    stderr: vec![],
+                });
+                (url, result)
+            })
+            .collect();
+        BatchOutput { results }
+    }
+
+    /// Run yt-dlp separately for each URL of a batch file (see `read_batch_file` and `run_batch`).
+    pub fn run_batch_file(&self, path: impl AsRef<Path>) -> Result<BatchOutput, Error> {
+        Ok(self.run_batch(read_batch_file(path)?))
+    }
+
+    /// Run yt-dlp asynchronously for each of the URLs, one after another. See `run_batch` for details.
+    #[cfg(feature = "tokio")]
+    pub async fn run_batch_async<I, S>(&self, urls: I) -> BatchOutput
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut results = vec![];
+        for url in urls {
+            let url = url.into();
+            let result = self
+                .with_url(&url)
+                .run_async()
+                .await
+                .map_err(|error| EntryError {
+                    url: url.clone(),
+                    id: None,
+                    error,
+                    stderr: vec![],
+                });
+            results.push((url, result));
+        }
+        BatchOutput { results }
+    }
+}
+
+impl Playlist {
+    /// Extract the full metadata of the first `max` entries that only have a URL (e.g. fetched
+    /// with `flat_playlist`), replacing them in place. The entries are extracted with the options
+    /// of `builder` (its URL is ignored), by up to four yt-dlp processes at a time. Entries that
+    /// fail are kept as they were, and their errors are returned in playlist order.
+    pub fn resolve_entries(&mut self, builder: &YoutubeDl, max: usize) -> Vec<EntryError> {
+        let entries = match &mut self.entries {
+            Some(entries) => entries,
+            None => return vec![],
+        };
+        let flat: Vec<(usize, &mut SingleVideo, String)> = entries
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, entry)| entry.formats.is_none())
+            .filter_map(|(index, entry)| {
+                let url = entry.webpage_url.clone().or_else(|| entry.url.clone())?;
+                Some((index, entry, url))
+            })
+            .take(max)
+            .collect();
+        let workers = flat.len().min(RESOLVE_CONCURRENCY);
+        let jobs = Mutex::new(flat.into_iter());
+        let errors = Mutex::new(vec![]);
+
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let next = jobs.lock().unwrap_or_else(|err| err.into_inner()).next();
+                    let (index, entry, url) = match next {
+                        Some(job) => job,
+                        None => break,
+                    };
+                    match builder.with_url(&url).run() {
+                        Ok(YoutubeDlOutput::SingleVideo(video)) => *entry = *video,
+                        // The entry is a playlist itself (e.g. on a channel page), keep it flat.
+                        Ok(YoutubeDlOutput::Playlist(_)) => {}
+                        Err(error) => {
+                            let error = EntryError {
+                                url,
+                                id: Some(entry.id.clone()),
+                                error,
+                                stderr: vec![],
+                            };
+                            errors
+                                .lock()
+                                .unwrap_or_else(|err| err.into_inner())
+                                .push((index, error));
+                        }
+                    }
+                });
+            }
+        });
+
+        let mut errors = errors.into_inner().unwrap_or_else(|err| err.into_inner());
+        errors.sort_by_key(|(index, _)| *index);
+        errors.into_iter().map(|(_, error)| error).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_batch_file;
+
+    #[test]
+    fn test_read_batch_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("batch.txt");
+        std::fs::write(
+            &path,
+            "# comment\nhttps://example.com/a\n\n; other comment\n  https://example.com/b  \n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_batch_file(&path).unwrap(),
+            ["https://example.com/a", "https://example.com/b"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_batch() {
+        use crate::tests::fake_yt_dlp;
+        use crate::{Error, YoutubeDl};
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = r#"for url; do :; done
+if [ "$url" = "https://example.com/dead" ]; then
+  echo "ERROR: Video unavailable" >&2
+  exit 1
+fi
+echo "{\"id\": \"${url##*/}\"}""#;
+        let path = fake_yt_dlp(dir.path(), script);
+
+        let batch = YoutubeDl::new("")
+            .youtube_dl_path(path)
+            .run_batch(["https://example.com/a", "https://example.com/dead"]);
+
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch.successes().count(), 1);
+        let video = batch
+            .get("https://example.com/a")
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .clone()
+            .into_single_video()
+            .unwrap();
+        assert_eq!(video.id, "a");
+
+        let failure = batch.failures().next().unwrap();
+        assert_eq!(failure.url, "https://example.com/dead");
+        assert!(matches!(failure.error, Error::ExitCode { code: 1, .. }));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_entries() {
+        use crate::tests::fake_yt_dlp;
+        use crate::{Playlist, SingleVideo, YoutubeDl};
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = r#"for url; do :; done
+if [ "$url" = "https://example.com/dead" ]; then
+  echo "ERROR: Video unavailable" >&2
+  exit 1
+fi
+echo "{\"id\": \"${url##*/}\", \"title\": \"full\", \"formats\": []}""#;
+        let path = fake_yt_dlp(dir.path(), script);
+        let entry = |id: &str, formats| SingleVideo {
+            id: id.into(),
+            url: Some(format!("https://example.com/{}", id)),
+            formats,
+            ..Default::default()
+        };
+        let mut playlist = Playlist {
+            entries: Some(vec![
+                entry("a", None),
+                entry("b", Some(vec![])),
+                entry("dead", None),
+                entry("c", None),
+            ]),
+            ..Default::default()
+        };
+        let mut builder = YoutubeDl::new("");
+        builder.youtube_dl_path(path);
+
+        let errors = playlist.resolve_entries(&builder, 2);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].id.as_deref(), Some("dead"));
+        let titles: Vec<_> = playlist
+            .entries
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|entry| entry.title.as_deref())
+            .collect();
+        assert_eq!(titles, [Some("full"), None, None, None]);
+
+        let errors = playlist.resolve_entries(&builder, 10);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            playlist.entries.as_ref().unwrap()[3].title.as_deref(),
+            Some("full")
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Runs yt-dlp through the `youtube_dl` crate and prints the parsed output as JSON, e.g. to
+//! check how the crate's model represents a video.
+//!
+//! ```text
+//! cargo run --features cli --bin ytdl-rs -- --preset audio https://www.youtube.com/watch?v=VFbhKZFzbzk
+//! ```
+
+use std::env;
+use std::io::{self, Write};
+use std::process;
+
+use youtube_dl::{
+    Error, FormatTable, Hooks, ProfileRegistry, Progress, SingleVideo, YoutubeDl, YoutubeDlClient,
+    YoutubeDlOutput,
+};
+
+const USAGE: &str = "usage: ytdl-rs [OPTIONS] <URL>
+
+Options:
+  -f, --format <SELECTOR>   format selector, e.g. `bv*+ba/b`
+  --preset <PRESET>         `best` (best video and audio), `audio` (best audio, extracted)
+                            or `flat` (list playlist entries without extracting them)
+  --flat-playlist           don't extract the entries of playlists
+  --cookies <FILE>          cookies file in Netscape format
+  --yt-dlp <PATH>           path of the yt-dlp executable
+  --strict                  fail if the output contains fields missing from the model
+  --formats                 print the formats as a table instead of JSON
+  --download <DIR>          download to the directory, printing the output with the
+                            downloaded files
+  --progress                show the download progress instead of printing the output
+                            (with --download)
+  -h, --help                print this help";
+
+/// Prints the download progress to stderr.
+struct ProgressLine;
+
+impl Hooks for ProgressLine {
+    fn on_progress(&self, _url: &str, progress: &Progress) {
+        let percent = progress.fraction().map_or("?".to_string(), |fraction| {
+            format!("{:.1}", fraction * 100.0)
+        });
+        let speed = progress.speed.map_or(String::new(), |speed| {
+            format!(" at {:.2} MiB/s", speed / 1024.0 / 1024.0)
+        });
+        let id = progress.video_id.as_deref().unwrap_or("download");
+        eprint!("\r[{}] {}%{}   ", id, percent, speed);
+        if progress.status == "finished" {
+            eprintln!();
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Options {
+    help: bool,
+    url: Option<String>,
+    download: Option<String>,
+    formats: bool,
+    progress: bool,
+}
+
+/// Apply the command line arguments to the builder.
+fn parse_args(
+    builder: &mut YoutubeDl,
+    mut args: impl Iterator<Item = String>,
+) -> Result<Options, String> {
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", name))
+        };
+        match arg.as_str() {
+            "-f" | "--format" => {
+                builder.format(value(&arg)?);
+            }
+            "--preset" => match value(&arg)?.as_str() {
+                "best" => {
+                    builder.format("bv*+ba/b");
+                }
+                "audio" => {
+                    builder.format("ba/b").extract_audio(true);
+                }
+                "flat" => {
+                    builder.flat_playlist(true);
+                }
+                preset => return Err(format!("unknown preset: {}", preset)),
+            },
+            "--flat-playlist" => {
+                builder.flat_playlist(true);
+            }
+            "--cookies" => {
+                builder.cookies(value(&arg)?);
+            }
+            "--yt-dlp" => {
+                builder.youtube_dl_path(value(&arg)?);
+            }
+            "--strict" => {
+                builder.strict_model(true);
+            }
+            "--formats" => options.formats = true,
+            "--download" => options.download = Some(value(&arg)?),
+            "--progress" => options.progress = true,
+            "-h" | "--help" => options.help = true,
+            url if !url.starts_with('-') && options.url.is_none() => {
+                options.url = Some(url.to_string())
+            }
+            other => return Err(format!("unexpected argument: {}\n\n{}", other, USAGE)),
+        }
+    }
+    Ok(options)
+}
+
+/// The videos of the output: the video itself, or the extracted entries of a playlist.
+fn videos(output: &YoutubeDlOutput) -> Vec<&SingleVideo> {
+    match output {
+        YoutubeDlOutput::SingleVideo(video) => vec![video],
+        YoutubeDlOutput::Playlist(playlist) => playlist.entries.iter().flatten().collect(),
+    }
+}
+
+/// Runs yt-dlp once, downloading if requested.
+fn run(builder: &YoutubeDl, options: &Options) -> Result<(), Error> {
+    let output = match (&options.download, options.progress) {
+        (Some(directory), true) => {
+            let report = builder.clone().hooks(ProgressLine).download_to(directory)?;
+            for file in report.files() {
+                eprintln!("downloaded {}", file.display());
+            }
+            return Ok(());
+        }
+        (Some(directory), false) => {
+            let output = builder.run_and_download(directory)?;
+            let downloads = videos(&output)
+                .into_iter()
+                .flat_map(|video| video.requested_downloads.iter().flatten());
+            for path in downloads.filter_map(|download| download.filepath.as_deref()) {
+                eprintln!("downloaded {}", path);
+            }
+            output
+        }
+        (None, _) => builder.run()?,
+    };
+
+    if options.formats {
+        // Like `YoutubeDl::list_formats`, the formats of the first video.
+        let formats = videos(&output)
+            .first()
+            .and_then(|video| video.formats.clone())
+            .unwrap_or_default();
+        println!("{}", FormatTable::from(formats));
+    } else {
+        let mut stdout = io::stdout().lock();
+        serde_json::to_writer_pretty(&mut stdout, &output)?;
+        writeln!(stdout)?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let mut defaults = YoutubeDl::new("");
+    let options = match parse_args(&mut defaults, env::args().skip(1)) {
+        Ok(Options { help: true, .. }) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Ok(Options { url: None, .. }) => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(2);
+        }
+    };
+    let client = YoutubeDlClient::new(defaults, ProfileRegistry::new());
+    let builder = client.builder(options.url.as_deref().unwrap_or_default());
+
+    if let Err(err) = run(&builder, &options) {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_args;
+    use youtube_dl::YoutubeDl;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let mut builder = YoutubeDl::new("");
+        let options = parse_args(
+            &mut builder,
+            args(&[
+                "--preset",
+                "audio",
+                "--download",
+                "out",
+                "https://example.com/v",
+            ]),
+        )
+        .unwrap();
+        assert_eq!(options.url.as_deref(), Some("https://example.com/v"));
+        assert_eq!(options.download.as_deref(), Some("out"));
+        let effective_args = builder.effective_args();
+        assert!(effective_args.contains(&"ba/b".to_string()));
+        assert!(effective_args.contains(&"--extract-audio".to_string()));
+
+        assert!(parse_args(&mut builder, args(&["-h"])).unwrap().help);
+        assert!(parse_args(&mut builder, args(&["--preset", "huge"])).is_err());
+        assert!(parse_args(&mut builder, args(&["--format"])).is_err());
+    }
+}
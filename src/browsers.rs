@@ -0,0 +1,196 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+/// A browser profile found by `list_browser_profiles`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BrowserProfile {
+    /// The browser name as expected by `YoutubeDl::cookies_from_browser` (`firefox`, `chrome`
+    /// or `chromium`)
+    pub browser: String,
+    /// The name of the profile shown by the browser, or the name of its directory
+    pub name: String,
+    /// The profile directory, which can be passed as the profile to `cookies_from_browser`
+    pub path: PathBuf,
+}
+
+#[derive(Clone, Copy)]
+enum Layout {
+    /// A directory with one subdirectory per profile, containing `cookies.sqlite`
+    Firefox,
+    /// A user data directory with `Local State` and profiles like `Default` or `Profile 1`
+    Chromium,
+}
+
+/// Find the Firefox, Chrome and Chromium profiles of the current user, e.g. to let the user pick
+/// the profile to read cookies from.
+///
+/// ```rust,no_run
+/// use youtube_dl::{list_browser_profiles, YoutubeDl};
+///
+/// let profiles = list_browser_profiles();
+/// if let Some(profile) = profiles.first() {
+///     let path = profile.path.to_string_lossy();
+///     YoutubeDl::new("https://www.youtube.com/watch?v=VFbhKZFzbzk").cookies_from_browser(
+///         profile.browser.as_str(),
+///         None,
+///         Some(&path),
+///         None,
+///     );
+/// }
+/// ```
+pub fn list_browser_profiles() -> Vec<BrowserProfile> {
+    find_profiles(&browser_roots())
+}
+
+fn browser_roots() -> Vec<(&'static str, Layout, PathBuf)> {
+    let home = env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from);
+    let mut roots = vec![];
+    if cfg!(target_os = "windows") {
+        if let Some(app_data) = env::var_os("APPDATA").map(PathBuf::from) {
+            roots.push((
+                "firefox",
+                Layout::Firefox,
+                app_data.join(r"Mozilla\Firefox\Profiles"),
+            ));
+        }
+        if let Some(local) = env::var_os("LOCALAPPDATA").map(PathBuf::from) {
+            roots.push((
+                "chrome",
+                Layout::Chromium,
+                local.join(r"Google\Chrome\User Data"),
+            ));
+            roots.push((
+                "chromium",
+                Layout::Chromium,
+                local.join(r"Chromium\User Data"),
+            ));
+        }
+    } else if let Some(home) = home {
+        if cfg!(target_os = "macos") {
+            let support = home.join("Library/Application Support");
+            roots.push(("firefox", Layout::Firefox, support.join("Firefox/Profiles")));
+            roots.push(("chrome", Layout::Chromium, support.join("Google/Chrome")));
+            roots.push(("chromium", Layout::Chromium, support.join("Chromium")));
+        } else {
+            for firefox in [
+                ".mozilla/firefox",
+                "snap/firefox/common/.mozilla/firefox",
+                ".var/app/org.mozilla.firefox/.mozilla/firefox",
+            ] {
+                roots.push(("firefox", Layout::Firefox, home.join(firefox)));
+            }
+            let config = env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| home.join(".config"));
+            roots.push(("chrome", Layout::Chromium, config.join("google-chrome")));
+            roots.push(("chromium", Layout::Chromium, config.join("chromium")));
+        }
+    }
+    roots
+}
+
+fn find_profiles(roots: &[(&str, Layout, PathBuf)]) -> Vec<BrowserProfile> {
+    let mut profiles = vec![];
+    for (browser, layout, root) in roots {
+        let entries = match fs::read_dir(root) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        let names = match layout {
+            Layout::Firefox => Value::Null,
+            Layout::Chromium => profile_names(root),
+        };
+        let mut found: Vec<BrowserProfile> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| has_cookies(*layout, path))
+            .map(|path| {
+                let dir_name = path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned();
+                let name = names[dir_name.as_str()]["name"]
+                    .as_str()
+                    .map_or(dir_name, String::from);
+                BrowserProfile {
+                    browser: browser.to_string(),
+                    name,
+                    path,
+                }
+            })
+            .collect();
+        found.sort_by(|a, b| a.path.cmp(&b.path));
+        profiles.extend(found);
+    }
+    profiles
+}
+
+fn has_cookies(layout: Layout, profile: &Path) -> bool {
+    match layout {
+        Layout::Firefox => profile.join("cookies.sqlite").is_file(),
+        Layout::Chromium => {
+            profile.join("Cookies").is_file() || profile.join("Network/Cookies").is_file()
+        }
+    }
+}
+
+/// The display names of Chromium profiles, from `profile.info_cache` in `Local State`.
+fn profile_names(root: &Path) -> Value {
+    let local_state = fs::read(root.join("Local State")).unwrap_or_default();
+    serde_json::from_slice::<Value>(&local_state)
+        .map(|state| state["profile"]["info_cache"].clone())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{find_profiles, Layout};
+
+    #[test]
+    fn test_find_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let firefox = dir.path().join("firefox");
+        let chrome = dir.path().join("chrome");
+        for (profile, file) in [
+            (firefox.join("abc.default-release"), "cookies.sqlite"),
+            (chrome.join("Default"), "Cookies"),
+            (chrome.join("Profile 1/Network"), "Cookies"),
+        ] {
+            fs::create_dir_all(&profile).unwrap();
+            fs::write(profile.join(file), "").unwrap();
+        }
+        fs::create_dir_all(firefox.join("Crash Reports")).unwrap();
+        fs::write(
+            chrome.join("Local State"),
+            r#"{"profile": {"info_cache": {"Default": {"name": "Work"}}}}"#,
+        )
+        .unwrap();
+
+        let profiles = find_profiles(&[
+            ("firefox", Layout::Firefox, firefox.clone()),
+            ("chrome", Layout::Chromium, chrome.clone()),
+            ("chromium", Layout::Chromium, dir.path().join("missing")),
+        ]);
+        let found: Vec<_> = profiles
+            .iter()
+            .map(|profile| (profile.browser.as_str(), profile.name.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("firefox", "abc.default-release"),
+                ("chrome", "Work"),
+                ("chrome", "Profile 1"),
+            ]
+        );
+        assert_eq!(profiles[1].path, chrome.join("Default"));
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::fixtures::args_hash;
+use crate::YoutubeDl;
+
+#[derive(Debug)]
+struct Entry {
+    stdout: Vec<u8>,
+    inserted: Instant,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    entries: HashMap<u64, Entry>,
+    clock: u64,
+}
+
+/// A cache for the metadata fetched by `YoutubeDl::run`, keyed by the program, the URL and all
+/// options.
+/// It keeps the `capacity` most recently used results in memory, and optionally stores them in
+/// a directory so they survive restarts. Clones share the in-memory entries, so one cache can be
+/// used by many builders (see `YoutubeDl::cache`).
+#[derive(Clone, Debug)]
+pub struct MetadataCache {
+    entries: Arc<Mutex<Entries>>,
+    capacity: usize,
+    ttl: Option<Duration>,
+    directory: Option<PathBuf>,
+}
+
+impl MetadataCache {
+    /// Create an in-memory cache for up to `capacity` results, which never expire.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Arc::default(),
+            capacity,
+            ttl: None,
+            directory: None,
+        }
+    }
+
+    /// Discard results that are older than `ttl`.
+    pub fn ttl(&mut self, ttl: Duration) -> &mut Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Also store the results in this directory (which is created if it doesn't exist), and
+    /// look them up there when they are not in memory.
+    pub fn directory<P: AsRef<Path>>(&mut self, directory: P) -> &mut Self {
+        self.directory = Some(directory.as_ref().to_owned());
+        self
+    }
+
+    /// Remove all results from memory and from the directory. Other files in the directory are
+    /// left alone.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().entries.clear();
+        if let Some(directory) = &self.directory {
+            if let Ok(files) = fs::read_dir(directory) {
+                for file in files.flatten() {
+                    if is_entry_file(&file.file_name().to_string_lossy()) {
+                        let _ = fs::remove_file(file.path());
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_fresh(&self, age: Duration) -> bool {
+        self.ttl.map_or(true, |ttl| age < ttl)
+    }
+
+    fn path(&self, key: u64) -> Option<PathBuf> {
+        self.directory
+            .as_ref()
+            .map(|directory| directory.join(format!("{:016x}.json", key)))
+    }
+
+    pub(crate) fn get(&self, builder: &YoutubeDl, args: &[&str]) -> Option<Vec<u8>> {
+        let key = key(builder, args);
+        {
+            let mut entries = self.entries.lock().unwrap();
+            entries.clock += 1;
+            let clock = entries.clock;
+            if let Some(entry) = entries.entries.get_mut(&key) {
+                if self.is_fresh(entry.inserted.elapsed()) {
+                    entry.last_used = clock;
+                    return Some(entry.stdout.clone());
+                }
+                entries.entries.remove(&key);
+            }
+        }
+
+        let path = self.path(key)?;
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default();
+        if !self.is_fresh(age) {
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+        let stdout = fs::read(&path).ok()?;
+        log::debug!("using cached metadata from {}", path.display());
+        self.insert_memory(key, &stdout);
+        Some(stdout)
+    }
+
+    pub(crate) fn insert(&self, builder: &YoutubeDl, args: &[&str], stdout: &[u8]) {
+        let key = key(builder, args);
+        self.insert_memory(key, stdout);
+        if let Some(path) = self.path(key) {
+            let written = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(&path, stdout));
+            if let Err(err) = written {
+                log::warn!("failed to cache metadata in {}: {}", path.display(), err);
+            }
+        }
+    }
+
+    fn insert_memory(&self, key: u64, stdout: &[u8]) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let last_used = entries.clock;
+        entries.entries.insert(
+            key,
+            Entry {
+                stdout: stdout.to_vec(),
+                inserted: Instant::now(),
+                last_used,
+            },
+        );
+        while entries.entries.len() > self.capacity {
+            let oldest = entries
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key);
+            match oldest {
+                Some(oldest) => entries.entries.remove(&oldest),
+                None => break,
+            };
+        }
+    }
+}
+
+/// The same options give different results with another program (e.g. youtube-dl instead of
+/// yt-dlp, or another version at a different path).
+fn key(builder: &YoutubeDl, args: &[&str]) -> u64 {
+    let mut key: Vec<String> = builder
+        .program()
+        .iter()
+        .map(|part| part.to_string_lossy().into_owned())
+        .collect();
+    key.push(format!("{:?}", builder.flavor));
+    key.extend(args.iter().map(|arg| arg.to_string()));
+    args_hash(&key.iter().map(String::as_str).collect::<Vec<_>>())
+}
+
+/// Whether the file name is one of the `{:016x}.json` files written by the cache.
+fn is_entry_file(name: &str) -> bool {
+    name.strip_suffix(".json")
+        .is_some_and(|stem| stem.len() == 16 && stem.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MetadataCache;
+    use crate::{Flavor, YoutubeDl};
+    use std::time::Duration;
+
+    #[test]
+    fn test_lru_eviction() {
+        let builder = YoutubeDl::new("a");
+        let cache = MetadataCache::new(2);
+        cache.insert(&builder, &["-J", "a"], b"a");
+        cache.insert(&builder, &["-J", "b"], b"b");
+        assert_eq!(cache.get(&builder, &["-J", "a"]).unwrap(), b"a");
+        cache.insert(&builder, &["-J", "c"], b"c");
+
+        assert!(cache.get(&builder, &["-J", "a"]).is_some());
+        assert!(cache.get(&builder, &["-J", "b"]).is_none());
+        assert!(cache.get(&builder, &["-J", "c"]).is_some());
+    }
+
+    #[test]
+    fn test_ttl_and_directory() {
+        let builder = YoutubeDl::new("a");
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = MetadataCache::new(10);
+        cache.directory(dir.path());
+        cache.insert(&builder, &["-J", "a"], b"a");
+
+        // A new cache (e.g. after a restart) finds the result on disk.
+        let mut restarted = MetadataCache::new(10);
+        restarted.directory(dir.path());
+        assert_eq!(restarted.get(&builder, &["-J", "a"]).unwrap(), b"a");
+
+        restarted.ttl(Duration::ZERO);
+        assert!(restarted.get(&builder, &["-J", "a"]).is_none());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_key_includes_program() {
+        let cache = MetadataCache::new(10);
+        let builder = YoutubeDl::new("a");
+        cache.insert(&builder, &["-J", "a"], b"a");
+
+        let mut other = YoutubeDl::new("a");
+        other.flavor(Flavor::YoutubeDl);
+        assert!(cache.get(&other, &["-J", "a"]).is_none());
+        let mut other = YoutubeDl::new("a");
+        other.youtube_dl_path("/opt/yt-dlp");
+        assert!(cache.get(&other, &["-J", "a"]).is_none());
+        assert!(cache.get(&builder, &["-J", "a"]).is_some());
+    }
+
+    #[test]
+    fn test_clear_keeps_other_files() {
+        let builder = YoutubeDl::new("a");
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.json"), "{}").unwrap();
+        let mut cache = MetadataCache::new(10);
+        cache.directory(dir.path());
+        cache.insert(&builder, &["-J", "a"], b"a");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+
+        cache.clear();
+        assert!(cache.get(&builder, &["-J", "a"]).is_none());
+        assert!(dir.path().join("notes.json").exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,298 @@
+//! Helpers for working with chapters.
+
+use std::fmt::Write;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::{Chapter, Error, RunReport, YoutubeDl, YoutubeDlOutput};
+
+/// Output template for chapters extracted with `YoutubeDl::extract_chapter`, unless an output
+/// template is set.
+const CHAPTER_OUTPUT_TEMPLATE: &str = "%(title)s - %(section_title)s [%(id)s].%(ext)s";
+
+impl Chapter {
+    /// Duration of the chapter, if both start and end time are known.
+    pub fn duration(&self) -> Option<Duration> {
+        let duration = self.end_time? - self.start_time?;
+        Duration::try_from_secs_f64(duration).ok()
+    }
+
+    fn contains(&self, timestamp: f64) -> bool {
+        match (self.start_time, self.end_time) {
+            (Some(start), Some(end)) => start <= timestamp && timestamp < end,
+            _ => false,
+        }
+    }
+}
+
+/// Helpers for the chapters of a video (`SingleVideo::chapters`).
+pub trait Chapters {
+    /// The chapter at the given position (in seconds).
+    fn chapter_at(&self, timestamp: f64) -> Option<&Chapter>;
+
+    /// The duration of each chapter, see `Chapter::duration`.
+    fn durations(&self) -> Vec<Option<Duration>>;
+
+    /// Pairs of indices of chapters whose time ranges overlap. Empty if the chapters are valid.
+    fn overlapping(&self) -> Vec<(usize, usize)>;
+
+    /// Chapters in ffmpeg's metadata format (`;FFMETADATA1`), to be passed to ffmpeg with
+    /// `-i chapters.txt -map_metadata 1`. Chapters without start or end time are skipped.
+    fn to_ffmetadata(&self) -> String;
+}
+
+impl Chapters for [Chapter] {
+    fn chapter_at(&self, timestamp: f64) -> Option<&Chapter> {
+        self.iter().find(|chapter| chapter.contains(timestamp))
+    }
+
+    fn durations(&self) -> Vec<Option<Duration>> {
+        self.iter().map(Chapter::duration).collect()
+    }
+
+    fn overlapping(&self) -> Vec<(usize, usize)> {
+        let mut overlapping = vec![];
+        for (i, a) in self.iter().enumerate() {
+            for (j, b) in self.iter().enumerate().skip(i + 1) {
+                if let (Some(a_start), Some(a_end), Some(b_start), Some(b_end)) =
+                    (a.start_time, a.end_time, b.start_time, b.end_time)
+                {
+                    if a_start < b_end && b_start < a_end {
+                        overlapping.push((i, j));
+                    }
+                }
+            }
+        }
+        overlapping
+    }
+
+    fn to_ffmetadata(&self) -> String {
+        let mut metadata = String::from(";FFMETADATA1\n");
+        for chapter in self {
+            let (start, end) = match (chapter.start_time, chapter.end_time) {
+                (Some(start), Some(end)) => (start, end),
+                _ => continue,
+            };
+            metadata.push_str("\n[CHAPTER]\nTIMEBASE=1/1000\n");
+            let _ = writeln!(metadata, "START={}", (start * 1000.0).round() as u64);
+            let _ = writeln!(metadata, "END={}", (end * 1000.0).round() as u64);
+            if let Some(title) = &chapter.title {
+                let _ = writeln!(metadata, "title={}", escape_ffmetadata(title));
+            }
+        }
+        metadata
+    }
+}
+
+/// A chapter of a video, by title or by index, for `YoutubeDl::extract_chapter`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ChapterSelector {
+    /// The chapter with this title. Titles are compared ignoring case if no title matches
+    /// exactly.
+    Title(String),
+    /// The chapter at this index, starting at 0
+    Index(usize),
+}
+
+impl From<&str> for ChapterSelector {
+    fn from(title: &str) -> Self {
+        ChapterSelector::Title(title.to_string())
+    }
+}
+
+impl From<String> for ChapterSelector {
+    fn from(title: String) -> Self {
+        ChapterSelector::Title(title)
+    }
+}
+
+impl From<usize> for ChapterSelector {
+    fn from(index: usize) -> Self {
+        ChapterSelector::Index(index)
+    }
+}
+
+impl ChapterSelector {
+    fn find<'a>(&self, chapters: &'a [Chapter]) -> Option<&'a Chapter> {
+        match self {
+            ChapterSelector::Index(index) => chapters.get(*index),
+            ChapterSelector::Title(title) => chapters
+                .iter()
+                .find(|chapter| chapter.title.as_deref() == Some(title))
+                .or_else(|| {
+                    chapters.iter().find(|chapter| {
+                        chapter
+                            .title
+                            .as_deref()
+                            .is_some_and(|t| t.eq_ignore_ascii_case(title))
+                    })
+                }),
+        }
+    }
+}
+
+impl YoutubeDl {
+    /// Download only one chapter of the video into the folder (with `--download-sections`) and
+    /// return the path of the file, e.g. a single song of a concert. The metadata is fetched
+    /// first to look up the chapter. Fails with `Error::NoSuchChapter` if the video has no such
+    /// chapter.
+    pub fn extract_chapter(
+        &self,
+        chapter: impl Into<ChapterSelector>,
+        folder: impl AsRef<Path>,
+    ) -> Result<PathBuf, Error> {
+        let builder = self.chapter_builder(self.run()?, &chapter.into())?;
+        downloaded_file(builder.download_to(folder)?)
+    }
+
+    /// Download only one chapter of the video asynchronously, see `extract_chapter`.
+    #[cfg(feature = "tokio")]
+    pub async fn extract_chapter_async(
+        &self,
+        chapter: impl Into<ChapterSelector>,
+        folder: impl AsRef<Path>,
+    ) -> Result<PathBuf, Error> {
+        let builder = self.chapter_builder(self.run_async().await?, &chapter.into())?;
+        downloaded_file(builder.download_to_async(folder).await?)
+    }
+
+    fn chapter_builder(
+        &self,
+        output: YoutubeDlOutput,
+        selector: &ChapterSelector,
+    ) -> Result<YoutubeDl, Error> {
+        let video = output.into_single_video().ok_or(Error::NoSuchChapter)?;
+        let chapters = video.chapters.as_deref().unwrap_or_default();
+        let chapter = selector.find(chapters).ok_or(Error::NoSuchChapter)?;
+        let (start, end) = match (chapter.start_time, chapter.end_time) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return Err(Error::NoSuchChapter),
+        };
+
+        let mut builder = self.clone();
+        builder.download_sections = vec![format!("*{}-{}", start, end)];
+        if builder.output_template.is_none() {
+            builder.output_template(CHAPTER_OUTPUT_TEMPLATE);
+        }
+        Ok(builder)
+    }
+}
+
+fn downloaded_file(report: RunReport) -> Result<PathBuf, Error> {
+    report.files().next().map(Path::to_owned).ok_or_else(|| {
+        Error::Io(io::Error::new(
+            io::ErrorKind::NotFound,
+            "yt-dlp reported no downloaded file",
+        ))
+    })
+}
+
+/// Special characters in ffmetadata values have to be escaped with a backslash.
+fn escape_ffmetadata(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChapterSelector, Chapters};
+    use crate::{Chapter, SingleVideo};
+    use std::time::Duration;
+
+    fn chapter(title: &str, start: f64, end: f64) -> Chapter {
+        Chapter {
+            title: Some(title.into()),
+            start_time: Some(start),
+            end_time: Some(end),
+        }
+    }
+
+    #[test]
+    fn test_chapters() {
+        let video = SingleVideo {
+            chapters: Some(vec![
+                chapter("Intro", 0.0, 30.5),
+                chapter("Part 1; A=B", 30.5, 90.0),
+                chapter("Outro", 80.0, 100.0),
+            ]),
+            ..Default::default()
+        };
+        let chapters = video.chapters.as_deref().unwrap();
+
+        assert_eq!(
+            chapters.chapter_at(30.5).unwrap().title.as_deref(),
+            Some("Part 1; A=B")
+        );
+        assert!(chapters.chapter_at(100.0).is_none());
+        assert_eq!(chapters.durations()[0], Some(Duration::from_millis(30500)));
+        assert_eq!(chapters.overlapping(), [(1, 2)]);
+        assert_eq!(
+            chapters[..2].to_ffmetadata(),
+            ";FFMETADATA1\n\
+             \n[CHAPTER]\nTIMEBASE=1/1000\nSTART=0\nEND=30500\ntitle=Intro\n\
+             \n[CHAPTER]\nTIMEBASE=1/1000\nSTART=30500\nEND=90000\ntitle=Part 1\\; A\\=B\n"
+        );
+    }
+
+    #[test]
+    fn test_chapter_selector() {
+        let chapters = [chapter("Intro", 0.0, 30.5), chapter("Song", 30.5, 90.0)];
+        let find = |selector: ChapterSelector| selector.find(&chapters).map(|c| c.start_time);
+        assert_eq!(find("Song".into()), Some(Some(30.5)));
+        assert_eq!(find("song".into()), Some(Some(30.5)));
+        assert_eq!(find(0.into()), Some(Some(0.0)));
+        assert_eq!(find(2.into()), None);
+        assert_eq!(find("Outro".into()), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_chapter() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = r#"case "$*" in
+    *--download-sections*) echo "[download] Destination: /downloads/Video - Song [abc].mp4" ;;
+    *) echo '{"id": "abc", "chapters": [{"title": "Intro", "start_time": 0, "end_time": 30.5},
+        {"title": "Song", "start_time": 30.5, "end_time": 90}]}' ;;
+esac"#;
+        let path = crate::tests::fake_yt_dlp(dir.path(), script);
+        let mut builder = crate::YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.youtube_dl_path(path);
+
+        let file = builder.extract_chapter("Song", "/downloads").unwrap();
+        assert_eq!(file.to_str(), Some("/downloads/Video - Song [abc].mp4"));
+        assert!(matches!(
+            builder.extract_chapter(5, "/downloads"),
+            Err(crate::Error::NoSuchChapter)
+        ));
+    }
+
+    #[test]
+    fn test_chapter_builder_replaces_sections() {
+        let video = SingleVideo {
+            chapters: Some(vec![chapter("Intro", 0.0, 30.5)]),
+            ..Default::default()
+        };
+        let mut builder = crate::YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.download_section("*0-10");
+        let chapter_builder = builder
+            .chapter_builder(
+                crate::YoutubeDlOutput::SingleVideo(Box::new(video)),
+                &"Intro".into(),
+            )
+            .unwrap();
+        let args = chapter_builder.process_args();
+        let sections: Vec<_> = args
+            .windows(2)
+            .filter(|pair| pair[0] == "--download-sections")
+            .map(|pair| pair[1])
+            .collect();
+        assert_eq!(sections, ["*0-30.5"]);
+    }
+}
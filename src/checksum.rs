@@ -0,0 +1,138 @@
+use std::fmt;
+#[cfg(feature = "checksum")]
+use std::fmt::Write as _;
+#[cfg(feature = "checksum")]
+use std::fs::File;
+#[cfg(feature = "checksum")]
+use std::io::{self, Read};
+#[cfg(feature = "checksum")]
+use std::path::Path;
+
+#[cfg(feature = "checksum")]
+use sha2::{Sha256, Sha512};
+
+#[cfg(feature = "checksum")]
+use crate::{DownloadOutcome, Error, RunReport};
+
+/// The hash function used by `YoutubeDl::verify_downloads` (with the `checksum` feature).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HashAlgo {
+    /// SHA-256
+    Sha256,
+    /// SHA-512
+    Sha512,
+}
+
+/// The hash of a downloaded file, see `DownloadOutcome::Verified`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Digest {
+    /// The hash function
+    pub algo: HashAlgo,
+    /// The hash as lowercase hex digits
+    pub hex: String,
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.algo {
+            HashAlgo::Sha256 => "sha256",
+            HashAlgo::Sha512 => "sha512",
+        };
+        write!(f, "{}:{}", name, self.hex)
+    }
+}
+
+#[cfg(feature = "checksum")]
+impl Digest {
+    /// Hash the file at the path.
+    pub fn of_file(algo: HashAlgo, path: impl AsRef<Path>) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        let bytes = match algo {
+            HashAlgo::Sha256 => hash::<Sha256>(file)?,
+            HashAlgo::Sha512 => hash::<Sha512>(file)?,
+        };
+        let mut hex = String::with_capacity(bytes.len() * 2);
+        for byte in bytes {
+            let _ = write!(hex, "{:02x}", byte);
+        }
+        Ok(Digest { algo, hex })
+    }
+}
+
+#[cfg(feature = "checksum")]
+fn hash<D: sha2::Digest>(mut file: File) -> io::Result<Vec<u8>> {
+    let mut hasher = D::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        match file.read(&mut buffer)? {
+            0 => break,
+            read => hasher.update(&buffer[..read]),
+        }
+    }
+    Ok(hasher.finalize().to_vec())
+}
+
+#[cfg(feature = "checksum")]
+impl RunReport {
+    /// Replace each `DownloadOutcome::Downloaded` with `DownloadOutcome::Verified`, and add the
+    /// digest to each `DownloadOutcome::AlreadyExists`. Files that can't be hashed keep their
+    /// outcome and are listed in `verify_failures`.
+    pub(crate) fn verify(&mut self, algo: HashAlgo) {
+        for outcome in &mut self.outcomes {
+            match outcome {
+                DownloadOutcome::Downloaded(path) => match Digest::of_file(algo, &*path) {
+                    Ok(digest) => {
+                        *outcome = DownloadOutcome::Verified(std::mem::take(path), digest)
+                    }
+                    Err(err) => self.verify_failures.push((path.clone(), err.to_string())),
+                },
+                DownloadOutcome::AlreadyExists(path, digest) => match Digest::of_file(algo, &*path)
+                {
+                    Ok(hashed) => *digest = Some(hashed),
+                    Err(err) => self.verify_failures.push((path.clone(), err.to_string())),
+                },
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "checksum"))]
+mod tests {
+    use super::{Digest, HashAlgo};
+    use crate::{DownloadOutcome, RunReport};
+
+    #[test]
+    fn test_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("video.mp4");
+        std::fs::write(&path, "abc").unwrap();
+
+        let missing = dir.path().join("missing.mp4");
+
+        let mut report = RunReport {
+            outcomes: vec![
+                DownloadOutcome::Downloaded(path.clone()),
+                DownloadOutcome::AlreadyExists(path.clone(), None),
+                DownloadOutcome::Downloaded(missing.clone()),
+            ],
+            ..Default::default()
+        };
+        report.verify(HashAlgo::Sha256);
+        let digest = Digest {
+            algo: HashAlgo::Sha256,
+            hex: "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".into(),
+        };
+        assert_eq!(
+            report.outcomes,
+            [
+                DownloadOutcome::Verified(path.clone(), digest.clone()),
+                DownloadOutcome::AlreadyExists(path, Some(digest)),
+                DownloadOutcome::Downloaded(missing.clone()),
+            ]
+        );
+        assert_eq!(report.files().count(), 3);
+        assert_eq!(report.verify_failures.len(), 1);
+        assert_eq!(report.verify_failures[0].0, missing);
+    }
+}
@@ -0,0 +1,241 @@
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+
+use crate::{Error, Playlist, YoutubeDl, YoutubeDlOutput};
+
+/// A part of a playlist fetched by `PlaylistChunks`.
+#[derive(Clone, Debug)]
+pub struct PlaylistChunk {
+    /// The playlist index (starting at 1) of the first entry of this chunk.
+    pub start: u32,
+    /// The playlist index of the last entry that was requested for this chunk. The chunk can
+    /// contain fewer entries, e.g. at the end of the playlist or when errors are ignored.
+    pub end: u32,
+    /// The playlist metadata, with the entries of this chunk.
+    pub playlist: Playlist,
+}
+
+/// Iterator over the chunks of a playlist, created by `YoutubeDl::fetch_playlist_chunked`.
+/// Each chunk is fetched with a separate yt-dlp invocation (using `--playlist-items start:end`),
+/// so a failed chunk can be retried and an interrupted enumeration can be resumed with
+/// `start_at(last_fetched_index() + 1)`.
+///
+/// The iterator ends after the first chunk with fewer entries than requested (entries that
+/// yt-dlp couldn't extract count as well). If the URL is not a playlist, the video is returned as
+/// a single chunk. If `YoutubeDl::playlist_items` is set, only that entry is fetched.
+#[derive(Clone, Debug)]
+pub struct PlaylistChunks {
+    builder: YoutubeDl,
+    chunk_size: u32,
+    next_index: u32,
+    /// The last playlist index to fetch.
+    last_index: u32,
+    last_fetched_index: Option<u32>,
+    finished: bool,
+}
+
+/// The entries of a chunk as yt-dlp printed them, including those it couldn't extract.
+#[derive(Deserialize)]
+struct RawEntries {
+    entries: Option<Vec<IgnoredAny>>,
+}
+
+impl PlaylistChunks {
+    fn new(builder: YoutubeDl, chunk_size: u32) -> Self {
+        assert!(chunk_size > 0, "the chunk size must be at least 1");
+        let item = builder
+            .playlist_items
+            .as_deref()
+            .and_then(|items| items.parse().ok());
+        Self {
+            builder,
+            chunk_size,
+            next_index: item.unwrap_or(1),
+            last_index: item.unwrap_or(u32::MAX),
+            last_fetched_index: None,
+            finished: false,
+        }
+    }
+
+    /// Start (or resume) at the given playlist index, starting at 1.
+    pub fn start_at(mut self, index: u32) -> Self {
+        self.next_index = index.max(1);
+        self.finished = false;
+        self
+    }
+
+    /// The playlist index of the last entry that was fetched successfully.
+    pub fn last_fetched_index(&self) -> Option<u32> {
+        self.last_fetched_index
+    }
+
+    /// The playlist index the next chunk starts at.
+    pub fn next_index(&self) -> u32 {
+        self.next_index
+    }
+
+    /// The builder for the next chunk, or `None` if all chunks were fetched.
+    fn chunk_builder(&mut self) -> Option<(YoutubeDl, u32)> {
+        if self.finished || self.next_index > self.last_index {
+            self.finished = true;
+            return None;
+        }
+        let end = self
+            .next_index
+            .saturating_add(self.chunk_size - 1)
+            .min(self.last_index);
+        let mut builder = self.builder.clone();
+        builder.playlist_items = Some(format!("{}:{}", self.next_index, end));
+        Some((builder, end))
+    }
+
+    fn complete(
+        &mut self,
+        builder: &YoutubeDl,
+        end: u32,
+        stdout: Vec<u8>,
+    ) -> Result<Option<PlaylistChunk>, Error> {
+        let raw: RawEntries = serde_json::from_slice(&stdout)?;
+        let output = builder.process_json_output(stdout)?;
+        let count = match &output {
+            YoutubeDlOutput::Playlist(_) => raw.entries.map_or(0, |entries| entries.len()),
+            YoutubeDlOutput::SingleVideo(_) => 1,
+        };
+        let playlist = match output {
+            YoutubeDlOutput::Playlist(playlist) => *playlist,
+            YoutubeDlOutput::SingleVideo(video) => {
+                self.finished = true;
+                Playlist {
+                    id: Some(video.id.clone()),
+                    title: video.title.clone(),
+                    entries: Some(vec![*video]),
+                    ..Default::default()
+                }
+            }
+        };
+        if count == 0 {
+            self.finished = true;
+            return Ok(None);
+        }
+
+        // yt-dlp prints an entry (or null) for each index, so the playlist ends when it prints
+        // fewer than requested.
+        let start = self.next_index;
+        let requested = end - start + 1;
+        let count = u32::try_from(count).unwrap_or(u32::MAX).min(requested);
+        if count < requested {
+            self.finished = true;
+        }
+        self.last_fetched_index = Some(start + count - 1);
+        self.next_index = end.saturating_add(1);
+        Ok(Some(PlaylistChunk {
+            start,
+            end,
+            playlist,
+        }))
+    }
+
+    /// Fetch the next chunk asynchronously.
+    #[cfg(feature = "tokio")]
+    pub async fn next_async(&mut self) -> Option<Result<PlaylistChunk, Error>> {
+        let (builder, end) = self.chunk_builder()?;
+        let result = builder.run_json_async().await;
+        result
+            .and_then(|stdout| self.complete(&builder, end, stdout))
+            .transpose()
+    }
+}
+
+/// Yields an error when fetching a chunk failed. Calling `next` again retries that chunk.
+impl Iterator for PlaylistChunks {
+    type Item = Result<PlaylistChunk, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (builder, end) = self.chunk_builder()?;
+        builder
+            .run_json()
+            .and_then(|stdout| self.complete(&builder, end, stdout))
+            .transpose()
+    }
+}
+
+impl YoutubeDl {
+    /// Fetch the playlist in chunks of `chunk_size` entries, one yt-dlp invocation per chunk.
+    /// This is more robust than fetching very large playlists (e.g. channels with thousands of
+    /// videos) at once, see `PlaylistChunks`. Setting `flat_playlist` makes each chunk much
+    /// faster to fetch.
+    pub fn fetch_playlist_chunked(&self, chunk_size: u32) -> PlaylistChunks {
+        PlaylistChunks::new(self.clone(), chunk_size)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::tests::fake_yt_dlp;
+    use crate::YoutubeDl;
+
+    #[test]
+    fn test_fetch_playlist_chunked() {
+        let dir = tempfile::tempdir().unwrap();
+        // A playlist with 5 entries, printing the entries of the requested range.
+        let script = r#"while [ "$1" != "--playlist-items" ]; do shift; done
+start=${2%:*}; end=${2#*:}
+entries=""; i=$start
+while [ $i -le $end ] && [ $i -le 5 ]; do
+  entries="$entries{\"id\": \"v$i\"},"; i=$((i + 1))
+done
+echo "{\"_type\": \"playlist\", \"id\": \"pl\", \"entries\": [${entries%,}]}""#;
+        let path = fake_yt_dlp(dir.path(), script);
+
+        let mut builder = YoutubeDl::new("https://example.com/playlist");
+        builder.youtube_dl_path(path);
+        let mut chunks = builder.fetch_playlist_chunked(2);
+
+        let ids: Vec<Vec<String>> = chunks
+            .by_ref()
+            .map(|chunk| {
+                let chunk = chunk.unwrap();
+                let entries = chunk.playlist.entries.unwrap();
+                entries.into_iter().map(|video| video.id).collect()
+            })
+            .collect();
+        assert_eq!(ids, [vec!["v1", "v2"], vec!["v3", "v4"], vec!["v5"]]);
+        assert_eq!(chunks.last_fetched_index(), Some(5));
+
+        let resumed = builder.fetch_playlist_chunked(2).start_at(4);
+        let first = resumed.into_iter().next().unwrap().unwrap();
+        assert_eq!((first.start, first.end), (4, 5));
+        assert_eq!(first.playlist.entries.unwrap()[0].id, "v4");
+
+        let mut single = builder.clone();
+        single.playlist_items(3);
+        let chunks: Vec<_> = single.fetch_playlist_chunked(2).collect();
+        assert_eq!(chunks.len(), 1);
+        let chunk = chunks[0].as_ref().unwrap();
+        assert_eq!((chunk.start, chunk.end), (3, 3));
+    }
+
+    #[test]
+    fn test_chunk_of_unavailable_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        // Entries 3 and 4 are unavailable, the playlist has 6.
+        let script = r#"while [ "$1" != "--playlist-items" ]; do shift; done
+case "$2" in
+  1:2) echo '{"_type": "playlist", "entries": [{"id": "v1"}, {"id": "v2"}]}' ;;
+  3:4) echo '{"_type": "playlist", "entries": [null, null]}' ;;
+  5:6) echo '{"_type": "playlist", "entries": [{"id": "v5"}, {"id": "v6"}]}' ;;
+  *) echo '{"_type": "playlist", "entries": []}' ;;
+esac"#;
+        let path = fake_yt_dlp(dir.path(), script);
+
+        let mut builder = YoutubeDl::new("https://example.com/playlist");
+        builder.youtube_dl_path(path);
+        let mut chunks = builder.fetch_playlist_chunked(2);
+        let counts: Vec<usize> = chunks
+            .by_ref()
+            .map(|chunk| chunk.unwrap().playlist.entries.unwrap().len())
+            .collect();
+        assert_eq!(counts, [2, 0, 2]);
+        assert_eq!(chunks.last_fetched_index(), Some(6));
+    }
+}
@@ -0,0 +1,448 @@
+use std::fmt;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::limiter::site;
+#[cfg(feature = "tokio")]
+use crate::runner::Tokio;
+use crate::runner::{self, Blocking, ProcessRunner};
+use crate::{Error, ErrorKind, MetadataFetcher, RunReport, YoutubeDl, YoutubeDlOutput};
+
+type Profile = Arc<dyn Fn(&mut YoutubeDl) + Send + Sync>;
+
+/// How `YoutubeDlClient` retries failed runs, set per extractor with
+/// `ProfileRegistry::retry_policy`.
+///
+/// Only errors that may go away are retried: timeouts, I/O errors, HTTP status 429 and 5xx, and
+/// yt-dlp errors other than permanent ones like private, removed or age-restricted videos. The
+/// delay before each retry starts at `initial_delay` and is multiplied by `multiplier` up to
+/// `max_delay`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of retries after the first attempt
+    pub retries: u32,
+    /// Delay before the first retry
+    pub initial_delay: Duration,
+    /// Factor by which the delay grows after each retry
+    pub multiplier: f64,
+    /// Maximum delay between retries
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+impl RetryPolicy {
+    /// Don't retry.
+    pub const NONE: Self = RetryPolicy {
+        retries: 0,
+        initial_delay: Duration::ZERO,
+        multiplier: 1.0,
+        max_delay: Duration::ZERO,
+    };
+
+    /// Retry up to `retries` times, waiting 1 second before the first retry and doubling the
+    /// delay up to 1 minute.
+    pub fn new(retries: u32) -> Self {
+        RetryPolicy {
+            retries,
+            initial_delay: Duration::from_secs(1),
+            multiplier: 2.0,
+            max_delay: Duration::from_secs(60),
+        }
+    }
+
+    /// The delay before the retry (starting at 1), or `None` if the error should not be retried.
+    fn delay(&self, retry: u32, error: &Error) -> Option<Duration> {
+        if retry > self.retries || !is_retryable(error) {
+            return None;
+        }
+        let factor = self.multiplier.max(1.0).powi(retry as i32 - 1);
+        Some(
+            self.initial_delay
+                .mul_f64(factor.min(u32::MAX as f64))
+                .min(self.max_delay.max(self.initial_delay)),
+        )
+    }
+}
+
+/// The extractor that reported the error, like `soundcloud` for
+/// `ERROR: [soundcloud] 123: HTTP Error 503`.
+fn failed_extractor(error: &Error) -> Option<&str> {
+    match error {
+        Error::ExitCode { stderr, .. } => stderr
+            .lines()
+            .filter_map(|line| line.strip_prefix("ERROR: ["))
+            .find_map(|line| line.split_once(']'))
+            .map(|(extractor, _)| extractor),
+        _ => None,
+    }
+}
+
+/// Messages of yt-dlp errors that don't go away by retrying.
+const PERMANENT_ERRORS: &[&str] = &[
+    "Private video",
+    "Video unavailable",
+    "This video is unavailable",
+    "has been removed",
+    "Unsupported URL",
+];
+
+fn is_retryable(error: &Error) -> bool {
+    match error {
+        Error::ProcessTimeout | Error::Io(_) => true,
+        Error::ExitCode { stderr, .. } => {
+            let transient = match error.kind() {
+                ErrorKind::HttpStatus(status) => status == 429 || status >= 500,
+                kind => kind == ErrorKind::Other,
+            };
+            transient
+                && !PERMANENT_ERRORS
+                    .iter()
+                    .any(|message| stderr.contains(message))
+        }
+        _ => false,
+    }
+}
+
+/// Presets of options for URLs of specific sites, e.g. cookies for YouTube or a proxy for
+/// region-locked sites. Used by `YoutubeDlClient` to configure the builder for each URL.
+///
+/// A pattern with a dot (like `youtube.com`) matches the domain and its subdomains. A pattern
+/// without a dot (like `youtube` or `ytsearch`) matches a part of the domain or the prefix of
+/// a search URL. `*` matches all URLs. All matching profiles are applied in the order they
+/// were added.
+///
+/// Retry policies are keyed by yt-dlp's extractor name instead, because the failure semantics
+/// depend on the extractor rather than on the URL (e.g. a generic site embedding a SoundCloud
+/// player).
+#[derive(Clone, Default)]
+pub struct ProfileRegistry {
+    profiles: Vec<(String, Profile)>,
+    retry_policies: Vec<(String, RetryPolicy)>,
+}
+
+impl fmt::Debug for ProfileRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let patterns: Vec<&String> = self.profiles.iter().map(|(pattern, _)| pattern).collect();
+        f.debug_struct("ProfileRegistry")
+            .field("profiles", &patterns)
+            .field("retry_policies", &self.retry_policies)
+            .finish()
+    }
+}
+
+impl ProfileRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply `profile` to the builders for URLs matching `pattern`.
+    pub fn add<F>(&mut self, pattern: impl Into<String>, profile: F) -> &mut Self
+    where
+        F: Fn(&mut YoutubeDl) + Send + Sync + 'static,
+    {
+        self.profiles.push((pattern.into(), Arc::new(profile)));
+        self
+    }
+
+    /// Retry runs that failed in the extractor `extractor` according to `policy`, e.g. retry
+    /// `soundcloud` aggressively but never retry an extractor that fails permanently. The name is
+    /// the one in yt-dlp's messages (`ERROR: [soundcloud] ...`), where `youtube` also matches
+    /// `youtube:tab`. `*` matches all failures, including timeouts and failures without an
+    /// extractor. If several policies match, the one added last is used.
+    pub fn retry_policy(&mut self, extractor: impl Into<String>, policy: RetryPolicy) -> &mut Self {
+        self.retry_policies.push((extractor.into(), policy));
+        self
+    }
+
+    /// The retry policy for failures of the extractor (`None` for failures without one),
+    /// `RetryPolicy::NONE` if no policy matches.
+    pub fn retry_policy_for(&self, extractor: Option<&str>) -> RetryPolicy {
+        self.retry_policies
+            .iter()
+            .rev()
+            .find(|(pattern, _)| {
+                pattern == "*"
+                    || extractor.is_some_and(|extractor| {
+                        let family = extractor.split(':').next().unwrap_or_default();
+                        pattern.eq_ignore_ascii_case(extractor)
+                            || pattern.eq_ignore_ascii_case(family)
+                    })
+            })
+            .map_or(RetryPolicy::NONE, |(_, policy)| *policy)
+    }
+
+    /// Apply all profiles matching the builder's URL.
+    pub fn apply(&self, builder: &mut YoutubeDl) {
+        let site = site(&builder.url);
+        for (pattern, profile) in &self.profiles {
+            if matches(pattern, &site) {
+                profile(builder);
+            }
+        }
+    }
+}
+
+fn matches(pattern: &str, site: &str) -> bool {
+    if pattern == "*" {
+        true
+    } else if pattern.contains('.') {
+        site == pattern || site.ends_with(&format!(".{}", pattern))
+    } else {
+        site.starts_with(pattern) || site.split('.').any(|label| label == pattern)
+    }
+}
+
+/// Runs yt-dlp for many URLs with shared default options and per-site profiles.
+///
+/// ```rust
+/// use youtube_dl::{ProfileRegistry, YoutubeDl, YoutubeDlClient};
+///
+/// let mut profiles = ProfileRegistry::new();
+/// profiles.add("youtube.com", |builder| {
+///     builder.cookies("youtube-cookies.txt");
+/// });
+/// let mut defaults = YoutubeDl::new("");
+/// defaults.socket_timeout("15");
+///
+/// let client = YoutubeDlClient::new(defaults, profiles);
+/// let builder = client.builder("https://www.youtube.com/watch?v=VFbhKZFzbzk");
+/// ```
+#[derive(Clone, Debug)]
+pub struct YoutubeDlClient {
+    defaults: YoutubeDl,
+    profiles: ProfileRegistry,
+}
+
+impl YoutubeDlClient {
+    /// Create a client that uses the options of `defaults` (its URL is ignored) and applies the
+    /// matching `profiles` on top of them.
+    pub fn new(defaults: YoutubeDl, profiles: ProfileRegistry) -> Self {
+        Self { defaults, profiles }
+    }
+
+    /// The profiles consulted for each URL.
+    pub fn profiles(&self) -> &ProfileRegistry {
+        &self.profiles
+    }
+
+    /// Create the builder for `url`, with the default options and all matching profiles applied.
+    pub fn builder(&self, url: &str) -> YoutubeDl {
+        let mut builder = self.defaults.with_url(url);
+        self.profiles.apply(&mut builder);
+        builder
+    }
+
+    /// Fetch the metadata for `url` (see `YoutubeDl::run`), retrying according to the retry
+    /// policy of the failed extractor.
+    pub fn run(&self, url: &str) -> Result<YoutubeDlOutput, Error> {
+        let builder = &self.builder(url);
+        runner::block_on(self.with_retries(&Blocking, builder, || async { builder.run() }))
+    }
+
+    /// Download `url` to the folder (see `YoutubeDl::download_to`), retrying according to the
+    /// retry policy of the failed extractor.
+    pub fn download_to(&self, url: &str, folder: impl AsRef<Path>) -> Result<RunReport, Error> {
+        let builder = &self.builder(url);
+        let folder = folder.as_ref();
+        runner::block_on(
+            self.with_retries(&Blocking, builder, || async { builder.download_to(folder) }),
+        )
+    }
+
+    /// Fetch the metadata for `url` asynchronously.
+    #[cfg(feature = "tokio")]
+    pub async fn run_async(&self, url: &str) -> Result<YoutubeDlOutput, Error> {
+        let builder = &self.builder(url);
+        self.with_retries(&Tokio, builder, || builder.run_async())
+            .await
+    }
+
+    /// Download `url` to the folder asynchronously.
+    #[cfg(feature = "tokio")]
+    pub async fn download_to_async(
+        &self,
+        url: &str,
+        folder: impl AsRef<Path>,
+    ) -> Result<RunReport, Error> {
+        let builder = &self.builder(url);
+        let folder = folder.as_ref();
+        self.with_retries(&Tokio, builder, || builder.download_to_async(folder))
+            .await
+    }
+
+    /// Run until `run` succeeds or the retry policy gives up, waiting with the runner in between.
+    async fn with_retries<R, T, F>(
+        &self,
+        runner: &R,
+        builder: &YoutubeDl,
+        run: impl Fn() -> F,
+    ) -> Result<T, Error>
+    where
+        R: ProcessRunner,
+        F: Future<Output = Result<T, Error>>,
+    {
+        let mut retry = 0;
+        loop {
+            match run().await {
+                Err(error) => {
+                    retry += 1;
+                    let policy = self.profiles.retry_policy_for(failed_extractor(&error));
+                    let delay = match before_retry(builder, &policy, retry, &error) {
+                        Some(delay) => delay,
+                        None => return Err(error),
+                    };
+                    runner.sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Decide whether to retry after the error, and report the retry to the builder's hooks.
+fn before_retry(
+    builder: &YoutubeDl,
+    policy: &RetryPolicy,
+    retry: u32,
+    error: &Error,
+) -> Option<Duration> {
+    let delay = policy.delay(retry, error)?;
+    log::info!(
+        "{} failed, retry {} in {:?}: {}",
+        builder.url,
+        retry,
+        delay,
+        error
+    );
+    if let Some(hooks) = &builder.hooks {
+        hooks.0.on_retry(&builder.url, retry, error);
+    }
+    Some(delay)
+}
+
+impl MetadataFetcher for YoutubeDlClient {
+    fn fetch(&self, url: &str) -> Result<YoutubeDlOutput, Error> {
+        self.run(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{failed_extractor, matches, ProfileRegistry, RetryPolicy, YoutubeDlClient};
+    use crate::{Error, YoutubeDl};
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("youtube.com", "youtube.com"));
+        assert!(matches("youtube.com", "m.youtube.com"));
+        assert!(!matches("youtube.com", "notyoutube.com"));
+        assert!(matches("youtube", "youtube.com"));
+        assert!(matches("ytsearch", "ytsearch5"));
+        assert!(!matches("vimeo", "youtube.com"));
+        assert!(matches("*", "example.org"));
+    }
+
+    #[test]
+    fn test_profiles_applied() {
+        let mut profiles = ProfileRegistry::new();
+        profiles
+            .add("*", |builder| {
+                builder.user_agent("crawler");
+            })
+            .add("youtube.com", |builder| {
+                builder.cookies("yt.txt");
+            });
+        let mut defaults = YoutubeDl::new("");
+        defaults.socket_timeout("15");
+        let client = YoutubeDlClient::new(defaults, profiles);
+
+        let youtube = client.builder("https://www.youtube.com/watch?v=abc");
+        let args = youtube.process_args();
+        assert!(args.contains(&"--cookies"));
+        assert!(args.contains(&"crawler"));
+        assert!(args.contains(&"--socket-timeout"));
+        assert_eq!(args.last(), Some(&"https://www.youtube.com/watch?v=abc"));
+
+        let vimeo = client.builder("https://vimeo.com/1");
+        assert!(!vimeo.process_args().contains(&"--cookies"));
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let mut profiles = ProfileRegistry::new();
+        profiles
+            .retry_policy("*", RetryPolicy::new(2))
+            .retry_policy("soundcloud", RetryPolicy::new(10))
+            .retry_policy("youtube", RetryPolicy::NONE);
+        assert_eq!(profiles.retry_policy_for(Some("soundcloud")).retries, 10);
+        assert_eq!(profiles.retry_policy_for(Some("SoundCloud")).retries, 10);
+        assert_eq!(profiles.retry_policy_for(Some("youtube:tab")).retries, 0);
+        assert_eq!(profiles.retry_policy_for(Some("vimeo")).retries, 2);
+        assert_eq!(profiles.retry_policy_for(None).retries, 2);
+        assert_eq!(
+            ProfileRegistry::new().retry_policy_for(Some("vimeo")),
+            RetryPolicy::NONE
+        );
+
+        let policy = RetryPolicy::new(3);
+        let transient = Error::ExitCode {
+            code: 1,
+            stderr: "ERROR: [soundcloud] 123: HTTP Error 503: Service Unavailable".into(),
+        };
+        assert_eq!(policy.delay(1, &transient), Some(Duration::from_secs(1)));
+        assert_eq!(policy.delay(3, &transient), Some(Duration::from_secs(4)));
+        assert_eq!(policy.delay(4, &transient), None);
+        let private = Error::ExitCode {
+            code: 1,
+            stderr: "ERROR: [youtube] abc: Private video. Sign in if you've been granted access"
+                .into(),
+        };
+        assert_eq!(policy.delay(1, &private), None);
+
+        assert_eq!(failed_extractor(&transient), Some("soundcloud"));
+        assert_eq!(failed_extractor(&Error::ProcessTimeout), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_client_retries() {
+        let dir = tempfile::tempdir().unwrap();
+        let attempts = dir.path().join("attempts");
+        // Fails with a transient error on the first attempt.
+        let script = format!(
+            r#"echo x >> {0}
+if [ "$(wc -l < {0})" -lt 2 ]; then echo "ERROR: [generic] video: HTTP Error 503" >&2; exit 1; fi
+echo '{{"id": "abc"}}'"#,
+            attempts.display()
+        );
+        let path = crate::tests::fake_yt_dlp(dir.path(), &script);
+        let mut defaults = YoutubeDl::new("");
+        defaults.youtube_dl_path(path);
+        let mut profiles = ProfileRegistry::new();
+        profiles.retry_policy(
+            "generic",
+            RetryPolicy {
+                initial_delay: Duration::ZERO,
+                ..RetryPolicy::new(1)
+            },
+        );
+        let client = YoutubeDlClient::new(defaults, profiles);
+
+        let output = client.run("https://example.com/video").unwrap();
+        assert_eq!(output.into_single_video().unwrap().id, "abc");
+        assert_eq!(
+            std::fs::read_to_string(&attempts).unwrap().lines().count(),
+            2
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use std::path::{Path, PathBuf};
+
+use crate::{CollisionPolicy, Error, Flavor, YoutubeDl};
+
+/// yt-dlp's default output template.
+const DEFAULT_OUTPUT_TEMPLATE: &str = "%(title)s [%(id)s].%(ext)s";
+
+impl YoutubeDl {
+    /// The arguments to print the paths the entries would be downloaded to.
+    fn filename_args<'a>(&'a self, location: &'a str) -> Vec<&'a str> {
+        let mut args = self.common_args();
+        if self.flavor == Flavor::YoutubeDl {
+            args.option("-o", location);
+            args.flag("--get-filename");
+        } else {
+            args.option("-P", location);
+            args.option("--print", "filename");
+        }
+        match &self.load_info_json {
+            Some(info_file) => args.option("--load-info-json", info_file),
+            None => args.positional(&self.url),
+        }
+        args.into_vec()
+    }
+
+    /// With `CollisionPolicy::RenameWithSuffix`, a copy of the builder whose output template
+    /// doesn't collide with existing files in `folder`, or `None` if the files don't exist yet.
+    /// If the file names can't be predicted, the download runs as usual (and yt-dlp skips the
+    /// existing files).
+    pub(crate) fn avoid_collisions(&self, folder: &Path) -> Result<Option<YoutubeDl>, Error> {
+        if self.collision_policy != Some(CollisionPolicy::RenameWithSuffix) {
+            return Ok(None);
+        }
+        let location = self.download_location(folder);
+        let predicted = self.run_json_with(self.filename_args(&location));
+        Ok(self.renamed_after(folder, predicted))
+    }
+
+    /// Asynchronous version of `avoid_collisions`.
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn avoid_collisions_async(
+        &self,
+        folder: &Path,
+    ) -> Result<Option<YoutubeDl>, Error> {
+        if self.collision_policy != Some(CollisionPolicy::RenameWithSuffix) {
+            return Ok(None);
+        }
+        let location = self.download_location(folder);
+        let predicted = self
+            .run_json_with_async(self.filename_args(&location))
+            .await;
+        Ok(self.renamed_after(folder, predicted))
+    }
+
+    fn renamed_after(&self, folder: &Path, predicted: Result<Vec<u8>, Error>) -> Option<YoutubeDl> {
+        match predicted {
+            Ok(stdout) => self.renamed(folder, &stdout),
+            Err(err) => {
+                log::warn!("failed to predict the file names of {}: {}", self.url, err);
+                None
+            }
+        }
+    }
+
+    /// Add the first suffix ` (1)`, ` (2)`, ... to the output template for which none of the
+    /// predicted files exist. The same suffix is used for all entries of a playlist.
+    fn renamed(&self, folder: &Path, stdout: &[u8]) -> Option<YoutubeDl> {
+        let predicted: Vec<PathBuf> = String::from_utf8_lossy(stdout)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| folder.join(line.trim()))
+            .collect();
+        if !predicted.iter().any(|path| path.exists()) {
+            return None;
+        }
+        let template = match (&self.output_template, self.flavor) {
+            (Some(template), _) => template.as_str(),
+            (None, Flavor::YoutubeDl) => crate::LEGACY_OUTPUT_TEMPLATE,
+            (None, _) => DEFAULT_OUTPUT_TEMPLATE,
+        };
+        let ending = Ending::of(template);
+        let suffix = (1..).map(|n| format!(" ({})", n)).find(|suffix| {
+            !predicted
+                .iter()
+                .any(|path| ending.add_to_path(path, suffix).exists())
+        })?;
+
+        let mut builder = self.clone();
+        builder.output_template(ending.add_to_template(template, &suffix));
+        Some(builder)
+    }
+}
+
+/// The end of the output template that the suffix is inserted before.
+enum Ending<'a> {
+    /// `.%(ext)s`, the extension of the file
+    Extension,
+    /// A literal extension like `.mp4`
+    Literal(&'a str),
+    /// The suffix is added at the end
+    None,
+}
+
+impl<'a> Ending<'a> {
+    fn of(template: &'a str) -> Self {
+        if template.ends_with(".%(ext)s") {
+            return Ending::Extension;
+        }
+        let name = template.rsplit(['/', '\\']).next().unwrap_or(template);
+        match name.rfind('.') {
+            Some(dot) if dot > 0 && !name[dot..].contains('%') => Ending::Literal(&name[dot..]),
+            _ => Ending::None,
+        }
+    }
+
+    fn add_to_template(&self, template: &str, suffix: &str) -> String {
+        let (stem, ending) = match self {
+            Ending::Extension => (template.strip_suffix(".%(ext)s"), ".%(ext)s"),
+            Ending::Literal(ending) => (template.strip_suffix(ending), *ending),
+            Ending::None => (None, ""),
+        };
+        match stem {
+            Some(stem) => format!("{}{}{}", stem, suffix, ending),
+            None => format!("{}{}", template, suffix),
+        }
+    }
+
+    /// The path of a predicted file, downloaded with the suffix added to the template.
+    fn add_to_path(&self, path: &Path, suffix: &str) -> PathBuf {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let name = match self {
+            Ending::Extension => match path.extension() {
+                Some(ext) => {
+                    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                    format!("{}{}.{}", stem, suffix, ext.to_string_lossy())
+                }
+                None => format!("{}{}", name, suffix),
+            },
+            Ending::Literal(ending) => match name.strip_suffix(ending) {
+                Some(stem) => format!("{}{}{}", stem, suffix, ending),
+                None => format!("{}{}", name, suffix),
+            },
+            Ending::None => format!("{}{}", name, suffix),
+        };
+        path.with_file_name(name)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs;
+
+    use crate::tests::fake_yt_dlp;
+    use crate::{CollisionPolicy, YoutubeDl};
+
+    #[test]
+    fn test_rename_with_suffix() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(dir.path(), "echo 'Title [abc].mp4'");
+        let folder = dir.path().join("downloads");
+        fs::create_dir(&folder).unwrap();
+        let mut builder = YoutubeDl::new("https://example.com/video");
+        builder
+            .youtube_dl_path(path)
+            .collision_policy(CollisionPolicy::RenameWithSuffix);
+        assert!(builder.avoid_collisions(&folder).unwrap().is_none());
+
+        fs::write(folder.join("Title [abc].mp4"), "").unwrap();
+        fs::write(folder.join("Title [abc] (1).mp4"), "").unwrap();
+        let renamed = builder.avoid_collisions(&folder).unwrap().unwrap();
+        assert_eq!(
+            renamed.output_template.as_deref(),
+            Some("%(title)s [%(id)s] (2).%(ext)s")
+        );
+    }
+
+    #[test]
+    fn test_rename_without_ext_field() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(dir.path(), "echo 'v1.2 abc.mp4'");
+        fs::write(dir.path().join("v1.2 abc.mp4"), "").unwrap();
+        let mut builder = YoutubeDl::new("https://example.com/video");
+        builder
+            .youtube_dl_path(path)
+            .collision_policy(CollisionPolicy::RenameWithSuffix)
+            .output_template("%(title)s %(id)s.mp4");
+        let renamed = builder.avoid_collisions(dir.path()).unwrap().unwrap();
+        assert_eq!(
+            renamed.output_template.as_deref(),
+            Some("%(title)s %(id)s (1).mp4")
+        );
+
+        let path = fake_yt_dlp(dir.path(), "echo 'v1.2 abc'");
+        fs::write(dir.path().join("v1.2 abc"), "").unwrap();
+        fs::write(dir.path().join("v1.2 abc (1)"), "").unwrap();
+        builder
+            .youtube_dl_path(path)
+            .output_template("%(title)s %(id)s");
+        let renamed = builder.avoid_collisions(dir.path()).unwrap().unwrap();
+        assert_eq!(
+            renamed.output_template.as_deref(),
+            Some("%(title)s %(id)s (2)")
+        );
+    }
+
+    #[test]
+    fn test_prediction_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(dir.path(), "echo 'ERROR: broken' >&2; exit 1");
+        let mut builder = YoutubeDl::new("https://example.com/video");
+        builder
+            .youtube_dl_path(path)
+            .collision_policy(CollisionPolicy::RenameWithSuffix);
+        assert!(builder.avoid_collisions(dir.path()).unwrap().is_none());
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt::Write as _;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+pub use cookie_store::CookieStore;
+use cookie_store::{CookieDomain, CookieExpiration, RawCookie};
+use url::Url;
+
+use crate::{Error, YoutubeDl};
+
+/// Write the cookies of the jar in the Netscape `cookies.txt` format read by yt-dlp's
+/// `--cookies` option. Expired cookies are skipped.
+pub fn to_netscape(jar: &CookieStore) -> String {
+    let mut text = String::from("# Netscape HTTP Cookie File\n");
+    for cookie in jar.iter_unexpired() {
+        let (domain, include_subdomains) = match &cookie.domain {
+            CookieDomain::HostOnly(host) => (host.clone(), false),
+            CookieDomain::Suffix(suffix) => (format!(".{}", suffix), true),
+            CookieDomain::NotPresent | CookieDomain::Empty => continue,
+        };
+        let expires = match &cookie.expires {
+            CookieExpiration::AtUtc(time) => time.unix_timestamp().max(0),
+            CookieExpiration::SessionEnd => 0,
+        };
+        let _ = writeln!(
+            text,
+            "{}{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            if cookie.http_only() == Some(true) {
+                "#HttpOnly_"
+            } else {
+                ""
+            },
+            domain,
+            flag(include_subdomains),
+            cookie.path.as_ref(),
+            flag(cookie.secure() == Some(true)),
+            expires,
+            cookie.name(),
+            cookie.value(),
+        );
+    }
+    text
+}
+
+fn flag(value: bool) -> &'static str {
+    if value {
+        "TRUE"
+    } else {
+        "FALSE"
+    }
+}
+
+/// Read cookies in the Netscape `cookies.txt` format (e.g. exported by yt-dlp with `--cookies`)
+/// into a jar, e.g. to use them with reqwest through `reqwest_cookie_store`. Invalid lines and
+/// expired cookies are skipped.
+pub fn from_netscape(text: &str) -> CookieStore {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let mut jar = CookieStore::default();
+    for line in text.lines() {
+        let (line, http_only) = match line.strip_prefix("#HttpOnly_") {
+            Some(line) => (line, true),
+            None if line.starts_with('#') => continue,
+            None => (line, false),
+        };
+        let fields: Vec<&str> = line.trim_end_matches('\r').split('\t').collect();
+        let [domain, include_subdomains, path, secure, expires, name, value] = fields[..] else {
+            continue;
+        };
+        let expires: i64 = expires.parse().unwrap_or(0);
+        if expires != 0 && expires <= now {
+            continue;
+        }
+
+        let host = domain.trim_start_matches('.');
+        let mut cookie = format!("{}={}; Path={}", name, value, path);
+        if include_subdomains == "TRUE" {
+            let _ = write!(cookie, "; Domain={}", host);
+        }
+        if expires != 0 {
+            let _ = write!(cookie, "; Max-Age={}", expires - now);
+        }
+        if secure == "TRUE" {
+            cookie.push_str("; Secure");
+        }
+        if http_only {
+            cookie.push_str("; HttpOnly");
+        }
+        let url = Url::parse(&format!("https://{}{}", host, path));
+        if let (Ok(cookie), Ok(url)) = (RawCookie::parse(cookie), url) {
+            let _ = jar.insert_raw(&cookie, &url);
+        }
+    }
+    jar
+}
+
+impl YoutubeDl {
+    /// Pass the cookies of the jar to yt-dlp (with `--cookies`), e.g. a session of a site that
+    /// the application logged in to with its own HTTP client. The cookies are written to a
+    /// temporary file, which is removed when the builder and its clones are dropped.
+    pub fn cookies_from_jar(&mut self, jar: &CookieStore) -> Result<&mut Self, Error> {
+        let mut file = tempfile::Builder::new()
+            .prefix("youtube-dl-rs-cookies-")
+            .suffix(".txt")
+            .tempfile()?;
+        file.write_all(to_netscape(jar).as_bytes())?;
+        file.flush()?;
+        let path = file.into_temp_path();
+        self.cookies = Some(path.to_string_lossy().into_owned());
+        self.cookie_file = Some(Arc::new(path));
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_netscape, to_netscape};
+    use crate::YoutubeDl;
+
+    const COOKIES: &str = "# Netscape HTTP Cookie File
+.example.com\tTRUE\t/\tTRUE\t4102444800\tSID\tabc
+#HttpOnly_www.example.com\tFALSE\t/account\tFALSE\t0\tsession\txyz
+.example.com\tTRUE\t/\tFALSE\t1000\texpired\t1
+";
+
+    #[test]
+    fn test_roundtrip() {
+        let jar = from_netscape(COOKIES);
+        assert_eq!(jar.iter_unexpired().count(), 2);
+        let text = to_netscape(&jar);
+        // The expiry time is converted to a `Max-Age` and back, so it may be off by a second.
+        let sid = text
+            .lines()
+            .find(|line| line.ends_with("\tSID\tabc"))
+            .unwrap();
+        assert!(sid.starts_with(".example.com\tTRUE\t/\tTRUE\t410244480"));
+        assert!(
+            text.contains("#HttpOnly_www.example.com\tFALSE\t/account\tFALSE\t0\tsession\txyz\n")
+        );
+        assert!(!text.contains("expired"));
+    }
+
+    #[test]
+    fn test_cookies_from_jar() {
+        let jar = from_netscape(COOKIES);
+        let mut builder = YoutubeDl::new("https://www.example.com/video");
+        builder.cookies_from_jar(&jar).unwrap();
+        let args = builder.effective_args();
+        let path = &args[args.iter().position(|arg| arg == "--cookies").unwrap() + 1];
+        let written = std::fs::read_to_string(path).unwrap();
+        assert!(written.contains("\tSID\tabc\n"));
+
+        drop(builder);
+        assert!(!std::path::Path::new(path).exists());
+    }
+}
@@ -0,0 +1,174 @@
+use std::path::PathBuf;
+
+use crate::{Error, YoutubeDl};
+
+/// Information about the environment of yt-dlp, parsed from the header it prints in verbose
+/// mode. Enable it with `YoutubeDl::diagnostics`, the result is part of the `RunReport`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Diagnostics {
+    /// Version of yt-dlp, e.g. `stable@2024.03.10 from yt-dlp/yt-dlp [615a84447] (pip)`
+    pub version: Option<String>,
+    /// Version of Python, e.g. `3.11.8 (CPython x86_64 64bit) - Linux-6.1.0-x86_64 (...)`
+    pub python_version: Option<String>,
+    /// Version of ffmpeg, if it was found
+    pub ffmpeg_version: Option<String>,
+    /// Name of the extractor used for the URL, e.g. `youtube`
+    pub extractor: Option<String>,
+    /// All `[debug]` lines of the header, without the prefix
+    pub header: Vec<String>,
+}
+
+impl Diagnostics {
+    pub(crate) fn parse(stdout: &str, stderr: &str) -> Self {
+        let mut diagnostics = Diagnostics::default();
+
+        for line in stderr.lines().map(str::trim) {
+            let line = match line.strip_prefix("[debug] ") {
+                Some(line) => line,
+                None => continue,
+            };
+            // The header ends once the extractors are loaded.
+            let end_of_header = line.starts_with("Loaded ") && line.ends_with(" extractors");
+
+            if let Some(version) = line.strip_prefix("yt-dlp version ") {
+                diagnostics.version = Some(version.to_string());
+            } else if let Some(version) = line.strip_prefix("Python ") {
+                diagnostics.python_version = Some(version.to_string());
+            } else if let Some(versions) = line.strip_prefix("exe versions: ") {
+                diagnostics.ffmpeg_version = versions
+                    .split(", ")
+                    .find_map(|exe| exe.strip_prefix("ffmpeg "))
+                    .map(String::from);
+            }
+            diagnostics.header.push(line.to_string());
+
+            if end_of_header {
+                break;
+            }
+        }
+
+        diagnostics.extractor = stdout.lines().chain(stderr.lines()).find_map(|line| {
+            let (extractor, message) = line.trim().strip_prefix('[')?.split_once("] ")?;
+            message
+                .starts_with("Extracting URL: ")
+                .then(|| extractor.to_string())
+        });
+
+        diagnostics
+    }
+}
+
+/// The plugins yt-dlp found, returned by `YoutubeDl::list_plugins`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Plugins {
+    /// Directories that were searched for plugins
+    pub directories: Vec<PathBuf>,
+    /// Names of the extractor plugins (e.g. `SamplePluginIE`)
+    pub extractors: Vec<String>,
+    /// Names of the postprocessor plugins (e.g. `SamplePluginPP`)
+    pub postprocessors: Vec<String>,
+}
+
+impl Plugins {
+    pub(crate) fn parse(stderr: &str) -> Self {
+        let mut plugins = Plugins::default();
+        for line in stderr.lines().map(str::trim) {
+            let line = match line.strip_prefix("[debug] ") {
+                Some(line) => line,
+                None => continue,
+            };
+
+            if let Some(directories) = line.strip_prefix("Plugin directories: ") {
+                // Printed as a Python list: ['/a/b', '/c/d']
+                plugins.directories = directories
+                    .trim_matches(['[', ']'])
+                    .split(", ")
+                    .map(|dir| dir.trim_matches(['\'', '"']))
+                    .filter(|dir| !dir.is_empty())
+                    .map(PathBuf::from)
+                    .collect();
+            } else if let Some(names) = line.strip_prefix("Extractor Plugins: ") {
+                plugins.extractors = parse_plugin_names(names);
+            } else if let Some(names) = line.strip_prefix("Post-Processor Plugins: ") {
+                plugins.postprocessors = parse_plugin_names(names);
+            }
+        }
+        plugins
+    }
+}
+
+/// Parses e.g. `SamplePluginIE (sample), OtherIE`, dropping the module names.
+fn parse_plugin_names(names: &str) -> Vec<String> {
+    names
+        .split(", ")
+        .map(|name| {
+            name.split(" (")
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        })
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+impl YoutubeDl {
+    /// Query the extractor and postprocessor plugins yt-dlp finds with the plugin
+    /// options of this builder (see `plugin_dirs`), by parsing its verbose output.
+    pub fn list_plugins(&self) -> Result<Plugins, Error> {
+        let mut args = self.common_args();
+        args.flag("-v");
+        // Without a URL, yt-dlp prints the verbose header and exits with an error.
+        let result = self.run_process(args.into_vec())?;
+        Ok(Plugins::parse(&String::from_utf8_lossy(&result.stderr)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Diagnostics, Plugins};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_parse_diagnostics() {
+        let stderr = "[debug] Command-line config: ['-v', 'https://www.youtube.com/watch?v=abc']
+[debug] Encodings: locale UTF-8, fs utf-8, pref UTF-8, out utf-8, error utf-8, screen utf-8
+[debug] yt-dlp version stable@2024.03.10 from yt-dlp/yt-dlp [615a84447] (pip)
+[debug] Python 3.11.8 (CPython x86_64 64bit) - Linux-6.7.9-x86_64-with-glibc2.38
+[debug] exe versions: ffmpeg 6.1.1 (setts), ffprobe 6.1.1
+[debug] Optional libraries: Cryptodome-3.20.0, brotli-1.1.0, certifi-2024.02.02
+[debug] Loaded 1803 extractors
+[debug] [youtube] Extracting URL: https://www.youtube.com/watch?v=abc
+";
+        let stdout = "[youtube] Extracting URL: https://www.youtube.com/watch?v=abc\n";
+        let diagnostics = Diagnostics::parse(stdout, stderr);
+
+        assert_eq!(
+            diagnostics.version.as_deref(),
+            Some("stable@2024.03.10 from yt-dlp/yt-dlp [615a84447] (pip)")
+        );
+        assert!(diagnostics.python_version.unwrap().starts_with("3.11.8"));
+        assert_eq!(diagnostics.ffmpeg_version.as_deref(), Some("6.1.1 (setts)"));
+        assert_eq!(diagnostics.extractor.as_deref(), Some("youtube"));
+        assert_eq!(diagnostics.header.len(), 7);
+    }
+
+    #[test]
+    fn test_parse_plugins() {
+        let stderr = "[debug] yt-dlp version stable@2024.03.10 from yt-dlp/yt-dlp [615a84447] (pip)
+[debug] Plugin directories: ['/home/user/.config/yt-dlp/plugins/sample/yt_dlp_plugins']
+[debug] Extractor Plugins: SamplePluginIE (sample), OtherIE
+[debug] Post-Processor Plugins: SamplePluginPP
+Usage: yt-dlp [OPTIONS] URL [URL...]
+";
+        let plugins = Plugins::parse(stderr);
+        assert_eq!(
+            plugins.directories,
+            [PathBuf::from(
+                "/home/user/.config/yt-dlp/plugins/sample/yt_dlp_plugins"
+            )]
+        );
+        assert_eq!(plugins.extractors, ["SamplePluginIE", "OtherIE"]);
+        assert_eq!(plugins.postprocessors, ["SamplePluginPP"]);
+    }
+}
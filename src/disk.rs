@@ -0,0 +1,95 @@
+//! Free disk space checks, see `YoutubeDl::require_free_space`.
+
+use std::io;
+use std::path::Path;
+
+use crate::{Error, YoutubeDlOutput};
+
+/// Available space (for unprivileged users) on the file system containing `path`.
+/// If `path` does not exist yet, its closest existing ancestor is used.
+fn available_space(path: &Path) -> io::Result<u64> {
+    let existing = path
+        .ancestors()
+        .find(|p| p.exists())
+        .unwrap_or_else(|| Path::new("."));
+    fs4::available_space(existing)
+}
+
+/// Expected size of the download, if yt-dlp reported a (approximate) file size for every video.
+pub(crate) fn download_size(output: &YoutubeDlOutput) -> Option<u64> {
+    match output {
+        YoutubeDlOutput::SingleVideo(video) => video.estimated_size(None),
+        YoutubeDlOutput::Playlist(playlist) => playlist.estimated_total_size(),
+    }
+}
+
+pub(crate) fn check_free_space(
+    folder: &Path,
+    output: &YoutubeDlOutput,
+    margin: u64,
+) -> Result<(), Error> {
+    let size = match download_size(output) {
+        Some(size) => size,
+        None => {
+            log::debug!("file size unknown, skipping free space check");
+            return Ok(());
+        }
+    };
+
+    let required = size.saturating_add(margin);
+    let available = available_space(folder)?;
+    if available < required {
+        Err(Error::InsufficientDiskSpace {
+            required,
+            available,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_free_space, download_size};
+    use crate::{Error, Playlist, SingleVideo, YoutubeDlOutput};
+
+    fn video(filesize: Option<i64>, filesize_approx: Option<f64>) -> SingleVideo {
+        SingleVideo {
+            filesize,
+            filesize_approx,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_download_size() {
+        let single = YoutubeDlOutput::SingleVideo(Box::new(video(None, Some(1500.0))));
+        assert_eq!(download_size(&single), Some(1500));
+
+        let playlist = Playlist {
+            entries: Some(vec![
+                video(Some(100), Some(1500.0)),
+                video(None, Some(50.0)),
+            ]),
+            ..Default::default()
+        };
+        let playlist = YoutubeDlOutput::Playlist(Box::new(playlist));
+        assert_eq!(download_size(&playlist), Some(150));
+
+        let unknown = YoutubeDlOutput::SingleVideo(Box::new(video(None, None)));
+        assert_eq!(download_size(&unknown), None);
+    }
+
+    #[test]
+    fn test_check_free_space() {
+        let dir = tempfile::tempdir().unwrap();
+        let small = YoutubeDlOutput::SingleVideo(Box::new(video(Some(1), None)));
+        check_free_space(&dir.path().join("not/yet/created"), &small, 0).unwrap();
+
+        let huge = YoutubeDlOutput::SingleVideo(Box::new(video(Some(i64::MAX), None)));
+        assert!(matches!(
+            check_free_space(dir.path(), &huge, u64::MAX),
+            Err(Error::InsufficientDiskSpace { .. })
+        ));
+    }
+}
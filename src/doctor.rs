@@ -0,0 +1,212 @@
+use std::fmt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::YoutubeDl;
+
+/// The video extracted to check the network, the test video of yt-dlp's YouTube extractor.
+const KNOWN_VIDEO: &str = "https://www.youtube.com/watch?v=BaW_jenozKc";
+
+/// The result of one check of `doctor`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Check {
+    /// The check passed, with details like the version that was found
+    Ok(String),
+    /// The check failed, with a message explaining what is wrong
+    Failed(String),
+}
+
+impl Check {
+    /// Whether the check passed.
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Check::Ok(_))
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Check::Ok(details) => write!(f, "ok: {}", details),
+            Check::Failed(message) => write!(f, "failed: {}", message),
+        }
+    }
+}
+
+/// The result of `doctor`, e.g. to show setup instructions before the first download.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DoctorReport {
+    /// Whether yt-dlp can be started, with its version
+    pub binary: Check,
+    /// Whether ffmpeg can be started, with its version. It is needed to merge formats and for
+    /// postprocessing like extracting audio.
+    pub ffmpeg: Check,
+    /// Whether files can be created in the output directory
+    pub output_dir: Check,
+    /// Whether yt-dlp can extract a well-known YouTube video, i.e. the network (and proxy, if
+    /// set) works and the extractor is not broken by changes on YouTube
+    pub network: Check,
+}
+
+impl DoctorReport {
+    /// Whether all checks passed.
+    pub fn is_ok(&self) -> bool {
+        self.checks().iter().all(|(_, check)| check.is_ok())
+    }
+
+    /// The checks by name (`binary`, `ffmpeg`, `output_dir` and `network`).
+    pub fn checks(&self) -> [(&'static str, &Check); 4] {
+        [
+            ("binary", &self.binary),
+            ("ffmpeg", &self.ffmpeg),
+            ("output_dir", &self.output_dir),
+            ("network", &self.network),
+        ]
+    }
+}
+
+/// Check that everything needed to download to `output_dir` is set up, with the default
+/// settings. See `YoutubeDl::doctor` to check a configured builder (e.g. with a custom path of
+/// yt-dlp, or a proxy).
+///
+/// ```rust,no_run
+/// let report = youtube_dl::doctor("videos");
+/// for (name, check) in report.checks() {
+///     println!("{}: {}", name, check);
+/// }
+/// ```
+pub fn doctor(output_dir: impl AsRef<Path>) -> DoctorReport {
+    YoutubeDl::new(KNOWN_VIDEO).doctor(output_dir)
+}
+
+impl YoutubeDl {
+    /// Check that everything needed to download to `output_dir` with the settings of this
+    /// builder is set up: that yt-dlp and ffmpeg can be started, that the output directory is
+    /// writable and that a well-known video can be extracted. This blocks until yt-dlp finished
+    /// the extraction, which is limited to 30 seconds unless `process_timeout` is set.
+    pub fn doctor(&self, output_dir: impl AsRef<Path>) -> DoctorReport {
+        let binary = self.check_binary();
+        let network = if binary.is_ok() {
+            self.check_network()
+        } else {
+            Check::Failed("yt-dlp could not be started".to_string())
+        };
+        DoctorReport {
+            binary,
+            ffmpeg: self.check_ffmpeg(),
+            output_dir: check_output_dir(output_dir.as_ref()),
+            network,
+        }
+    }
+
+    fn check_binary(&self) -> Check {
+        let output = match self.command(vec!["--version"]).output() {
+            Ok(output) => output,
+            Err(err) => {
+                return Check::Failed(format!(
+                    "{}. Install yt-dlp or set its path with `youtube_dl_path`",
+                    self.spawn_error(err)
+                ))
+            }
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        match stdout.lines().next() {
+            Some(version) if output.status.success() => Check::Ok(version.trim().to_string()),
+            _ => Check::Failed(format!(
+                "`--version` failed with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+        }
+    }
+
+    fn check_ffmpeg(&self) -> Check {
+        let ffmpeg = self.ffmpeg();
+        let output = Command::new(&ffmpeg)
+            .arg("-version")
+            .stdin(Stdio::null())
+            .output();
+        match output {
+            Ok(output) if output.status.success() => {
+                // The first line looks like `ffmpeg version 6.1.1 Copyright (c) ...`.
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let version = stdout
+                    .lines()
+                    .next()
+                    .and_then(|line| line.strip_prefix("ffmpeg version "))
+                    .and_then(|rest| rest.split_whitespace().next())
+                    .unwrap_or("unknown version");
+                Check::Ok(version.to_string())
+            }
+            Ok(output) => Check::Failed(format!("{} -version failed with {}", ffmpeg.display(), output.status)),
+            Err(err) => Check::Failed(format!(
+                "{} could not be started: {}. Install ffmpeg or set its path with `ffmpeg_location`",
+                ffmpeg.display(),
+                err
+            )),
+        }
+    }
+
+    fn check_network(&self) -> Check {
+        let mut builder = self.clone();
+        builder.url = KNOWN_VIDEO.to_string();
+        if builder.process_timeout.is_none() {
+            builder.process_timeout(Duration::from_secs(30));
+        }
+        match builder.run() {
+            Ok(output) => Check::Ok(format!(
+                "extracted {}",
+                output.into_single_video().map(|video| video.id).unwrap_or_default()
+            )),
+            Err(err) => Check::Failed(format!(
+                "could not extract {}: {}. Check the network connection and proxy, or update yt-dlp",
+                KNOWN_VIDEO, err
+            )),
+        }
+    }
+}
+
+fn check_output_dir(dir: &Path) -> Check {
+    match tempfile::Builder::new()
+        .prefix(".youtube-dl-rs-")
+        .tempfile_in(dir)
+    {
+        Ok(_) => Check::Ok(dir.display().to_string()),
+        Err(err) => Check::Failed(format!("cannot create files in {}: {}", dir.display(), err)),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::Check;
+    use crate::tests::fake_yt_dlp;
+    use crate::YoutubeDl;
+
+    #[test]
+    fn test_doctor() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = "case \"$*\" in\n\
+                      *--version*) echo 2024.03.10 ;;\n\
+                      *) echo '{\"id\": \"BaW_jenozKc\"}' ;;\n\
+                      esac";
+        let path = fake_yt_dlp(dir.path(), script);
+
+        let report = YoutubeDl::new("https://example.com/video")
+            .youtube_dl_path(path)
+            .ffmpeg_location(dir.path().join("missing-ffmpeg"))
+            .doctor(dir.path());
+        assert_eq!(report.binary, Check::Ok("2024.03.10".into()));
+        assert!(!report.ffmpeg.is_ok());
+        assert!(report.output_dir.is_ok());
+        assert_eq!(report.network, Check::Ok("extracted BaW_jenozKc".into()));
+        assert!(!report.is_ok());
+
+        let missing = dir.path().join("missing");
+        let report = YoutubeDl::new("https://example.com/video")
+            .youtube_dl_path(&missing)
+            .doctor(&missing);
+        assert!(!report.binary.is_ok());
+        assert!(!report.output_dir.is_ok());
+        assert!(!report.network.is_ok());
+    }
+}
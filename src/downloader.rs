@@ -143,6 +143,7 @@ async fn create_file(path: impl AsRef<Path>) -> tokio::io::Result<File> {
         .read(true)
         .write(true)
         .create(true)
+        .truncate(true)
         .mode(0o744)
         .open(&path)
         .await
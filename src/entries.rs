@@ -0,0 +1,330 @@
+use std::io::{self, Read};
+use std::process::Child;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+use crate::runner::{self, Blocking, ProcessRunner};
+use crate::{fixtures, trace, EntryError, Error, ProcessResult, SingleVideo, YoutubeDl};
+
+#[derive(Debug)]
+enum Line {
+    Stdout(Vec<u8>),
+    Stderr(String),
+    /// The run is over, with an error if it failed as a whole (e.g. `Error::ProcessTimeout`).
+    Finished(Result<(), Error>),
+}
+
+/// Iterator over the entries of a playlist as yt-dlp extracts them, created by
+/// `YoutubeDl::stream_entries`. Each entry is either the parsed video or an `EntryError` with the
+/// stderr lines yt-dlp printed for it since the previous entry (warnings and the error message).
+/// yt-dlp continues with the next entry after an error. If the run fails as a whole (e.g. with
+/// `Error::ProcessTimeout` or `Error::OutputTooLarge`), that is the last error.
+///
+/// Dropping the iterator stops yt-dlp.
+#[derive(Debug)]
+pub struct EntryStream {
+    url: String,
+    lines: mpsc::Receiver<Line>,
+    stderr: Vec<String>,
+    cancelled: Arc<AtomicBool>,
+    finished: bool,
+}
+
+impl EntryStream {
+    fn error(&mut self, id: Option<String>, error: Error) -> EntryError {
+        EntryError {
+            url: self.url.clone(),
+            id,
+            error,
+            stderr: std::mem::take(&mut self.stderr),
+        }
+    }
+}
+
+/// The ID of the entry an error message is about, from messages like
+/// `ERROR: [youtube] abc: Video unavailable`.
+fn entry_id(message: &str) -> Option<String> {
+    let (_, rest) = message.strip_prefix('[')?.split_once("] ")?;
+    let (id, _) = rest.split_once(": ")?;
+    Some(id.to_string())
+}
+
+impl Iterator for EntryStream {
+    type Item = Result<SingleVideo, EntryError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            // The sender is only dropped early if the run panicked.
+            let line = self.lines.recv().unwrap_or(Line::Finished(Ok(())));
+            match line {
+                Line::Stdout(line) => {
+                    if line.iter().all(u8::is_ascii_whitespace) {
+                        continue;
+                    }
+                    return Some(match serde_json::from_slice::<SingleVideo>(&line) {
+                        Ok(video) => {
+                            self.stderr.clear();
+                            Ok(video)
+                        }
+                        Err(err) => Err(self.error(None, err.into())),
+                    });
+                }
+                Line::Stderr(line) => {
+                    let message = line.strip_prefix("ERROR: ").map(str::to_string);
+                    self.stderr.push(line);
+                    if let Some(message) = message {
+                        let id = entry_id(&message);
+                        return Some(Err(self.error(id, Error::EntryFailed { message })));
+                    }
+                }
+                Line::Finished(result) => {
+                    self.finished = true;
+                    if let Err(err) = result {
+                        return Some(Err(self.error(None, err)));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Drop for EntryStream {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Sends each line read through it to the `EntryStream`, while the runner reads the whole output.
+struct ForwardLines<R> {
+    inner: R,
+    line: Vec<u8>,
+    sender: mpsc::Sender<Line>,
+    to_line: fn(Vec<u8>) -> Line,
+}
+
+impl<R> ForwardLines<R> {
+    fn new(inner: R, sender: mpsc::Sender<Line>, to_line: fn(Vec<u8>) -> Line) -> Self {
+        ForwardLines {
+            inner,
+            line: Vec::new(),
+            sender,
+            to_line,
+        }
+    }
+
+    fn send(&mut self) -> io::Result<()> {
+        let line = (self.to_line)(std::mem::take(&mut self.line));
+        self.sender
+            .send(line)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "entry stream dropped"))
+    }
+}
+
+impl<R: Read> Read for ForwardLines<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        let mut rest = &buf[..read];
+        while let Some(end) = rest.iter().position(|&byte| byte == b'\n') {
+            self.line.extend_from_slice(&rest[..end]);
+            self.send()?;
+            rest = &rest[end + 1..];
+        }
+        self.line.extend_from_slice(rest);
+        if read == 0 && !self.line.is_empty() {
+            self.send()?;
+        }
+        Ok(read)
+    }
+}
+
+fn stderr_line(line: Vec<u8>) -> Line {
+    Line::Stderr(String::from_utf8_lossy(&line).trim_end().to_string())
+}
+
+/// Runs the process like `Blocking`, and forwards its output to the `EntryStream` line by line.
+struct Streaming {
+    lines: mpsc::Sender<Line>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Streaming {
+    /// Send the lines of output that were cached or recorded before.
+    fn replay(&self, stdout: &[u8]) {
+        for line in stdout.split(|&byte| byte == b'\n') {
+            let _ = self.lines.send(Line::Stdout(line.to_vec()));
+        }
+    }
+}
+
+impl ProcessRunner for Streaming {
+    type Child = Child;
+
+    async fn sleep(&self, duration: Duration) {
+        Blocking.sleep(duration).await;
+    }
+
+    fn spawn(&self, builder: &YoutubeDl, args: Vec<&str>) -> Result<(Self::Child, u32), Error> {
+        Blocking.spawn(builder, args)
+    }
+
+    async fn wait(
+        &self,
+        builder: &YoutubeDl,
+        mut child: Self::Child,
+    ) -> Result<ProcessResult, Error> {
+        let stdout = child.stdout.take().unwrap();
+        let stdout = ForwardLines::new(stdout, self.lines.clone(), Line::Stdout);
+        let stderr = child.stderr.take().unwrap();
+        let stderr = ForwardLines::new(stderr, self.lines.clone(), stderr_line);
+        runner::wait_blocking(builder, child, stdout, stderr, Some(&self.cancelled))
+    }
+}
+
+impl YoutubeDl {
+    /// Run yt-dlp with `--dump-json` and return the entries of the playlist as they are
+    /// extracted, instead of waiting for the whole playlist (see `EntryStream`). Errors are
+    /// always ignored in this mode, so that one failing entry doesn't stop the others.
+    ///
+    /// The run is otherwise the same as `run`: hooks, `max_stdout_bytes`, `fallback_paths` and
+    /// the `cache` apply, and it can be recorded and replayed. Cached and replayed runs only
+    /// yield the entries, not the errors of the original run.
+    pub fn stream_entries(&self) -> Result<EntryStream, Error> {
+        let (sender, lines) = mpsc::channel();
+        let runner = Streaming {
+            lines: sender,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        };
+        let stream = EntryStream {
+            url: self.url.clone(),
+            lines,
+            stderr: vec![],
+            cancelled: runner.cancelled.clone(),
+            finished: false,
+        };
+
+        let builder = self.clone();
+        std::thread::spawn(move || {
+            let result = builder.stream_with(&runner);
+            let _ = runner.lines.send(Line::Finished(result));
+        });
+        Ok(stream)
+    }
+
+    fn stream_with(&self, runner: &Streaming) -> Result<(), Error> {
+        let mut args = self.common_args();
+        args.flag("--dump-json");
+        args.flag("--ignore-errors");
+        args.positional(&self.url);
+        let args = args.into_vec();
+        trace::args(&args);
+
+        if let Some(stdout) = self.cache.as_ref().and_then(|cache| cache.get(self, &args)) {
+            runner.replay(&stdout);
+            return Ok(());
+        }
+        if let Some(dir) = &self.replay_dir {
+            runner.replay(&fixtures::load(dir, &args)?);
+            return Ok(());
+        }
+
+        // A failed entry makes yt-dlp exit with an error, the run is still complete.
+        let result = runner::block_on(self.run_with(runner, args.clone()))?;
+        if let Some(dir) = &self.record_dir {
+            fixtures::store(dir, &args, &result.stdout)?;
+        }
+        if let Some(cache) = &self.cache {
+            cache.insert(self, &args, &result.stdout);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::entry_id;
+
+    #[test]
+    fn test_entry_id() {
+        assert_eq!(
+            entry_id("[youtube] abc: Video unavailable").as_deref(),
+            Some("abc")
+        );
+        assert_eq!(entry_id("Unable to download webpage"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stream_entries() {
+        use crate::tests::fake_yt_dlp;
+        use crate::{Error, YoutubeDl};
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = r#"echo '{"id": "a"}'
+sleep 0.1
+echo "WARNING: [youtube] b: Falling back to another client" >&2
+echo "ERROR: [youtube] b: Video unavailable" >&2
+sleep 0.1
+echo '{"id": "c"}'
+exit 1"#;
+        let path = fake_yt_dlp(dir.path(), script);
+
+        let mut builder = YoutubeDl::new("https://example.com/playlist");
+        builder.youtube_dl_path(path);
+        let entries: Vec<_> = builder.stream_entries().unwrap().collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].as_ref().unwrap().id, "a");
+        assert_eq!(entries[2].as_ref().unwrap().id, "c");
+
+        let error = entries[1].as_ref().unwrap_err();
+        assert_eq!(error.id.as_deref(), Some("b"));
+        assert!(matches!(error.error, Error::EntryFailed { .. }));
+        assert_eq!(
+            error.stderr,
+            [
+                "WARNING: [youtube] b: Falling back to another client",
+                "ERROR: [youtube] b: Video unavailable",
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stream_entries_like_run() {
+        use crate::tests::fake_yt_dlp;
+        use crate::{Error, YoutubeDl};
+        use std::time::Duration;
+
+        let dir = tempfile::tempdir().unwrap();
+        let fixtures = dir.path().join("fixtures");
+        let path = fake_yt_dlp(dir.path(), "echo '{\"id\": \"a\"}'\necho '{\"id\": \"b\"}'");
+        let mut builder = YoutubeDl::new("https://example.com/playlist");
+        builder.youtube_dl_path(&path).record_to(&fixtures);
+        assert_eq!(builder.stream_entries().unwrap().count(), 2);
+
+        let mut replay = YoutubeDl::new("https://example.com/playlist");
+        replay
+            .youtube_dl_path("/nonexistent")
+            .replay_from(&fixtures);
+        let ids: Vec<_> = replay
+            .stream_entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().id)
+            .collect();
+        assert_eq!(ids, ["a", "b"]);
+
+        let path = fake_yt_dlp(dir.path(), "echo '{\"id\": \"a\"}'\nexec sleep 30");
+        let entries: Vec<_> = YoutubeDl::new("https://example.com/playlist")
+            .youtube_dl_path(path)
+            .process_timeout(Duration::from_millis(300))
+            .stream_entries()
+            .unwrap()
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(
+            entries[1].as_ref().unwrap_err().error,
+            Error::ProcessTimeout
+        ));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::Error;
+
+/// The cause of an `Error`, recognized from yt-dlp's error message where possible, see
+/// `Error::kind`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The video is age-restricted and can only be downloaded when signed in, see
+    /// `YoutubeDl::use_age_gate_bypass`.
+    AgeRestricted,
+    /// The video is an upcoming live stream or premiere (`Error::NotYetAvailable`).
+    NotYetAvailable,
+    /// A request failed with this HTTP status (`HTTP Error 429: Too Many Requests`), e.g. 404
+    /// for a permanent failure or 429 when rate limited.
+    HttpStatus(u16),
+    /// Any other error
+    Other,
+}
+
+impl Error {
+    /// The cause of the error, for deciding how to handle it without parsing stderr.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::NotYetAvailable { .. } => ErrorKind::NotYetAvailable,
+            Error::ExitCode { stderr, .. } => classify(stderr),
+            Error::EntryFailed { message } => classify(message),
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+fn classify(message: &str) -> ErrorKind {
+    const AGE_RESTRICTED: &[&str] = &[
+        "Sign in to confirm your age",
+        "This video may be inappropriate for some users",
+        "age-restricted",
+    ];
+    if AGE_RESTRICTED
+        .iter()
+        .any(|pattern| message.contains(pattern))
+    {
+        ErrorKind::AgeRestricted
+    } else if let Some(status) = http_status(message) {
+        ErrorKind::HttpStatus(status)
+    } else {
+        ErrorKind::Other
+    }
+}
+
+/// The status of the last `HTTP Error <status>` in the message.
+fn http_status(message: &str) -> Option<u16> {
+    message
+        .rmatch_indices("HTTP Error ")
+        .find_map(|(index, prefix)| {
+            let rest = &message[index + prefix.len()..];
+            let digits = rest.get(..3)?;
+            let after = rest[3..].chars().next();
+            let is_status = digits.bytes().all(|b| b.is_ascii_digit())
+                && after.map_or(true, |c| !c.is_ascii_digit());
+            is_status.then(|| digits.parse().ok()).flatten()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ErrorKind;
+    use crate::Error;
+
+    #[test]
+    fn test_kind() {
+        let err = Error::ExitCode {
+            code: 1,
+            stderr: "ERROR: [youtube] abc: Sign in to confirm your age. This video may be \
+                inappropriate for some users. Use --cookies-from-browser or --cookies"
+                .into(),
+        };
+        assert_eq!(err.kind(), ErrorKind::AgeRestricted);
+
+        let err = Error::ExitCode {
+            code: 1,
+            stderr: "ERROR: [youtube] abc: Video unavailable".into(),
+        };
+        assert_eq!(err.kind(), ErrorKind::Other);
+
+        let err = Error::ExitCode {
+            code: 1,
+            stderr: "WARNING: [youtube] HTTP Error 403: Forbidden\n\
+                ERROR: unable to download video data: HTTP Error 429: Too Many Requests"
+                .into(),
+        };
+        assert_eq!(err.kind(), ErrorKind::HttpStatus(429));
+        let err = Error::EntryFailed {
+            message: "[generic] Unable to download webpage: HTTP Error 404: Not Found".into(),
+        };
+        assert_eq!(err.kind(), ErrorKind::HttpStatus(404));
+        let err = Error::EntryFailed {
+            message: "500 videos could not be found".into(),
+        };
+        assert_eq!(err.kind(), ErrorKind::Other);
+        assert_eq!(
+            Error::NotYetAvailable { starts_at: None }.kind(),
+            ErrorKind::NotYetAvailable
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::hooks::HooksHandle;
+use crate::{Error, Hooks, Progress, RunReport, YoutubeDl};
+
+/// Events emitted while downloading with `YoutubeDl::run_events`.
+#[derive(Clone, Debug)]
+pub enum RunEvent {
+    /// The yt-dlp process was started.
+    Spawned {
+        /// Process ID of yt-dlp
+        pid: u32,
+    },
+    /// yt-dlp wrote a line to stderr (usually a warning or an error).
+    StderrLine(String),
+    /// Download progress update.
+    Progress(Progress),
+    /// A postprocessor (e.g. `Merger` or `ExtractAudio`) is running.
+    PostProcessing {
+        /// Name of the postprocessor
+        postprocessor: String,
+        /// Message printed by the postprocessor
+        message: String,
+    },
+    /// The yt-dlp process exited.
+    Finished {
+        /// Exit status of yt-dlp
+        status: ExitStatus,
+    },
+}
+
+/// Forwards all hook calls as events, and to the hooks registered on the builder (if any).
+struct EventHooks {
+    sender: Sender<RunEvent>,
+    inner: Option<HooksHandle>,
+}
+
+impl EventHooks {
+    fn send(&self, event: RunEvent) {
+        // The receiver might have been dropped, which is fine.
+        let _ = self.sender.send(event);
+    }
+}
+
+impl Hooks for EventHooks {
+    fn on_spawn(&self, url: &str, pid: u32) {
+        if let Some(inner) = &self.inner {
+            inner.0.on_spawn(url, pid);
+        }
+        self.send(RunEvent::Spawned { pid });
+    }
+
+    fn on_complete(&self, url: &str, exit: ExitStatus, duration: Duration, stdout_len: usize) {
+        if let Some(inner) = &self.inner {
+            inner.0.on_complete(url, exit, duration, stdout_len);
+        }
+        self.send(RunEvent::Finished { status: exit });
+    }
+
+    fn on_retry(&self, url: &str, attempt: u32, error: &Error) {
+        if let Some(inner) = &self.inner {
+            inner.0.on_retry(url, attempt, error);
+        }
+    }
+
+    fn on_progress(&self, url: &str, progress: &Progress) {
+        if let Some(inner) = &self.inner {
+            inner.0.on_progress(url, progress);
+        }
+        self.send(RunEvent::Progress(progress.clone()));
+    }
+
+    fn on_postprocess(&self, url: &str, postprocessor: &str, message: &str) {
+        if let Some(inner) = &self.inner {
+            inner.0.on_postprocess(url, postprocessor, message);
+        }
+        self.send(RunEvent::PostProcessing {
+            postprocessor: postprocessor.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    fn on_stderr_line(&self, url: &str, line: &str) {
+        if let Some(inner) = &self.inner {
+            inner.0.on_stderr_line(url, line);
+        }
+        self.send(RunEvent::StderrLine(line.to_string()));
+    }
+}
+
+impl YoutubeDl {
+    /// Download to the specified destination folder on a background thread, and receive
+    /// a stream of `RunEvent`s while it runs (e.g. to render progress in a GUI).
+    /// The channel is closed once the download is finished; join the handle to get its result.
+    pub fn run_events(
+        &self,
+        folder: impl AsRef<Path>,
+    ) -> (JoinHandle<Result<RunReport, Error>>, Receiver<RunEvent>) {
+        let (sender, receiver) = mpsc::channel();
+        let mut builder = self.clone();
+        let inner = builder.hooks.take();
+        builder.hooks(EventHooks { sender, inner });
+        let folder: PathBuf = folder.as_ref().to_owned();
+
+        let handle = thread::spawn(move || builder.download_to(folder));
+        (handle, receiver)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::RunEvent;
+    use crate::tests::fake_yt_dlp;
+    use crate::YoutubeDl;
+
+    #[test]
+    fn test_run_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = "echo 'WARNING: something odd' >&2\n\
+                      echo '[youtube_dl_rs:progress] downloading 5 10 NA 1.0 5 abc'\n\
+                      echo '[ExtractAudio] Destination: abc.mp3'";
+        let path = fake_yt_dlp(dir.path(), script);
+
+        let (handle, events) = YoutubeDl::new("https://example.com/video")
+            .youtube_dl_path(path)
+            .run_events(dir.path());
+        let events: Vec<_> = events.into_iter().collect();
+        handle.join().unwrap().unwrap();
+
+        assert!(matches!(events.first(), Some(RunEvent::Spawned { .. })));
+        assert!(matches!(events.last(), Some(RunEvent::Finished { status }) if status.success()));
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, RunEvent::StderrLine(line) if line == "WARNING: something odd")));
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, RunEvent::Progress(p) if p.downloaded_bytes == Some(5))));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            RunEvent::PostProcessing { postprocessor, .. } if postprocessor == "ExtractAudio"
+        )));
+    }
+}
@@ -0,0 +1,79 @@
+//! Record/replay of yt-dlp's JSON output, see `YoutubeDl::record_to` and `YoutubeDl::replay_from`.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::Error;
+
+// FNV-1a, because the fixture names have to stay stable across Rust versions
+// (which `DefaultHasher` does not guarantee).
+pub(crate) fn args_hash(args: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for arg in args {
+        for byte in arg.bytes().chain(std::iter::once(0)) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+fn fixture_path(dir: &Path, args: &[&str]) -> PathBuf {
+    dir.join(format!("{:016x}.json", args_hash(args)))
+}
+
+pub(crate) fn load(dir: &Path, args: &[&str]) -> Result<Vec<u8>, Error> {
+    let path = fixture_path(dir, args);
+    log::debug!("replaying fixture {}", path.display());
+    fs::read(&path).map_err(|err| {
+        Error::Io(io::Error::new(
+            err.kind(),
+            format!("failed to read fixture {}: {}", path.display(), err),
+        ))
+    })
+}
+
+pub(crate) fn store(dir: &Path, args: &[&str], stdout: &[u8]) -> Result<(), Error> {
+    fs::create_dir_all(dir)?;
+    let path = fixture_path(dir, args);
+    log::debug!("recording fixture {}", path.display());
+    fs::write(path, stdout)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{args_hash, load, store};
+    use crate::YoutubeDl;
+
+    #[test]
+    fn test_hash_is_stable() {
+        assert_eq!(args_hash(&[]), 0xcbf29ce484222325);
+        assert_ne!(args_hash(&["-J", "a"]), args_hash(&["-Ja"]));
+    }
+
+    #[test]
+    fn test_replay_recorded_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = "https://www.youtube.com/watch?v=dQw4w9WgXcQ";
+        let mut builder = YoutubeDl::new(url);
+        builder.socket_timeout("15").replay_from(&dir);
+
+        let args = builder.process_args();
+        store(
+            dir.path(),
+            &args,
+            br#"{"id": "dQw4w9WgXcQ", "title": "Never Gonna Give You Up"}"#,
+        )
+        .unwrap();
+        assert!(load(dir.path(), &["-J", url]).is_err());
+
+        let video = builder.run().unwrap().into_single_video().unwrap();
+        assert_eq!(video.id, "dQw4w9WgXcQ");
+        assert_eq!(
+            builder.run_raw().unwrap()["title"],
+            "Never Gonna Give You Up"
+        );
+    }
+}
@@ -0,0 +1,523 @@
+//! Client-side format selection, size estimation and format listing.
+
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use crate::{Error, Format, Playlist, SingleVideo, YoutubeDl, YoutubeDlOutput};
+
+/// Matches a single part of a format selector (e.g. `bv` or `137`) against a format.
+fn matches(keyword: &str, format: &Format) -> Option<bool> {
+    let has_video = format.vcodec.is_some();
+    let has_audio = format.acodec.is_some();
+    let matched = match keyword {
+        "best" | "b" | "worst" | "w" => has_video && has_audio,
+        "bestvideo" | "bv" | "worstvideo" | "wv" => has_video && !has_audio,
+        "bestaudio" | "ba" | "worstaudio" | "wa" => has_audio && !has_video,
+        "bv*" | "wv*" | "bestvideo*" | "worstvideo*" => has_video,
+        "ba*" | "wa*" | "bestaudio*" | "worstaudio*" => has_audio,
+        "b*" | "w*" | "best*" | "worst*" => has_video || has_audio,
+        _ => return None,
+    };
+    Some(matched)
+}
+
+fn select_one<'a>(formats: &'a [Format], part: &str) -> Option<&'a Format> {
+    // `ba.2` selects the second best audio format.
+    let (keyword, n) = part
+        .rsplit_once('.')
+        .and_then(|(keyword, n)| Some((keyword, n.parse().ok().filter(|&n| n > 0)?)))
+        .unwrap_or((part, 1));
+    let is_keyword = matches(keyword, &Format::default()).is_some();
+    if !is_keyword {
+        return formats
+            .iter()
+            .find(|f| f.format_id.as_deref() == Some(part));
+    }
+
+    // yt-dlp sorts formats from worst to best.
+    let mut candidates = formats.iter().filter(|f| matches(keyword, f) == Some(true));
+    if keyword.starts_with('w') {
+        candidates.nth(n - 1)
+    } else {
+        candidates.nth_back(n - 1)
+    }
+}
+
+/// Select formats with a subset of yt-dlp's format selector syntax: format IDs and the
+/// `best`/`worst` keywords (and their short forms like `bv`, `ba*`, or `ba.2` for the second
+/// best), merged with `+` and with fallbacks separated by `/`. Filters (`[height<=720]`) and
+/// sorting are not supported.
+pub(crate) fn select_formats<'a>(formats: &'a [Format], selector: &str) -> Option<Vec<&'a Format>> {
+    selector.split('/').find_map(|alternative| {
+        alternative
+            .split('+')
+            .map(|part| select_one(formats, part.trim()))
+            .collect()
+    })
+}
+
+/// Names of URL parameters with the expiry time as a Unix timestamp: `expire` of googlevideo
+/// and `Expires` of CloudFront.
+const EXPIRY_PARAMETERS: &[&str] = &["expire", "Expires"];
+
+fn url_expires_at(url: &str) -> Option<SystemTime> {
+    let mut parts = url.split(['?', '&', '/']);
+    while let Some(part) = parts.next() {
+        let value = if let Some(token) = part.strip_prefix("hdnts=").or_else(|| {
+            // Akamai tokens have `~` separated fields, `exp` is only meaningful among them.
+            part.contains('~').then_some(part)
+        }) {
+            match token
+                .split('~')
+                .find_map(|field| field.strip_prefix("exp="))
+            {
+                Some(value) => value,
+                None => continue,
+            }
+        } else {
+            match part.split_once('=') {
+                Some((name, value)) if EXPIRY_PARAMETERS.contains(&name) => value,
+                Some(_) => continue,
+                // Manifest URLs of googlevideo have the parameters as path segments.
+                None if part == "expire" => parts.next()?,
+                None => continue,
+            }
+        };
+        if let Ok(timestamp) = value.parse::<u64>() {
+            return SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(timestamp));
+        }
+    }
+    None
+}
+
+fn format_size(format: &Format) -> Option<u64> {
+    format
+        .filesize
+        .or(format.filesize_approx)
+        .map(|size| size as u64)
+}
+
+impl Format {
+    /// The HTTP headers that must be sent when requesting `url` (e.g. `User-Agent`), for
+    /// downloading or streaming the format without yt-dlp.
+    pub fn headers_for_request(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.http_headers
+            .iter()
+            .flatten()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// The HTTP headers of `headers_for_request` as a `reqwest` header map. Headers with names or
+    /// values that are not valid in HTTP are skipped.
+    #[cfg(any(feature = "downloader-rustls-tls", feature = "downloader-native-tls"))]
+    pub fn reqwest_headers(&self) -> reqwest::header::HeaderMap {
+        use reqwest::header::{HeaderName, HeaderValue};
+
+        self.headers_for_request()
+            .filter_map(|(name, value)| {
+                let name = HeaderName::from_bytes(name.as_bytes()).ok()?;
+                let value = HeaderValue::from_str(value).ok()?;
+                Some((name, value))
+            })
+            .collect()
+    }
+
+    /// When the direct `url` stops working, e.g. to know how long it can be cached. Parsed from
+    /// the expiry time some sites put into their URLs, like the `expire` parameter of YouTube's
+    /// googlevideo URLs. Returns `None` if the URL has no known expiry parameter.
+    pub fn url_expires_at(&self) -> Option<SystemTime> {
+        url_expires_at(self.url.as_deref()?)
+    }
+}
+
+impl SingleVideo {
+    /// Select formats of this video client-side (without running yt-dlp again). Supports a subset
+    /// of yt-dlp's format selectors: format IDs and keywords like `best`, `bv` or `ba*`, merged
+    /// with `+` and with fallbacks separated by `/` (e.g. `bv+ba/b`). Returns `None` if nothing
+    /// matches or the selector uses unsupported syntax like filters.
+    pub fn select_formats(&self, format_selector: &str) -> Option<Vec<&Format>> {
+        select_formats(self.formats.as_deref()?, format_selector)
+    }
+
+    /// Estimate the download size in bytes from `filesize` or `filesize_approx`.
+    /// With `None`, the formats selected by yt-dlp are used, otherwise the formats
+    /// matching the selector (see `select_formats`).
+    pub fn estimated_size(&self, format_selector: Option<&str>) -> Option<u64> {
+        match format_selector {
+            Some(selector) => self
+                .select_formats(selector)?
+                .into_iter()
+                .map(format_size)
+                .sum(),
+            None => self
+                .filesize
+                .map(|size| size as u64)
+                .or_else(|| self.filesize_approx.map(|size| size as u64)),
+        }
+    }
+}
+
+impl Playlist {
+    /// Estimate the total download size in bytes of all entries, using the formats selected by
+    /// yt-dlp. Returns `None` if the size of any entry is unknown (e.g. with `flat_playlist`).
+    pub fn estimated_total_size(&self) -> Option<u64> {
+        self.entries
+            .as_ref()?
+            .iter()
+            .map(|entry| entry.estimated_size(None))
+            .sum()
+    }
+}
+
+/// The formats of a video, returned by `YoutubeDl::list_formats`. The `Display` implementation
+/// prints a table like yt-dlp's `-F` option.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FormatTable {
+    formats: Vec<Format>,
+}
+
+impl FormatTable {
+    /// The formats, from worst to best (as sorted by yt-dlp).
+    pub fn formats(&self) -> &[Format] {
+        &self.formats
+    }
+
+    /// Consume the table, returning the formats.
+    pub fn into_formats(self) -> Vec<Format> {
+        self.formats
+    }
+}
+
+impl From<Vec<Format>> for FormatTable {
+    fn from(formats: Vec<Format>) -> Self {
+        FormatTable { formats }
+    }
+}
+
+const COLUMNS: [&str; 9] = [
+    "ID",
+    "EXT",
+    "RESOLUTION",
+    "FPS",
+    "FILESIZE",
+    "TBR",
+    "PROTO",
+    "VCODEC",
+    "ACODEC",
+];
+
+fn codec(codec: &Option<String>) -> String {
+    match codec.as_deref() {
+        None | Some("none") => String::new(),
+        Some(codec) => codec.to_string(),
+    }
+}
+
+fn human_size(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.2}{}", size, UNITS[unit])
+}
+
+fn row(format: &Format) -> [String; 9] {
+    let has_video = !codec(&format.vcodec).is_empty();
+    let resolution = match &format.resolution {
+        Some(resolution) => resolution.clone(),
+        None if !has_video => "audio only".to_string(),
+        None => String::new(),
+    };
+    let filesize = match (format.filesize, format.filesize_approx) {
+        (Some(size), _) => human_size(size),
+        (None, Some(size)) => format!("~{}", human_size(size)),
+        (None, None) => String::new(),
+    };
+    let protocol = format
+        .protocol
+        .and_then(|protocol| serde_json::to_value(protocol).ok())
+        .and_then(|value| value.as_str().map(String::from))
+        .unwrap_or_default();
+
+    [
+        format.format_id.clone().unwrap_or_default(),
+        format.ext.clone().unwrap_or_default(),
+        resolution,
+        format.fps.map(|fps| fps.to_string()).unwrap_or_default(),
+        filesize,
+        format
+            .tbr
+            .map(|tbr| format!("{}k", tbr.round()))
+            .unwrap_or_default(),
+        protocol,
+        codec(&format.vcodec),
+        codec(&format.acodec),
+    ]
+}
+
+impl fmt::Display for FormatTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows: Vec<_> = self.formats.iter().map(row).collect();
+        let mut widths = COLUMNS.map(str::len);
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let write_row = |f: &mut fmt::Formatter<'_>, cells: &[&str]| -> fmt::Result {
+            let line: Vec<_> = cells
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            writeln!(f, "{}", line.join(" ").trim_end())
+        };
+
+        write_row(f, &COLUMNS)?;
+        let total = widths.iter().sum::<usize>() + widths.len() - 1;
+        writeln!(f, "{}", "-".repeat(total))?;
+        for row in &rows {
+            let cells: Vec<&str> = row.iter().map(String::as_str).collect();
+            write_row(f, &cells)?;
+        }
+        Ok(())
+    }
+}
+
+impl YoutubeDl {
+    /// Fetch the available formats (like yt-dlp's `-F` option). For a playlist, the formats of
+    /// its first video are returned.
+    pub fn list_formats(&self) -> Result<FormatTable, Error> {
+        let video = match self.run()? {
+            YoutubeDlOutput::SingleVideo(video) => Some(*video),
+            YoutubeDlOutput::Playlist(playlist) => playlist
+                .entries
+                .and_then(|entries| entries.into_iter().next()),
+        };
+        let formats = video.and_then(|video| video.formats).unwrap_or_default();
+        Ok(FormatTable::from(formats))
+    }
+
+    /// Get fresh direct URLs for a format of a video, since the URLs in extracted metadata
+    /// expire after a few hours. Runs a minimal extraction (`-f <format_id> --print urls`) with
+    /// the options of this builder, for a URL or a YouTube video ID. Returns one URL per format,
+    /// so two for merged formats like `137+140`. Fails with `Error::NoMatchingFormat` if yt-dlp
+    /// printed no URL.
+    pub fn refresh_format_url(
+        &self,
+        video_id_or_url: &str,
+        format_id: &str,
+    ) -> Result<Vec<String>, Error> {
+        let builder = self.with_url(video_id_or_url);
+        let stdout = builder.run_json_with(builder.format_url_args(format_id))?;
+        parse_urls(&stdout)
+    }
+
+    /// Get fresh direct URLs for a format asynchronously, see `refresh_format_url`.
+    #[cfg(feature = "tokio")]
+    pub async fn refresh_format_url_async(
+        &self,
+        video_id_or_url: &str,
+        format_id: &str,
+    ) -> Result<Vec<String>, Error> {
+        let builder = self.with_url(video_id_or_url);
+        let stdout = builder
+            .run_json_with_async(builder.format_url_args(format_id))
+            .await?;
+        parse_urls(&stdout)
+    }
+
+    fn format_url_args<'a>(&'a self, format_id: &'a str) -> Vec<&'a str> {
+        let mut args = self.common_args();
+        args.option("-f", format_id);
+        args.flag("--no-playlist");
+        args.repeated("--print", "urls");
+        args.positional(&self.url);
+        args.into_vec()
+    }
+}
+
+fn parse_urls(stdout: &[u8]) -> Result<Vec<String>, Error> {
+    let urls: Vec<String> = String::from_utf8_lossy(stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect();
+    if urls.is_empty() {
+        Err(Error::NoMatchingFormat)
+    } else {
+        Ok(urls)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FormatTable;
+    use crate::{Format, SingleVideo, YoutubeDl};
+
+    fn format(id: &str, vcodec: Option<&str>, acodec: Option<&str>, size: f64) -> Format {
+        Format {
+            format_id: Some(id.into()),
+            vcodec: vcodec.map(String::from),
+            acodec: acodec.map(String::from),
+            filesize_approx: Some(size),
+            ..Default::default()
+        }
+    }
+
+    fn video() -> SingleVideo {
+        SingleVideo {
+            formats: Some(vec![
+                format("18", Some("avc1"), Some("mp4a"), 1000.0),
+                format("140", None, Some("mp4a"), 100.0),
+                format("251", None, Some("opus"), 200.0),
+                format("137", Some("avc1"), None, 3000.0),
+                format("248", Some("vp9"), None, 4000.0),
+            ]),
+            filesize_approx: Some(4200.0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_headers_for_request() {
+        let format: Format = serde_json::from_str(
+            r#"{"http_headers": {"User-Agent": "Mozilla/5.0", "Cookie": null, "DNT": 1}}"#,
+        )
+        .unwrap();
+        let headers: Vec<_> = format.headers_for_request().collect();
+        assert_eq!(headers, [("DNT", "1"), ("User-Agent", "Mozilla/5.0")]);
+
+        #[cfg(any(feature = "downloader-rustls-tls", feature = "downloader-native-tls"))]
+        assert_eq!(format.reqwest_headers()["user-agent"], "Mozilla/5.0");
+    }
+
+    #[test]
+    fn test_url_expires_at() {
+        use std::time::SystemTime;
+
+        let expires_at = |url: &str| {
+            let format = Format {
+                url: Some(url.into()),
+                ..Default::default()
+            };
+            format.url_expires_at().map(|time| {
+                time.duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs()
+            })
+        };
+        assert_eq!(
+            expires_at(
+                "https://rr1---sn-abc.googlevideo.com/videoplayback?expire=1700000000&ei=x&itag=18"
+            ),
+            Some(1700000000)
+        );
+        assert_eq!(
+            expires_at("https://manifest.googlevideo.com/api/manifest/hls_playlist/expire/1700000000/ei/x/index.m3u8"),
+            Some(1700000000)
+        );
+        assert_eq!(
+            expires_at("https://d1.cloudfront.net/v.mp4?Expires=1700000001&Signature=abc"),
+            Some(1700000001)
+        );
+        assert_eq!(
+            expires_at("https://cdn.example.com/v.m3u8?hdnts=exp=1700000002~acl=/*~hmac=abc"),
+            Some(1700000002)
+        );
+        assert_eq!(
+            expires_at("https://cdn.example.com/v.m3u8?token=st=1~exp=1700000003~hmac=abc"),
+            Some(1700000003)
+        );
+        assert_eq!(
+            expires_at("https://example.com/expired/v.mp4?exp=soon"),
+            None
+        );
+        assert_eq!(expires_at("https://example.com/v.mp4?exp=1700000004"), None);
+        assert_eq!(
+            expires_at("https://example.com/v.mp4?expire=18446744073709551615"),
+            None
+        );
+        assert_eq!(Format::default().url_expires_at(), None);
+    }
+
+    #[test]
+    fn test_select_formats() {
+        let video = video();
+        let ids = |selector: &str| -> Option<Vec<String>> {
+            video.select_formats(selector).map(|formats| {
+                formats
+                    .into_iter()
+                    .map(|f| f.format_id.clone().unwrap())
+                    .collect()
+            })
+        };
+
+        assert_eq!(ids("bv+ba").unwrap(), ["248", "251"]);
+        assert_eq!(ids("wv+wa").unwrap(), ["137", "140"]);
+        assert_eq!(ids("best").unwrap(), ["18"]);
+        assert_eq!(ids("137+140").unwrap(), ["137", "140"]);
+        assert_eq!(ids("299+140/18").unwrap(), ["18"]);
+        assert_eq!(ids("ba*").unwrap(), ["251"]);
+        assert_eq!(ids("bv+ba+ba.2").unwrap(), ["248", "251", "140"]);
+        assert_eq!(ids("ba.3"), None);
+        assert_eq!(ids("bv[height<=720]"), None);
+    }
+
+    #[test]
+    fn test_format_table() {
+        let table = FormatTable::from(video().formats.unwrap());
+        let output = table.to_string();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert!(lines[0].starts_with("ID  EXT RESOLUTION"));
+        assert_eq!(
+            lines[3],
+            "140     audio only     ~100.00B                   mp4a"
+        );
+        assert_eq!(lines[6], "248                    ~3.91KiB            vp9");
+    }
+
+    #[test]
+    fn test_estimated_size() {
+        let video = video();
+        assert_eq!(video.estimated_size(None), Some(4200));
+        assert_eq!(video.estimated_size(Some("bv+ba")), Some(4200));
+        assert_eq!(video.estimated_size(Some("18")), Some(1000));
+        assert_eq!(video.estimated_size(Some("1234")), None);
+
+        let playlist = crate::Playlist {
+            entries: Some(vec![video.clone(), video]),
+            ..Default::default()
+        };
+        assert_eq!(playlist.estimated_total_size(), Some(8400));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_refresh_format_url() {
+        use crate::tests::fake_yt_dlp;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = r#"case "$*" in
+    *"-f 137+140 --no-playlist --print urls abc"*)
+        echo "https://cdn.example.com/137"
+        echo "https://cdn.example.com/140" ;;
+esac"#;
+        let path = fake_yt_dlp(dir.path(), script);
+        let mut builder = YoutubeDl::new("");
+        builder.youtube_dl_path(path);
+
+        let urls = builder.refresh_format_url("abc", "137+140").unwrap();
+        assert_eq!(
+            urls,
+            ["https://cdn.example.com/137", "https://cdn.example.com/140"]
+        );
+        assert!(builder.refresh_format_url("abc", "999").is_err());
+    }
+}
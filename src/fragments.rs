@@ -0,0 +1,449 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, HeaderValue, RANGE};
+use reqwest::Url;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use tokio::task::JoinSet;
+
+use crate::{Error, Format, Protocol};
+
+/// Downloads fragmented formats (`http_dash_segments` with a fragment list, or unencrypted HLS
+/// media playlists) directly with reqwest, fetching several fragments concurrently and writing
+/// them to the file in order. This allows using yt-dlp only to extract the metadata, and doing
+/// the transfer in-process. When a fragment fails after all retries, the other requests are
+/// cancelled.
+///
+/// ```rust,no_run
+/// # async fn example() -> Result<(), youtube_dl::Error> {
+/// use youtube_dl::fragments::FragmentDownloader;
+/// use youtube_dl::YoutubeDl;
+///
+/// let video = YoutubeDl::new("https://www.youtube.com/watch?v=VFbhKZFzbzk")
+///     .run_async()
+///     .await?
+///     .into_single_video()
+///     .unwrap();
+/// let format = video.select_formats("bv").unwrap()[0];
+/// FragmentDownloader::new()
+///     .concurrency(8)
+///     .download(format, "video.mp4")
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct FragmentDownloader {
+    client: reqwest::Client,
+    concurrency: usize,
+    retries: u32,
+}
+
+impl Default for FragmentDownloader {
+    fn default() -> Self {
+        Self {
+            client: Default::default(),
+            concurrency: 4,
+            retries: 3,
+        }
+    }
+}
+
+impl FragmentDownloader {
+    /// Create a downloader that fetches 4 fragments at once and retries each fragment 3 times.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use this client for all requests (e.g. to configure a proxy).
+    pub fn client(&mut self, client: reqwest::Client) -> &mut Self {
+        self.client = client;
+        self
+    }
+
+    /// How many fragments are downloaded at the same time.
+    pub fn concurrency(&mut self, concurrency: usize) -> &mut Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// How often a failed fragment is retried before the download fails. The delay before each
+    /// retry doubles, starting at half a second.
+    pub fn retries(&mut self, retries: u32) -> &mut Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Download all fragments of the format and concatenate them into `destination`, returning
+    /// the number of bytes written. Fails with `Error::UnsupportedFormat` for formats that are
+    /// not fragmented or use encryption.
+    pub async fn download(
+        &self,
+        format: &Format,
+        destination: impl AsRef<Path>,
+    ) -> Result<u64, Error> {
+        let headers = format.reqwest_headers();
+        let fragments = self.fragments(format, &headers).await?;
+        log::debug!(
+            "downloading {} fragments of format {:?}",
+            fragments.len(),
+            format.format_id
+        );
+
+        let mut file = File::create(destination).await?;
+        let mut written = 0;
+        // Dropping the set (when returning an error) aborts the other requests.
+        let mut running = JoinSet::new();
+        // Fragments that finished before the ones preceding them, by index.
+        let mut finished: BTreeMap<usize, Vec<u8>> = BTreeMap::new();
+        let mut next = 0;
+        let mut fragments = fragments.into_iter().enumerate();
+        loop {
+            // Write the fragments first, so that they don't count against the concurrency.
+            while let Some(fragment) = finished.remove(&next) {
+                file.write_all(&fragment).await?;
+                written += fragment.len() as u64;
+                next += 1;
+            }
+            while running.len() + finished.len() < self.concurrency {
+                let (index, fragment) = match fragments.next() {
+                    Some(fragment) => fragment,
+                    None => break,
+                };
+                let client = self.client.clone();
+                let headers = headers.clone();
+                let retries = self.retries;
+                running
+                    .spawn(async move { (index, fetch(client, fragment, headers, retries).await) });
+            }
+            let (index, fragment) = match running.join_next().await {
+                Some(task) => task.map_err(std::io::Error::other)?,
+                None if fragments.len() > 0 => continue,
+                None if finished.is_empty() => break,
+                None => {
+                    let missing = format!("fragment {} was not downloaded", next);
+                    return Err(std::io::Error::other(missing).into());
+                }
+            };
+            finished.insert(index, fragment?);
+        }
+        file.flush().await?;
+        Ok(written)
+    }
+
+    async fn fragments(
+        &self,
+        format: &Format,
+        headers: &HeaderMap,
+    ) -> Result<Vec<Fragment>, Error> {
+        if let Some(fragments) = &format.fragments {
+            let base = format.fragment_base_url.as_deref();
+            return fragments
+                .iter()
+                .map(|fragment| {
+                    let url =
+                        fragment_url(base, fragment.url.as_deref(), fragment.path.as_deref())?;
+                    Ok(Fragment { url, range: None })
+                })
+                .collect();
+        }
+
+        match format.protocol {
+            Some(Protocol::M3U8) | Some(Protocol::M3U8Native) => {
+                let url = format.url.as_deref().ok_or_else(|| unsupported("no URL"))?;
+                let url = Url::parse(url).map_err(|err| unsupported(err.to_string()))?;
+                let playlist = self
+                    .client
+                    .get(url.clone())
+                    .headers(headers.clone())
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .text()
+                    .await?;
+                media_playlist_fragments(&url, &playlist)
+            }
+            _ => Err(unsupported("the format has no fragments")),
+        }
+    }
+}
+
+fn unsupported(reason: impl Into<String>) -> Error {
+    Error::UnsupportedFormat {
+        reason: reason.into(),
+    }
+}
+
+/// The delay before the first retry of a fragment, doubled for each further retry.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// A fragment to download: the URL and, for HLS byte ranges, the offset and length in it.
+#[derive(Debug, PartialEq)]
+struct Fragment {
+    url: Url,
+    range: Option<(u64, u64)>,
+}
+
+async fn fetch(
+    client: reqwest::Client,
+    fragment: Fragment,
+    mut headers: HeaderMap,
+    retries: u32,
+) -> Result<Vec<u8>, Error> {
+    if let Some((offset, length)) = fragment.range {
+        let range = format!("bytes={}-{}", offset, offset + length.max(1) - 1);
+        headers.insert(RANGE, HeaderValue::from_str(&range).expect("invalid range"));
+    }
+    let mut attempt = 0;
+    loop {
+        let result = async {
+            let response = client
+                .get(fragment.url.clone())
+                .headers(headers.clone())
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok::<_, reqwest::Error>(response.bytes().await?.to_vec())
+        }
+        .await;
+        match result {
+            Ok(bytes) => return Ok(bytes),
+            Err(err) if attempt < retries => {
+                let delay = RETRY_DELAY
+                    .saturating_mul(1 << attempt.min(16))
+                    .min(MAX_RETRY_DELAY);
+                attempt += 1;
+                log::warn!(
+                    "fragment {} failed, retrying in {:?}: {}",
+                    fragment.url,
+                    delay,
+                    err
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+/// The URL of a DASH fragment: its own URL, or its path relative to the base URL.
+fn fragment_url(base: Option<&str>, url: Option<&str>, path: Option<&str>) -> Result<Url, Error> {
+    let url = match (url, base, path) {
+        (Some(url), _, _) => Url::parse(url),
+        (None, Some(base), Some(path)) => Url::parse(base).and_then(|base| base.join(path)),
+        _ => return Err(unsupported("fragment without URL")),
+    };
+    url.map_err(|err| unsupported(err.to_string()))
+}
+
+/// The segments of an HLS media playlist, including the initialization section.
+fn media_playlist_fragments(playlist_url: &Url, playlist: &str) -> Result<Vec<Fragment>, Error> {
+    if playlist.contains("#EXT-X-STREAM-INF") {
+        return Err(unsupported("the URL is a master playlist"));
+    }
+    let join = |uri: &str| {
+        playlist_url
+            .join(uri)
+            .map_err(|err| unsupported(err.to_string()))
+    };
+    let mut fragments = vec![];
+    // The byte range of the next segment, and where the previous one ended.
+    let mut range = None;
+    let mut end = 0;
+    for line in playlist.lines().map(str::trim) {
+        if let Some(attributes) = line.strip_prefix("#EXT-X-KEY:") {
+            if !attributes.contains("METHOD=NONE") {
+                return Err(unsupported("the segments are encrypted"));
+            }
+        } else if let Some(attributes) = line.strip_prefix("#EXT-X-MAP:") {
+            let attribute = |name: &str| {
+                attributes
+                    .split(',')
+                    .find_map(|attribute| attribute.strip_prefix(name))
+                    .map(|value| value.trim_matches('"'))
+            };
+            if let Some(uri) = attribute("URI=") {
+                let range = match attribute("BYTERANGE=") {
+                    Some(byte_range) => Some(parse_byte_range(byte_range, 0)?),
+                    None => None,
+                };
+                fragments.push(Fragment {
+                    url: join(uri)?,
+                    range,
+                });
+            }
+        } else if let Some(byte_range) = line.strip_prefix("#EXT-X-BYTERANGE:") {
+            range = Some(parse_byte_range(byte_range, end)?);
+        } else if !line.is_empty() && !line.starts_with('#') {
+            if let Some((offset, length)) = range {
+                end = offset + length;
+            }
+            fragments.push(Fragment {
+                url: join(line)?,
+                range: range.take(),
+            });
+        }
+    }
+    Ok(fragments)
+}
+
+/// Parse a byte range like `1000@500` (length and offset) into the offset and length. Without an
+/// offset, the range starts at `end` (where the previous segment ended).
+fn parse_byte_range(byte_range: &str, end: u64) -> Result<(u64, u64), Error> {
+    let invalid = || unsupported(format!("invalid byte range {:?}", byte_range));
+    let (length, offset) = match byte_range.split_once('@') {
+        Some((length, offset)) => (length, offset.parse().map_err(|_| invalid())?),
+        None => (byte_range, end),
+    };
+    let length = length.parse().map_err(|_| invalid())?;
+    Ok((offset, length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fragment_url, media_playlist_fragments, Fragment, FragmentDownloader};
+    use reqwest::Url;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    /// Serve `/frag<n>` with the body `frag<n>;` after `delays[n]` milliseconds, returning the
+    /// format with the fragments.
+    fn serve(delays: &'static [u64]) -> crate::Format {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                std::thread::spawn(move || {
+                    let mut request_line = String::new();
+                    BufReader::new(&stream)
+                        .read_line(&mut request_line)
+                        .unwrap();
+                    let path = request_line.split(' ').nth(1).unwrap();
+                    let index: usize = path.trim_start_matches("/frag").parse().unwrap();
+                    std::thread::sleep(Duration::from_millis(delays[index]));
+                    let body = format!("frag{};", index);
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    stream.write_all(response.as_bytes()).unwrap();
+                });
+            }
+        });
+
+        let fragments = (0..delays.len())
+            .map(|index| crate::model::Fragment {
+                url: Some(format!("http://{}/frag{}", address, index)),
+                ..Default::default()
+            })
+            .collect();
+        crate::Format {
+            fragments: Some(fragments),
+            ..Default::default()
+        }
+    }
+
+    async fn download(delays: &'static [u64], concurrency: usize) -> (u64, String) {
+        let format = serve(delays);
+        let dir = tempfile::tempdir().unwrap();
+        let destination = dir.path().join("video.mp4");
+        let written = FragmentDownloader::new()
+            .client(reqwest::Client::builder().no_proxy().build().unwrap())
+            .concurrency(concurrency)
+            .download(&format, &destination)
+            .await
+            .unwrap();
+        (written, std::fs::read_to_string(destination).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_download_sequentially() {
+        let (written, contents) = download(&[0, 0, 0, 0, 0], 1).await;
+        assert_eq!(contents, "frag0;frag1;frag2;frag3;frag4;");
+        assert_eq!(written, 30);
+    }
+
+    #[tokio::test]
+    async fn test_download_out_of_order() {
+        // The later fragments finish before the first one.
+        let (written, contents) = download(&[300, 0, 100, 0, 0], 2).await;
+        assert_eq!(contents, "frag0;frag1;frag2;frag3;frag4;");
+        assert_eq!(written, 30);
+    }
+
+    #[test]
+    fn test_fragment_url() {
+        let base = Some("https://cdn.example.com/dash/");
+        assert_eq!(
+            fragment_url(base, None, Some("sq/1")).unwrap().as_str(),
+            "https://cdn.example.com/dash/sq/1"
+        );
+        assert_eq!(
+            fragment_url(base, Some("https://other.example.com/1"), None)
+                .unwrap()
+                .as_str(),
+            "https://other.example.com/1"
+        );
+        assert!(fragment_url(None, None, Some("sq/1")).is_err());
+    }
+
+    #[test]
+    fn test_media_playlist_urls() {
+        let playlist_url = Url::parse("https://cdn.example.com/hls/720p/index.m3u8").unwrap();
+        let playlist = "#EXTM3U
+#EXT-X-TARGETDURATION:6
+#EXT-X-MAP:URI=\"init.mp4\"
+#EXTINF:6.0,
+seg-1.m4s
+#EXTINF:6.0,
+https://other.example.com/seg-2.m4s
+#EXT-X-ENDLIST
+";
+        let urls: Vec<_> = media_playlist_fragments(&playlist_url, playlist)
+            .unwrap()
+            .into_iter()
+            .map(|fragment| String::from(fragment.url))
+            .collect();
+        assert_eq!(
+            urls,
+            [
+                "https://cdn.example.com/hls/720p/init.mp4",
+                "https://cdn.example.com/hls/720p/seg-1.m4s",
+                "https://other.example.com/seg-2.m4s",
+            ]
+        );
+
+        let encrypted = "#EXTM3U\n#EXT-X-KEY:METHOD=AES-128,URI=\"key\"\nseg-1.ts\n";
+        assert!(media_playlist_fragments(&playlist_url, encrypted).is_err());
+    }
+
+    #[test]
+    fn test_media_playlist_byte_ranges() {
+        let playlist_url = Url::parse("https://cdn.example.com/hls/index.m3u8").unwrap();
+        let playlist = "#EXTM3U
+#EXT-X-MAP:URI=\"main.mp4\",BYTERANGE=\"720@0\"
+#EXTINF:6.0,
+#EXT-X-BYTERANGE:1000@720
+main.mp4
+#EXTINF:6.0,
+#EXT-X-BYTERANGE:500
+main.mp4
+#EXT-X-ENDLIST
+";
+        let url = Url::parse("https://cdn.example.com/hls/main.mp4").unwrap();
+        let fragment = |offset, length| Fragment {
+            url: url.clone(),
+            range: Some((offset, length)),
+        };
+        assert_eq!(
+            media_playlist_fragments(&playlist_url, playlist).unwrap(),
+            [fragment(0, 720), fragment(720, 1000), fragment(1720, 500)]
+        );
+    }
+}
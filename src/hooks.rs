@@ -0,0 +1,99 @@
+use std::fmt;
+use std::path::Path;
+use std::process::ExitStatus;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{Error, Progress};
+
+/// Callbacks invoked while running yt-dlp, e.g. to feed metrics into Prometheus.
+/// All methods have empty default implementations, so only the relevant ones need to be implemented.
+/// Register an implementation with `YoutubeDl::hooks`.
+pub trait Hooks: Send + Sync {
+    /// Called after the yt-dlp process for `url` was spawned.
+    fn on_spawn(&self, _url: &str, _pid: u32) {}
+
+    /// Called after the process exited (this includes non-zero exit codes, but not timeouts).
+    fn on_complete(&self, _url: &str, _exit: ExitStatus, _duration: Duration, _stdout_len: usize) {}
+
+    /// Called before yt-dlp is run again for `url` after a failed attempt.
+    fn on_retry(&self, _url: &str, _attempt: u32, _error: &Error) {}
+
+    /// Called for each progress update while downloading.
+    fn on_progress(&self, _url: &str, _progress: &Progress) {}
+
+    /// Called when a postprocessor (e.g. `Merger` or `ExtractAudio`) reports what it is doing.
+    fn on_postprocess(&self, _url: &str, _postprocessor: &str, _message: &str) {}
+
+    /// Called for each line yt-dlp writes to stderr.
+    fn on_stderr_line(&self, _url: &str, _line: &str) {}
+}
+
+impl<H: Hooks + ?Sized> Hooks for Arc<H> {
+    fn on_spawn(&self, url: &str, pid: u32) {
+        (**self).on_spawn(url, pid)
+    }
+
+    fn on_complete(&self, url: &str, exit: ExitStatus, duration: Duration, stdout_len: usize) {
+        (**self).on_complete(url, exit, duration, stdout_len)
+    }
+
+    fn on_retry(&self, url: &str, attempt: u32, error: &Error) {
+        (**self).on_retry(url, attempt, error)
+    }
+
+    fn on_progress(&self, url: &str, progress: &Progress) {
+        (**self).on_progress(url, progress)
+    }
+
+    fn on_postprocess(&self, url: &str, postprocessor: &str, message: &str) {
+        (**self).on_postprocess(url, postprocessor, message)
+    }
+
+    fn on_stderr_line(&self, url: &str, line: &str) {
+        (**self).on_stderr_line(url, line)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct HooksHandle(pub(crate) Arc<dyn Hooks>);
+
+impl fmt::Debug for HooksHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Hooks")
+    }
+}
+
+/// Callback registered with `YoutubeDl::on_file_downloaded`.
+#[derive(Clone)]
+pub(crate) struct FileCallback(pub(crate) Arc<dyn Fn(&Path) + Send + Sync>);
+
+impl fmt::Debug for FileCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FileCallback")
+    }
+}
+
+/// Callback registered with `YoutubeDl::before_spawn`.
+#[derive(Clone)]
+pub(crate) struct SpawnCallback(pub(crate) Arc<dyn Fn(&mut std::process::Command) + Send + Sync>);
+
+impl fmt::Debug for SpawnCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SpawnCallback")
+    }
+}
+
+/// Callback registered with `YoutubeDl::before_spawn_async`.
+#[cfg(feature = "tokio")]
+#[derive(Clone)]
+pub(crate) struct AsyncSpawnCallback(
+    pub(crate) Arc<dyn Fn(&mut tokio::process::Command) + Send + Sync>,
+);
+
+#[cfg(feature = "tokio")]
+impl fmt::Debug for AsyncSpawnCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AsyncSpawnCallback")
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{Playlist, SingleVideo, YoutubeDlOutput};
+
+/// What kind of content an output is, returned by `YoutubeDlOutput::kind`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OutputKind {
+    /// A video, including finished live streams
+    Video,
+    /// Audio without video, like a SoundCloud track or a podcast episode
+    Audio,
+    /// A live stream that is currently running
+    Livestream,
+    /// A live stream or premiere that has not started yet
+    Upcoming,
+    /// A playlist, album or search result
+    Playlist,
+    /// A channel or user page
+    Channel,
+}
+
+/// Path segments of channel and user pages, like `/@name` or `/channel/UC...`.
+const CHANNEL_PATHS: &[&str] = &["/@", "/channel/", "/c/", "/user/"];
+
+impl YoutubeDlOutput {
+    /// Classify the output from its live status, codecs and URL, e.g. to ask which entry to
+    /// download when a playlist was given.
+    pub fn kind(&self) -> OutputKind {
+        match self {
+            YoutubeDlOutput::SingleVideo(video) => video_kind(video),
+            YoutubeDlOutput::Playlist(playlist) => playlist_kind(playlist),
+        }
+    }
+}
+
+fn video_kind(video: &SingleVideo) -> OutputKind {
+    match video.live_status.as_deref() {
+        Some("is_upcoming") => return OutputKind::Upcoming,
+        Some("is_live") => return OutputKind::Livestream,
+        None if video.is_live == Some(true) => return OutputKind::Livestream,
+        _ => {}
+    }
+    // The codecs of formats are `None` without video or audio, the ones of the video are `none`.
+    let formats = video.formats.as_deref().unwrap_or_default();
+    let audio_only = match video.vcodec.as_deref() {
+        Some(vcodec) => vcodec == "none",
+        None => {
+            !formats.is_empty()
+                && formats.iter().all(|format| format.vcodec.is_none())
+                && formats.iter().any(|format| format.acodec.is_some())
+        }
+    };
+    if audio_only {
+        OutputKind::Audio
+    } else {
+        OutputKind::Video
+    }
+}
+
+fn playlist_kind(playlist: &Playlist) -> OutputKind {
+    // Tabs of a channel (like `/@name/videos`) count as the channel.
+    let is_channel = playlist
+        .webpage_url
+        .as_deref()
+        .is_some_and(|url| CHANNEL_PATHS.iter().any(|path| url.contains(path)));
+    if is_channel {
+        OutputKind::Channel
+    } else {
+        OutputKind::Playlist
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OutputKind;
+    use crate::{Format, Playlist, SingleVideo, YoutubeDlOutput};
+
+    fn video(video: SingleVideo) -> YoutubeDlOutput {
+        YoutubeDlOutput::SingleVideo(Box::new(video))
+    }
+
+    fn playlist(url: &str) -> YoutubeDlOutput {
+        YoutubeDlOutput::Playlist(Box::new(Playlist {
+            webpage_url: Some(url.into()),
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn test_kind() {
+        let live = |status: &str| SingleVideo {
+            live_status: Some(status.into()),
+            vcodec: Some("avc1".into()),
+            ..Default::default()
+        };
+        assert_eq!(video(live("is_live")).kind(), OutputKind::Livestream);
+        assert_eq!(video(live("is_upcoming")).kind(), OutputKind::Upcoming);
+        assert_eq!(video(live("was_live")).kind(), OutputKind::Video);
+
+        let track = SingleVideo {
+            formats: Some(vec![Format {
+                acodec: Some("opus".into()),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        assert_eq!(video(track).kind(), OutputKind::Audio);
+        let audio = SingleVideo {
+            vcodec: Some("none".into()),
+            ..Default::default()
+        };
+        assert_eq!(video(audio).kind(), OutputKind::Audio);
+        assert_eq!(video(SingleVideo::default()).kind(), OutputKind::Video);
+
+        assert_eq!(
+            playlist("https://www.youtube.com/@name/videos").kind(),
+            OutputKind::Channel
+        );
+        assert_eq!(
+            playlist("https://www.youtube.com/playlist?list=PL123").kind(),
+            OutputKind::Playlist
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! Grouping formats into quality tiers, for building quality menus.
+
+use crate::{Format, Protocol};
+
+/// The video formats of one quality (e.g. `720p60`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct QualityTier<'a> {
+    /// Height of the video in pixels
+    pub height: u32,
+    /// The highest frame rate of the formats in this tier, if known
+    pub fps: Option<f64>,
+    /// Formats with video and audio, by increasing bitrate
+    pub muxed: Vec<&'a Format>,
+    /// Video-only formats, which need to be combined with an audio format, by increasing bitrate
+    pub adaptive: Vec<&'a Format>,
+}
+
+impl QualityTier<'_> {
+    /// A label like `1080p` or `720p60` (for frame rates above 30).
+    pub fn label(&self) -> String {
+        match self.fps {
+            Some(fps) if fps > 30.5 => format!("{}p{}", self.height, fps.round()),
+            _ => format!("{}p", self.height),
+        }
+    }
+
+    /// The format with the highest bitrate, preferring muxed formats if `muxed` is set.
+    pub fn best(&self, muxed: bool) -> Option<&Format> {
+        let (preferred, other) = if muxed {
+            (&self.muxed, &self.adaptive)
+        } else {
+            (&self.adaptive, &self.muxed)
+        };
+        preferred.last().or(other.last()).copied()
+    }
+}
+
+/// Formats grouped by quality, see `Formats::quality_ladder`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QualityLadder<'a> {
+    /// Video qualities, from the lowest to the highest resolution
+    pub tiers: Vec<QualityTier<'a>>,
+    /// Audio-only formats, by increasing bitrate
+    pub audio: Vec<&'a Format>,
+}
+
+/// Helpers for the formats of a video (e.g. `SingleVideo::formats`).
+pub trait Formats {
+    /// Group the formats into tiers by resolution and frame rate (high frame rate formats get
+    /// their own tier, like `720p60`), separating muxed from video-only formats. Storyboards,
+    /// formats without any codec information and video formats without a known height are
+    /// skipped.
+    fn quality_ladder(&self) -> QualityLadder<'_>;
+}
+
+fn bitrate(format: &Format) -> f64 {
+    format.tbr.or(format.vbr).or(format.abr).unwrap_or(0.0)
+}
+
+/// Frame rates above 30 (like 48, 50 and 60) are shown as a separate quality.
+fn is_high_frame_rate(format: &Format) -> bool {
+    format.fps.is_some_and(|fps| fps > 30.5)
+}
+
+impl Formats for [Format] {
+    fn quality_ladder(&self) -> QualityLadder<'_> {
+        let mut ladder = QualityLadder::default();
+        for format in self {
+            // Storyboards are images, listed with an `mhtml` protocol.
+            if format.protocol == Some(Protocol::Mhtml) {
+                continue;
+            }
+            // A codec of `none` is deserialized as `None`.
+            let video = format.vcodec.is_some();
+            let audio = format.acodec.is_some();
+            let height = format.height.map(|height| height as u32);
+
+            match (video, audio, height) {
+                (false, true, _) => ladder.audio.push(format),
+                (true, _, Some(height)) => {
+                    let high_frame_rate = is_high_frame_rate(format);
+                    let index = ladder.tiers.iter().position(|tier| {
+                        tier.height == height
+                            && tier.fps.is_some_and(|fps| fps > 30.5) == high_frame_rate
+                    });
+                    let tier = match index {
+                        Some(index) => &mut ladder.tiers[index],
+                        None => {
+                            ladder.tiers.push(QualityTier {
+                                height,
+                                fps: None,
+                                muxed: vec![],
+                                adaptive: vec![],
+                            });
+                            ladder.tiers.last_mut().unwrap()
+                        }
+                    };
+                    tier.fps = match (tier.fps, format.fps) {
+                        (Some(a), Some(b)) => Some(a.max(b)),
+                        (a, b) => a.or(b),
+                    };
+                    if audio {
+                        tier.muxed.push(format);
+                    } else {
+                        tier.adaptive.push(format);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let by_bitrate = |a: &&Format, b: &&Format| bitrate(a).total_cmp(&bitrate(b));
+        for tier in &mut ladder.tiers {
+            tier.muxed.sort_by(by_bitrate);
+            tier.adaptive.sort_by(by_bitrate);
+        }
+        ladder.audio.sort_by(by_bitrate);
+        ladder.tiers.sort_by(|a, b| {
+            a.height
+                .cmp(&b.height)
+                .then(a.fps.unwrap_or(0.0).total_cmp(&b.fps.unwrap_or(0.0)))
+        });
+        ladder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Formats;
+    use crate::{Format, Protocol};
+
+    fn format(
+        id: &str,
+        height: Option<f64>,
+        fps: Option<f64>,
+        codecs: (&str, &str),
+        tbr: f64,
+    ) -> Format {
+        // Deserialized like yt-dlp's output, which turns the `none` codecs into `None`.
+        serde_json::from_value(serde_json::json!({
+            "format_id": id,
+            "height": height,
+            "fps": fps,
+            "vcodec": codecs.0,
+            "acodec": codecs.1,
+            "tbr": tbr,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_quality_ladder() {
+        let formats = [
+            format("sb0", Some(45.0), None, ("none", "none"), 0.0),
+            Format {
+                protocol: Some(Protocol::Mhtml),
+                ..format("sb1", Some(90.0), None, ("avc1", "none"), 0.0)
+            },
+            format("140", None, None, ("none", "mp4a.40.2"), 129.0),
+            format("251", None, None, ("none", "opus"), 135.0),
+            format("18", Some(360.0), Some(30.0), ("avc1", "mp4a.40.2"), 500.0),
+            format("134", Some(360.0), Some(30.0), ("avc1", "none"), 300.0),
+            format("136", Some(720.0), Some(30.0), ("avc1", "none"), 1500.0),
+            format("247", Some(720.0), Some(30.0), ("vp9", "none"), 1200.0),
+            format("298", Some(720.0), Some(60.0), ("avc1", "none"), 3000.0),
+        ];
+        let ladder = formats.quality_ladder();
+
+        let ids = |formats: &[&Format]| -> Vec<String> {
+            formats
+                .iter()
+                .map(|f| f.format_id.clone().unwrap())
+                .collect()
+        };
+        assert_eq!(ids(&ladder.audio), ["140", "251"]);
+        let labels: Vec<_> = ladder.tiers.iter().map(|tier| tier.label()).collect();
+        assert_eq!(labels, ["360p", "720p", "720p60"]);
+
+        assert_eq!(ids(&ladder.tiers[0].muxed), ["18"]);
+        assert_eq!(ids(&ladder.tiers[0].adaptive), ["134"]);
+        assert_eq!(ids(&ladder.tiers[1].adaptive), ["247", "136"]);
+        assert_eq!(
+            ladder.tiers[1].best(true).unwrap().format_id.as_deref(),
+            Some("136")
+        );
+        assert_eq!(ids(&ladder.tiers[2].adaptive), ["298"]);
+    }
+}
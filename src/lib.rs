@@ -24,29 +24,147 @@
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::VecDeque;
 use std::error::Error as StdError;
+use std::ffi::{OsStr, OsString};
 use std::fmt;
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 use std::path::{Path, PathBuf};
 use std::process::ExitStatus;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 #[cfg(target_os = "windows")]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
+/// A download archive stored in SQLite, to import from and export to yt-dlp's text archives.
+#[cfg(feature = "sqlite-archive")]
+pub mod archive;
+mod args;
+/// Running yt-dlp for many URLs at once.
+pub mod batch;
+mod browsers;
+/// Caching of fetched metadata.
+pub mod cache;
+mod chapters;
+mod checksum;
+/// Fetching large playlists in chunks.
+pub mod chunks;
+/// Running yt-dlp for many URLs with shared options and per-site profiles.
+pub mod client;
+mod collision;
+/// Conversion between cookie jars and the cookies.txt format read by yt-dlp.
+#[cfg(feature = "cookies")]
+pub mod cookies;
+mod diagnostics;
+mod disk;
+mod doctor;
 /// Exposes a function to download the latest version of youtube-dl/yt-dlp.
 #[cfg(any(feature = "downloader-rustls-tls", feature = "downloader-native-tls"))]
 pub mod downloader;
+/// Streaming the entries of playlists while yt-dlp extracts them.
+pub mod entries;
+mod error_kind;
+/// Structured events emitted while yt-dlp runs.
+pub mod events;
+mod fixtures;
+mod formats;
+/// Downloading fragmented formats without yt-dlp.
+#[cfg(any(feature = "downloader-rustls-tls", feature = "downloader-native-tls"))]
+pub mod fragments;
+/// Callbacks for observing yt-dlp invocations.
+pub mod hooks;
+mod kind;
+mod ladder;
+mod limiter;
+#[cfg(feature = "manifests")]
+pub mod manifest;
+/// A `MetadataFetcher` that returns canned responses, for testing code that uses this crate.
+#[cfg(feature = "mock")]
+pub mod mock;
 pub mod model;
-
+mod options;
+mod preview;
+mod probe;
+/// Progress reporting for downloads.
+pub mod progress;
+#[cfg(feature = "indicatif")]
+mod progress_bar;
+mod quality;
+/// Reports about what happened during a download.
+pub mod report;
+mod runner;
+#[cfg(feature = "tower")]
+pub mod service;
+mod snapshot;
+mod spawn;
+mod stats;
+mod storyboard;
+#[cfg(feature = "audio-stream")]
+pub mod stream;
+mod strict;
+mod subtitles;
+mod tee;
+/// Downloads into temporary directories that are cleaned up automatically.
+pub mod temp;
+mod throttle;
+mod thumbnails;
+mod trace;
+mod upcoming;
+/// Parsing and canonicalization of YouTube URLs.
+pub mod urls;
+
+pub use crate::args::IntoYtDlpArgs;
+use crate::args::{ArgSet, ExtraArg, REDACTED};
+pub use crate::batch::{BatchOutput, EntryError};
+pub use crate::browsers::{list_browser_profiles, BrowserProfile};
+pub use crate::cache::MetadataCache;
+pub use crate::chapters::{ChapterSelector, Chapters};
+pub use crate::checksum::{Digest, HashAlgo};
+pub use crate::chunks::{PlaylistChunk, PlaylistChunks};
+pub use crate::client::{ProfileRegistry, RetryPolicy, YoutubeDlClient};
+pub use crate::diagnostics::{Diagnostics, Plugins};
+pub use crate::doctor::{doctor, Check, DoctorReport};
+pub use crate::entries::EntryStream;
+pub use crate::error_kind::ErrorKind;
+pub use crate::events::RunEvent;
+pub use crate::formats::FormatTable;
+#[cfg(feature = "tokio")]
+use crate::hooks::AsyncSpawnCallback;
+pub use crate::hooks::Hooks;
+use crate::hooks::{FileCallback, HooksHandle, SpawnCallback};
+pub use crate::kind::OutputKind;
+pub use crate::ladder::{Formats, QualityLadder, QualityTier};
+pub use crate::limiter::Limiter;
 pub use crate::model::*;
+pub use crate::options::{
+    AgeGateBypass, Bytes, CheckFormats, CollisionPolicy, CompatOption, LinkFormat, LoudnessTarget,
+    PostprocessorStage,
+};
+pub use crate::preview::PreviewSpec;
+pub use crate::probe::Probe;
+pub use crate::progress::Progress;
+#[cfg(feature = "indicatif")]
+pub use crate::progress_bar::ProgressBarAdapter;
+pub use crate::quality::QualityInfo;
+pub use crate::report::{DownloadOutcome, ExitReason, RunReport};
+pub use crate::snapshot::MODEL_VERSION;
+pub use crate::stats::PlaylistStats;
+pub use crate::storyboard::{Storyboard, StoryboardSheet, Tile};
+#[cfg(feature = "audio-stream")]
+pub use crate::stream::{fetch_audio_stream_url, AudioStream};
+use crate::tee::Tee;
+pub use crate::temp::TempDownload;
+pub use crate::throttle::ThrottleMitigation;
+pub use crate::thumbnails::{ThumbnailFormat, Thumbnails};
 
 #[cfg(any(feature = "downloader-rustls-tls", feature = "downloader-native-tls"))]
 pub use crate::downloader::download_yt_dlp;
 
 /// Data returned by `YoutubeDl::run`. Output can either be a single video or a playlist of videos.
-#[derive(Clone, Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum YoutubeDlOutput {
     /// Playlist result
     Playlist(Box<Playlist>),
@@ -89,9 +207,75 @@ pub enum Error {
         stderr: String,
     },
 
+    /// `youtube-dl` was terminated by a signal instead of exiting (only on Unix)
+    Killed {
+        /// The signal
+        signal: i32,
+        /// Standard error of youtube-dl
+        stderr: String,
+    },
+
     /// Process-level timeout expired.
     ProcessTimeout,
 
+    /// yt-dlp (or the program set with `command_prefix`) could not be started.
+    SpawnFailed {
+        /// The program, with the directory it was found in if it was looked up in `PATH`
+        program: PathBuf,
+        /// `PATH`, if the program was looked up in it
+        search_path: Option<OsString>,
+        /// Whether the program exists, but is not executable
+        not_executable: bool,
+        /// The error returned by the operating system
+        error: std::io::Error,
+    },
+
+    /// Not enough free disk space for the download (see `YoutubeDl::require_free_space`).
+    InsufficientDiskSpace {
+        /// Bytes required for the download, including the margin
+        required: u64,
+        /// Bytes available in the output directory
+        available: u64,
+    },
+
+    /// The output of yt-dlp exceeded the limit set with `YoutubeDl::max_stdout_bytes`.
+    OutputTooLarge {
+        /// The limit in bytes
+        limit: u64,
+    },
+
+    /// None of the formats matched the requested format selector.
+    NoMatchingFormat,
+
+    /// The video has no chapter matching the `ChapterSelector` (see `YoutubeDl::extract_chapter`).
+    NoSuchChapter,
+
+    /// yt-dlp reported an error for a single entry (see `YoutubeDl::stream_entries`).
+    EntryFailed {
+        /// The error message, without the `ERROR: ` prefix
+        message: String,
+    },
+
+    /// The video is an upcoming live stream or premiere, see `SingleVideo::check_available`.
+    NotYetAvailable {
+        /// When the video is expected to start, if known. Estimated when parsed from an error
+        /// message like `Premieres in 5 hours`.
+        starts_at: Option<SystemTime>,
+    },
+
+    /// Stored output was written with a newer model version than this crate supports (see
+    /// `YoutubeDlOutput::from_versioned_json`).
+    UnsupportedModelVersion {
+        /// The model version of the stored output
+        version: u32,
+    },
+
+    /// The output contains fields that are not part of the model (see `YoutubeDl::strict_model`).
+    UnmodeledFields {
+        /// Paths of the fields, like `formats[].new_field`
+        fields: Vec<String>,
+    },
+
     /// HTTP error (when fetching youtube-dl/yt-dlp)
     #[cfg(any(feature = "downloader-rustls-tls", feature = "downloader-native-tls"))]
     Http(reqwest::Error),
@@ -99,6 +283,50 @@ pub enum Error {
     /// When no GitHub release could be found to download the youtube-dl/yt-dlp executable.
     #[cfg(any(feature = "downloader-rustls-tls", feature = "downloader-native-tls"))]
     NoReleaseFound,
+
+    /// Error of the SQLite download archive
+    #[cfg(feature = "sqlite-archive")]
+    Sqlite(rusqlite::Error),
+
+    /// An HLS or DASH manifest could not be parsed.
+    #[cfg(feature = "manifests")]
+    InvalidManifest {
+        /// What is wrong with the manifest
+        reason: String,
+    },
+
+    /// The format can't be downloaded by `FragmentDownloader`.
+    #[cfg(any(feature = "downloader-rustls-tls", feature = "downloader-native-tls"))]
+    UnsupportedFormat {
+        /// Why the format is not supported
+        reason: String,
+    },
+}
+
+impl Error {
+    /// `Error::ExitCode` (or `Error::Killed`), or a more specific error recognized from the
+    /// output.
+    fn from_exit_status(status: ExitStatus, stderr: String) -> Self {
+        if let Some(error) = upcoming::parse_error(&stderr) {
+            return error;
+        }
+        match status.code() {
+            Some(code) => Error::ExitCode { code, stderr },
+            None => Error::Killed {
+                signal: report::signal(status),
+                stderr,
+            },
+        }
+    }
+
+    /// The meaning of yt-dlp's exit code, for `Error::ExitCode` and `Error::Killed`.
+    pub fn exit_reason(&self) -> Option<ExitReason> {
+        match self {
+            Self::ExitCode { code, .. } => Some(ExitReason::from_code(*code)),
+            Self::Killed { signal, .. } => Some(ExitReason::Signal(*signal)),
+            _ => None,
+        }
+    }
 }
 
 impl From<std::io::Error> for Error {
@@ -113,6 +341,13 @@ impl From<serde_json::Error> for Error {
     }
 }
 
+#[cfg(feature = "sqlite-archive")]
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        Error::Sqlite(err)
+    }
+}
+
 #[cfg(any(feature = "downloader-rustls-tls", feature = "downloader-native-tls"))]
 impl From<reqwest::Error> for Error {
     fn from(err: reqwest::Error) -> Self {
@@ -128,11 +363,65 @@ impl fmt::Display for Error {
             Self::ExitCode { code, stderr } => {
                 write!(f, "non-zero exit code: {}, stderr: {}", code, stderr)
             }
+            Self::Killed { signal, stderr } => {
+                write!(f, "terminated by signal {}, stderr: {}", signal, stderr)
+            }
             Self::ProcessTimeout => write!(f, "process timed out"),
+            Self::SpawnFailed {
+                program,
+                search_path,
+                not_executable,
+                error,
+            } => {
+                write!(f, "failed to start {}: {}", program.display(), error)?;
+                match search_path {
+                    _ if *not_executable => write!(f, " (the file is not executable)"),
+                    Some(search_path) => {
+                        write!(f, " (searched PATH: {})", search_path.to_string_lossy())
+                    }
+                    None => Ok(()),
+                }
+            }
+            Self::InsufficientDiskSpace {
+                required,
+                available,
+            } => write!(
+                f,
+                "insufficient disk space: {} bytes required, {} bytes available",
+                required, available
+            ),
+            Self::OutputTooLarge { limit } => {
+                write!(f, "output of youtube-dl exceeded {} bytes", limit)
+            }
+            Self::NoMatchingFormat => write!(f, "no matching format found"),
+            Self::NoSuchChapter => write!(f, "no such chapter"),
+            Self::EntryFailed { message } => write!(f, "entry failed: {}", message),
+            Self::NotYetAvailable { starts_at } => match starts_at
+                .and_then(|starts_at| starts_at.duration_since(SystemTime::now()).ok())
+            {
+                Some(duration) => write!(
+                    f,
+                    "video is not available yet, starts in {} minutes",
+                    duration.as_secs().div_ceil(60)
+                ),
+                None => write!(f, "video is not available yet"),
+            },
+            Self::UnsupportedModelVersion { version } => {
+                write!(f, "unsupported model version: {}", version)
+            }
+            Self::UnmodeledFields { fields } => {
+                write!(f, "fields missing from the model: {}", fields.join(", "))
+            }
             #[cfg(any(feature = "downloader-rustls-tls", feature = "downloader-native-tls"))]
             Self::Http(err) => write!(f, "http error: {}", err),
             #[cfg(any(feature = "downloader-rustls-tls", feature = "downloader-native-tls"))]
             Self::NoReleaseFound => write!(f, "no github release found for specified binary"),
+            #[cfg(feature = "sqlite-archive")]
+            Self::Sqlite(err) => write!(f, "sqlite error: {}", err),
+            #[cfg(feature = "manifests")]
+            Self::InvalidManifest { reason } => write!(f, "invalid manifest: {}", reason),
+            #[cfg(any(feature = "downloader-rustls-tls", feature = "downloader-native-tls"))]
+            Self::UnsupportedFormat { reason } => write!(f, "unsupported format: {}", reason),
         }
     }
 }
@@ -143,11 +432,27 @@ impl StdError for Error {
             Self::Io(err) => Some(err),
             Self::Json(err) => Some(err),
             Self::ExitCode { .. } => None,
+            Self::Killed { .. } => None,
             Self::ProcessTimeout => None,
+            Self::SpawnFailed { error, .. } => Some(error),
+            Self::InsufficientDiskSpace { .. } => None,
+            Self::OutputTooLarge { .. } => None,
+            Self::NoMatchingFormat => None,
+            Self::NoSuchChapter => None,
+            Self::EntryFailed { .. } => None,
+            Self::NotYetAvailable { .. } => None,
+            Self::UnsupportedModelVersion { .. } => None,
+            Self::UnmodeledFields { .. } => None,
             #[cfg(any(feature = "downloader-rustls-tls", feature = "downloader-native-tls"))]
             Self::Http(err) => Some(err),
             #[cfg(any(feature = "downloader-rustls-tls", feature = "downloader-native-tls"))]
             Self::NoReleaseFound => None,
+            #[cfg(feature = "sqlite-archive")]
+            Self::Sqlite(err) => Some(err),
+            #[cfg(feature = "manifests")]
+            Self::InvalidManifest { .. } => None,
+            #[cfg(any(feature = "downloader-rustls-tls", feature = "downloader-native-tls"))]
+            Self::UnsupportedFormat { .. } => None,
         }
     }
 }
@@ -246,10 +551,66 @@ impl fmt::Display for SearchOptions {
     }
 }
 
+/// How yt-dlp is executed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BinaryInvocation {
+    /// Run a standalone executable (e.g. `yt-dlp` from `PATH`).
+    Executable(PathBuf),
+    /// Run yt-dlp as a Python module with the given interpreter (`python3 -m yt_dlp`), e.g.
+    /// when it was installed with pip and there is no executable in `PATH`.
+    /// With `Flavor::YoutubeDl`, the `youtube_dl` module is used.
+    PythonModule {
+        /// Path to the Python interpreter
+        interpreter: PathBuf,
+    },
+}
+
+/// Which program is executed. This determines the default executable and the generated arguments.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Flavor {
+    /// yt-dlp (the default), including its nightly builds
+    #[default]
+    YtDlp,
+    /// The original youtube-dl. Its output directory is set with `-o` instead of `-P`, and
+    /// progress is parsed from its regular output. Options that only exist in yt-dlp (like
+    /// `plugin_dirs` or `compat_options`, also in `extra_arg`) are left out with a warning,
+    /// `on_file_downloaded` is not supported, and `run_and_download` only works for single
+    /// videos.
+    YoutubeDl,
+}
+
+impl Flavor {
+    fn executable(self) -> &'static str {
+        match self {
+            Flavor::YtDlp => "yt-dlp",
+            Flavor::YoutubeDl => "youtube-dl",
+        }
+    }
+
+    fn module(self) -> &'static str {
+        match self {
+            Flavor::YtDlp => "yt_dlp",
+            Flavor::YoutubeDl => "youtube_dl",
+        }
+    }
+}
+
+/// youtube-dl's default output template, used to download into a folder without `-P`.
+const LEGACY_OUTPUT_TEMPLATE: &str = "%(title)s-%(id)s.%(ext)s";
+
+/// The audio format of `normalize_audio`, which always re-encodes YouTube's audio.
+const LOUDNESS_AUDIO_FORMAT: &str = "mp3";
+
+/// The prefix of the extractor argument that selects the YouTube player clients.
+const PLAYER_CLIENT_ARG: &str = "youtube:player_client=";
+
 /// A builder to create a `youtube-dl` command to execute.
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct YoutubeDl {
-    youtube_dl_path: Option<PathBuf>,
+    invocation: Option<BinaryInvocation>,
+    fallback_paths: Vec<PathBuf>,
+    flavor: Flavor,
+    command_prefix: Vec<String>,
     format: Option<String>,
     flat_playlist: bool,
     socket_timeout: Option<String>,
@@ -257,6 +618,8 @@ pub struct YoutubeDl {
     auth: Option<(String, String)>,
     cookies: Option<String>,
     cookies_from_browser: Option<String>,
+    /// The value of `--extractor-args youtube:player_client=...`, see `add_player_clients`
+    player_clients: Option<String>,
     user_agent: Option<String>,
     referer: Option<String>,
     url: String,
@@ -266,14 +629,167 @@ pub struct YoutubeDl {
     date_after: Option<String>,
     date: Option<String>,
     extract_audio: bool,
+    audio_format: Option<String>,
+    loudness: Option<String>,
+    ffmpeg_location: Option<String>,
+    preview: Option<PreviewSpec>,
+    ignore_no_formats_error: bool,
+    allow_unplayable_formats: bool,
+    audio_multistreams: bool,
+    video_multistreams: bool,
+    simulate: bool,
+    skip_download: bool,
+    write_links: Vec<LinkFormat>,
+    compat_options: Option<String>,
+    http_chunk_size: Option<String>,
+    buffer_size: Option<String>,
+    no_resize_buffer: bool,
+    check_formats: Option<CheckFormats>,
+    plugin_dirs: Vec<String>,
+    no_plugins: bool,
+    postprocessors: Vec<String>,
     playlist_items: Option<String>,
     max_downloads: Option<String>,
-    extra_args: Vec<String>,
+    extra_args: Vec<ExtraArg>,
     output_template: Option<String>,
     output_directory: Option<String>,
     #[cfg(test)]
     debug: bool,
     ignore_errors: bool,
+    record_dir: Option<PathBuf>,
+    replay_dir: Option<PathBuf>,
+    hooks: Option<HooksHandle>,
+    cache: Option<MetadataCache>,
+    limiter: Option<Limiter>,
+    on_file_downloaded: Option<FileCallback>,
+    before_spawn: Option<SpawnCallback>,
+    #[cfg(feature = "tokio")]
+    before_spawn_async: Option<AsyncSpawnCallback>,
+    tee_stdout: Option<Tee>,
+    tee_stderr: Option<Tee>,
+    #[cfg(target_os = "windows")]
+    creation_flags: u32,
+    #[cfg(unix)]
+    run_as: Option<(u32, u32)>,
+    #[cfg(unix)]
+    nice: Option<String>,
+    required_free_space: Option<u64>,
+    resume_state: Option<String>,
+    isolated_temp: bool,
+    /// The `-P temp:` and `--cache-dir` values of an isolated download, set by `isolate`
+    temp_paths: Option<(String, String)>,
+    collision_policy: Option<CollisionPolicy>,
+    #[cfg(feature = "checksum")]
+    verify_downloads: Option<HashAlgo>,
+    measure_bandwidth: bool,
+    clip_as_full_video: bool,
+    download_sections: Vec<String>,
+    /// Keeps the cookies file written by `cookies_from_jar` until the builder is dropped.
+    #[cfg(feature = "cookies")]
+    cookie_file: Option<Arc<tempfile::TempPath>>,
+    print_to_file: Vec<(String, String)>,
+    load_info_json: Option<String>,
+    throttle: Option<ThrottleMitigation>,
+    max_stdout_bytes: Option<u64>,
+    capture_stderr_lines: Option<usize>,
+    diagnostics: bool,
+    strict_model: bool,
+}
+
+/// Like the derived implementation, but without passwords, cookies and secret extra arguments.
+impl fmt::Debug for YoutubeDl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("YoutubeDl");
+        f.field("invocation", &self.invocation);
+        f.field("fallback_paths", &self.fallback_paths);
+        f.field("flavor", &self.flavor);
+        f.field("command_prefix", &self.command_prefix);
+        f.field("format", &self.format);
+        f.field("flat_playlist", &self.flat_playlist);
+        f.field("socket_timeout", &self.socket_timeout);
+        f.field("all_formats", &self.all_formats);
+        f.field(
+            "auth",
+            &self.auth.as_ref().map(|(user, _)| (user, REDACTED)),
+        );
+        f.field("cookies", &self.cookies.as_ref().map(|_| REDACTED));
+        f.field("cookies_from_browser", &self.cookies_from_browser);
+        f.field("player_clients", &self.player_clients);
+        f.field("user_agent", &self.user_agent);
+        f.field("referer", &self.referer);
+        f.field("url", &self.url);
+        f.field("process_timeout", &self.process_timeout);
+        f.field("playlist_reverse", &self.playlist_reverse);
+        f.field("date_before", &self.date_before);
+        f.field("date_after", &self.date_after);
+        f.field("date", &self.date);
+        f.field("extract_audio", &self.extract_audio);
+        f.field("audio_format", &self.audio_format);
+        f.field("loudness", &self.loudness);
+        f.field("ffmpeg_location", &self.ffmpeg_location);
+        f.field("preview", &self.preview);
+        f.field("ignore_no_formats_error", &self.ignore_no_formats_error);
+        f.field("allow_unplayable_formats", &self.allow_unplayable_formats);
+        f.field("audio_multistreams", &self.audio_multistreams);
+        f.field("video_multistreams", &self.video_multistreams);
+        f.field("simulate", &self.simulate);
+        f.field("skip_download", &self.skip_download);
+        f.field("write_links", &self.write_links);
+        f.field("compat_options", &self.compat_options);
+        f.field("http_chunk_size", &self.http_chunk_size);
+        f.field("buffer_size", &self.buffer_size);
+        f.field("no_resize_buffer", &self.no_resize_buffer);
+        f.field("check_formats", &self.check_formats);
+        f.field("plugin_dirs", &self.plugin_dirs);
+        f.field("no_plugins", &self.no_plugins);
+        f.field("postprocessors", &self.postprocessors);
+        f.field("playlist_items", &self.playlist_items);
+        f.field("max_downloads", &self.max_downloads);
+        f.field(
+            "extra_args",
+            &args::redact(&args::flatten(&self.extra_args)),
+        );
+        f.field("output_template", &self.output_template);
+        f.field("output_directory", &self.output_directory);
+        #[cfg(test)]
+        f.field("debug", &self.debug);
+        f.field("ignore_errors", &self.ignore_errors);
+        f.field("record_dir", &self.record_dir);
+        f.field("replay_dir", &self.replay_dir);
+        f.field("hooks", &self.hooks);
+        f.field("cache", &self.cache);
+        f.field("limiter", &self.limiter);
+        f.field("on_file_downloaded", &self.on_file_downloaded);
+        f.field("before_spawn", &self.before_spawn);
+        #[cfg(feature = "tokio")]
+        f.field("before_spawn_async", &self.before_spawn_async);
+        f.field("tee_stdout", &self.tee_stdout);
+        f.field("tee_stderr", &self.tee_stderr);
+        #[cfg(target_os = "windows")]
+        f.field("creation_flags", &self.creation_flags);
+        #[cfg(unix)]
+        f.field("run_as", &self.run_as);
+        #[cfg(unix)]
+        f.field("nice", &self.nice);
+        f.field("required_free_space", &self.required_free_space);
+        f.field("resume_state", &self.resume_state);
+        f.field("isolated_temp", &self.isolated_temp);
+        f.field("temp_paths", &self.temp_paths);
+        f.field("collision_policy", &self.collision_policy);
+        #[cfg(feature = "checksum")]
+        f.field("verify_downloads", &self.verify_downloads);
+        f.field("measure_bandwidth", &self.measure_bandwidth);
+        f.field("clip_as_full_video", &self.clip_as_full_video);
+        f.field("download_sections", &self.download_sections);
+        f.field("print_to_file", &self.print_to_file);
+        f.field("load_info_json", &self.load_info_json);
+        f.field("throttle", &self.throttle);
+        f.field("max_stdout_bytes", &self.max_stdout_bytes);
+        f.field("capture_stderr_lines", &self.capture_stderr_lines);
+        f.field("diagnostics", &self.diagnostics);
+        f.field("strict_model", &self.strict_model);
+        f.finish()
+    }
 }
 
 impl YoutubeDl {
@@ -281,7 +797,10 @@ impl YoutubeDl {
     pub fn new(url: impl Into<String>) -> Self {
         Self {
             url: url.into(),
-            youtube_dl_path: None,
+            invocation: None,
+            fallback_paths: vec![],
+            flavor: Flavor::default(),
+            command_prefix: vec![],
             format: None,
             flat_playlist: false,
             socket_timeout: None,
@@ -289,6 +808,7 @@ impl YoutubeDl {
             auth: None,
             cookies: None,
             cookies_from_browser: None,
+            player_clients: None,
             user_agent: None,
             referer: None,
             process_timeout: None,
@@ -297,6 +817,25 @@ impl YoutubeDl {
             date_before: None,
             playlist_reverse: false,
             extract_audio: false,
+            audio_format: None,
+            loudness: None,
+            ffmpeg_location: None,
+            preview: None,
+            ignore_no_formats_error: false,
+            allow_unplayable_formats: false,
+            audio_multistreams: false,
+            video_multistreams: false,
+            simulate: false,
+            skip_download: false,
+            write_links: vec![],
+            compat_options: None,
+            http_chunk_size: None,
+            buffer_size: None,
+            no_resize_buffer: false,
+            check_formats: None,
+            plugin_dirs: vec![],
+            no_plugins: false,
+            postprocessors: vec![],
             playlist_items: None,
             max_downloads: None,
             extra_args: Vec::new(),
@@ -305,6 +844,42 @@ impl YoutubeDl {
             #[cfg(test)]
             debug: false,
             ignore_errors: false,
+            record_dir: None,
+            replay_dir: None,
+            hooks: None,
+            cache: None,
+            limiter: None,
+            on_file_downloaded: None,
+            before_spawn: None,
+            #[cfg(feature = "tokio")]
+            before_spawn_async: None,
+            tee_stdout: None,
+            tee_stderr: None,
+            #[cfg(target_os = "windows")]
+            creation_flags: CREATE_NO_WINDOW,
+            #[cfg(unix)]
+            run_as: None,
+            #[cfg(unix)]
+            nice: None,
+            required_free_space: None,
+            resume_state: None,
+            isolated_temp: false,
+            temp_paths: None,
+            collision_policy: None,
+            #[cfg(feature = "checksum")]
+            verify_downloads: None,
+            measure_bandwidth: false,
+            clip_as_full_video: false,
+            download_sections: vec![],
+            #[cfg(feature = "cookies")]
+            cookie_file: None,
+            print_to_file: vec![],
+            load_info_json: None,
+            throttle: None,
+            max_stdout_bytes: None,
+            capture_stderr_lines: None,
+            diagnostics: false,
+            strict_model: false,
         }
     }
 
@@ -315,7 +890,82 @@ impl YoutubeDl {
 
     /// Set the path to the `youtube-dl` or `yt-dlp executable.
     pub fn youtube_dl_path<P: AsRef<Path>>(&mut self, youtube_dl_path: P) -> &mut Self {
-        self.youtube_dl_path = Some(youtube_dl_path.as_ref().to_owned());
+        self.invocation = Some(BinaryInvocation::Executable(
+            youtube_dl_path.as_ref().to_owned(),
+        ));
+        self
+    }
+
+    /// Set how yt-dlp is executed, e.g. as a Python module. Defaults to the `yt-dlp`
+    /// executable in `PATH` (or `youtube-dl` with `Flavor::YoutubeDl`).
+    pub fn invocation(&mut self, invocation: BinaryInvocation) -> &mut Self {
+        self.invocation = Some(invocation);
+        self
+    }
+
+    /// Executables to try in order when yt-dlp fails because an extractor is broken (the errors
+    /// it asks to report upstream), e.g. a nightly build next to the stable one. A failed run is
+    /// only repeated if it produced no files or entries yet, and `Hooks::on_retry` is called
+    /// before each repetition, so the hooks can tell the attempts apart.
+    pub fn fallback_paths<I, P>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.fallback_paths = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set which program is executed, e.g. `Flavor::YoutubeDl` for the original youtube-dl.
+    /// The generated arguments are adjusted to what it supports.
+    pub fn flavor(&mut self, flavor: Flavor) -> &mut Self {
+        self.flavor = flavor;
+        self
+    }
+
+    /// Add directories to search for yt-dlp plugins (`--plugin-dirs`).
+    pub fn plugin_dirs<I, P>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        self.plugin_dirs.extend(
+            paths
+                .into_iter()
+                .map(|path| path.as_ref().to_string_lossy().into_owned()),
+        );
+        self
+    }
+
+    /// Set the `--no-plugin-dirs` command line flag, which disables searching the default plugin
+    /// directories. Directories added with `plugin_dirs` are still searched.
+    pub fn no_plugins(&mut self, no_plugins: bool) -> &mut Self {
+        self.no_plugins = no_plugins;
+        self
+    }
+
+    /// Run a postprocessor (e.g. `ModifyChapters` or one from a plugin) at the given stage, with
+    /// `args` as its keyword arguments (`--use-postprocessor NAME:when=WHEN;KEY=VALUE`).
+    /// Can be called multiple times.
+    pub fn use_postprocessor<I, K, V>(
+        &mut self,
+        name: &str,
+        when: PostprocessorStage,
+        args: I,
+    ) -> &mut Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut postprocessor = format!("{}:when={}", name, when.as_str());
+        for (key, value) in args {
+            postprocessor.push(';');
+            postprocessor.push_str(key.as_ref());
+            postprocessor.push('=');
+            postprocessor.push_str(value.as_ref());
+        }
+        self.postprocessors.push(postprocessor);
         self
     }
 
@@ -337,6 +987,34 @@ impl YoutubeDl {
         self
     }
 
+    /// Set the `--http-chunk-size` command line flag: download HTTP formats in chunks of this size,
+    /// which helps against throttling by some servers.
+    pub fn http_chunk_size(&mut self, size: Bytes) -> &mut Self {
+        self.http_chunk_size = Some(size.0.to_string());
+        self
+    }
+
+    /// Set the `--buffer-size` command line flag (the initial download buffer size).
+    pub fn buffer_size(&mut self, size: Bytes) -> &mut Self {
+        self.buffer_size = Some(size.0.to_string());
+        self
+    }
+
+    /// Set the `--no-resize-buffer` command line flag, which keeps the buffer at the size set
+    /// with `buffer_size` instead of adjusting it automatically.
+    pub fn no_resize_buffer(&mut self, no_resize_buffer: bool) -> &mut Self {
+        self.no_resize_buffer = no_resize_buffer;
+        self
+    }
+
+    /// Set whether yt-dlp checks that formats are downloadable before selecting them
+    /// (`--check-formats`, `--check-all-formats` or `--no-check-formats`). This helps when
+    /// stale format URLs make downloads fail with HTTP 403.
+    pub fn check_formats(&mut self, check_formats: CheckFormats) -> &mut Self {
+        self.check_formats = Some(check_formats);
+        self
+    }
+
     /// Set the `--user-agent` command line flag.
     pub fn user_agent<S: Into<String>>(&mut self, user_agent: S) -> &mut Self {
         self.user_agent = Some(user_agent.into());
@@ -435,6 +1113,101 @@ impl YoutubeDl {
         self
     }
 
+    /// Set the `--audio-format` command line flag: the format that `extract_audio` converts to,
+    /// like `mp3`, `opus` or `best` (the default, which keeps the downloaded format if possible).
+    pub fn audio_format<S: Into<String>>(&mut self, format: S) -> &mut Self {
+        self.audio_format = Some(format.into());
+        self
+    }
+
+    /// Set the `--ffmpeg-location` command line flag: the ffmpeg executable or the directory
+    /// containing it. Also used by `generate_preview`.
+    pub fn ffmpeg_location<P: AsRef<Path>>(&mut self, location: P) -> &mut Self {
+        self.ffmpeg_location = Some(location.as_ref().to_string_lossy().into_owned());
+        self
+    }
+
+    /// After downloading, make an animated GIF preview of each downloaded video with ffmpeg
+    /// (`<name>.preview.gif` next to the video). The previews are listed in `RunReport::previews`,
+    /// and videos whose preview failed in `RunReport::preview_failures`. `run_and_download` makes
+    /// the previews of the files in `SingleVideo::requested_downloads` and logs the failures.
+    pub fn generate_preview(&mut self, spec: PreviewSpec) -> &mut Self {
+        self.preview = Some(spec);
+        self
+    }
+
+    /// Extract the audio and normalize its loudness to the target, by passing ffmpeg's `loudnorm`
+    /// filter to the `ExtractAudio` postprocessor. Filtering requires re-encoding, so the audio is
+    /// converted to mp3 unless another `audio_format` is set. yt-dlp copies the audio stream
+    /// instead if it already has that format, which fails with the filter, so `best` and the
+    /// formats that YouTube serves (`m4a`, `opus`) don't work.
+    pub fn normalize_audio(&mut self, target: LoudnessTarget) -> &mut Self {
+        self.loudness = Some(format!("ExtractAudio+ffmpeg_o:-af {}", target.filter()));
+        self
+    }
+
+    /// Set the `--ignore-no-formats-error` command line flag, so that the metadata (title,
+    /// description, ...) of videos without any downloadable formats (e.g. DRM protected or
+    /// upcoming videos) is still returned instead of failing.
+    pub fn ignore_no_formats_error(&mut self, ignore_no_formats_error: bool) -> &mut Self {
+        self.ignore_no_formats_error = ignore_no_formats_error;
+        self
+    }
+
+    /// Set the `--allow-unplayable-formats` command line flag, which lists formats that can't be
+    /// played after downloading (e.g. DRM protected ones). Their downloads are usually useless.
+    pub fn allow_unplayable_formats(&mut self, allow_unplayable_formats: bool) -> &mut Self {
+        self.allow_unplayable_formats = allow_unplayable_formats;
+        self
+    }
+
+    /// Set the `--audio-multistreams` command line flag, which allows merging multiple audio
+    /// streams into one file (e.g. `bv+ba+ba.2` for two audio tracks). The merged formats are
+    /// listed in `SingleVideo::requested_formats`.
+    pub fn audio_multistreams(&mut self, audio_multistreams: bool) -> &mut Self {
+        self.audio_multistreams = audio_multistreams;
+        self
+    }
+
+    /// Set the `--video-multistreams` command line flag, which allows merging multiple video
+    /// streams into one file.
+    pub fn video_multistreams(&mut self, video_multistreams: bool) -> &mut Self {
+        self.video_multistreams = video_multistreams;
+        self
+    }
+
+    /// Set the `--simulate` command line flag for `download_to`: run the whole download process
+    /// (including the reports of `download_to`) without downloading or writing any files.
+    pub fn simulate(&mut self, simulate: bool) -> &mut Self {
+        self.simulate = simulate;
+        self
+    }
+
+    /// Set the `--skip-download` command line flag for `download_to`: don't download the media,
+    /// but still write the other requested files, e.g. with `--write-info-json` or `--write-subs`.
+    pub fn skip_download(&mut self, skip_download: bool) -> &mut Self {
+        self.skip_download = skip_download;
+        self
+    }
+
+    /// Write an internet shortcut to the source page next to each download
+    /// (`--write-link`, `--write-url-link`, ...). Can be called multiple times to write
+    /// shortcuts in several formats.
+    pub fn write_link(&mut self, format: LinkFormat) -> &mut Self {
+        if !self.write_links.contains(&format) {
+            self.write_links.push(format);
+        }
+        self
+    }
+
+    /// Set the `--compat-options` command line flag to restore legacy behaviors of yt-dlp.
+    /// An empty list removes the flag.
+    pub fn compat_options(&mut self, options: &[CompatOption]) -> &mut Self {
+        let options: Vec<_> = options.iter().map(CompatOption::as_str).collect();
+        self.compat_options = (!options.is_empty()).then(|| options.join(","));
+        self
+    }
+
     /// Set the `--playlist-items` command line flag.
     pub fn playlist_items(&mut self, index: u32) -> &mut Self {
         self.playlist_items = Some(index.to_string());
@@ -447,15 +1220,111 @@ impl YoutubeDl {
         self
     }
 
+    /// Set up access to age-restricted videos (which fail with `ErrorKind::AgeRestricted`
+    /// otherwise), either with the cookies of an account that has confirmed its age or with a
+    /// player client that doesn't require signing in.
+    ///
+    /// ```rust,no_run
+    /// use youtube_dl::{AgeGateBypass, YoutubeDl};
+    ///
+    /// let output = YoutubeDl::new("https://www.youtube.com/watch?v=VFbhKZFzbzk")
+    ///     .use_age_gate_bypass(AgeGateBypass::CookiesFromBrowser("firefox".into()))
+    ///     .run();
+    /// ```
+    pub fn use_age_gate_bypass(&mut self, bypass: AgeGateBypass) -> &mut Self {
+        match bypass {
+            AgeGateBypass::CookiesFile(path) => self.cookies(path),
+            AgeGateBypass::CookiesFromBrowser(browser) => {
+                self.cookies_from_browser(browser, None, None, None)
+            }
+            AgeGateBypass::EmbeddedClient => {
+                self.add_player_clients(&["web_embedded", "default"], false);
+                self
+            }
+        }
+    }
+
+    /// Add YouTube player clients to `--extractor-args youtube:player_client=...`, before the
+    /// clients that are already set if `first`. yt-dlp tries them in order.
+    fn add_player_clients(&mut self, clients: &[&str], first: bool) {
+        let current = self
+            .player_clients
+            .as_deref()
+            .and_then(|arg| arg.strip_prefix(PLAYER_CLIENT_ARG))
+            .map_or(vec![], |set| set.split(',').collect());
+        let (before, after) = if first {
+            (clients, &current[..])
+        } else {
+            (&current[..], clients)
+        };
+        let mut merged: Vec<&str> = vec![];
+        for client in before.iter().chain(after) {
+            if !merged.contains(client) {
+                merged.push(client);
+            }
+        }
+        self.player_clients = Some(format!("{}{}", PLAYER_CLIENT_ARG, merged.join(",")));
+    }
+
     /// Add an additional custom CLI argument.
     ///
     /// This allows specifying arguments that are not covered by other
-    /// configuration methods.
+    /// configuration methods. If an option is also set by the builder (e.g. `-f`),
+    /// the value given here is used.
+    ///
+    /// Each call adds a single argument, so an option and its value must be passed separately
+    /// (or with `extra_arg_pair`): `"--audio-format mp3"` is passed to yt-dlp as one argument
+    /// and logs a warning.
     pub fn extra_arg<S: Into<String>>(&mut self, arg: S) -> &mut Self {
-        self.extra_args.push(arg.into());
+        let arg = arg.into();
+        let name = arg.split('=').next().unwrap_or_default();
+        if arg.starts_with('-') && name.contains(char::is_whitespace) {
+            trace::split_extra_arg(&arg);
+        }
+        self.extra_args.push(ExtraArg::Single(arg));
+        self
+    }
+
+    /// Add a custom CLI option with its value, e.g. `extra_arg_pair("--audio-format", "mp3")`.
+    pub fn extra_arg_pair<F, V>(&mut self, flag: F, value: V) -> &mut Self
+    where
+        F: Into<String>,
+        V: Into<String>,
+    {
+        self.extra_args
+            .push(ExtraArg::Pair(flag.into(), value.into()));
+        self
+    }
+
+    /// Add the arguments of custom typed options (see `IntoYtDlpArgs`), like with `extra_args`.
+    pub fn apply_args<A: IntoYtDlpArgs>(&mut self, options: A) -> &mut Self {
+        self.extra_args(options.into_args())
+    }
+
+    /// Add multiple custom CLI arguments, each one as a separate argument (see `extra_arg`).
+    pub fn extra_args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        for arg in args {
+            self.extra_arg(arg);
+        }
         self
     }
 
+    /// The arguments `run` passes to yt-dlp, after combining the builder options with
+    /// the extra arguments. Useful for logging or checking the generated command.
+    pub fn effective_args(&self) -> Vec<String> {
+        self.process_args().into_iter().map(String::from).collect()
+    }
+
+    /// Like `effective_args`, but with passwords, cookie files and header values replaced by
+    /// `<redacted>`, for logging the command line.
+    pub fn redacted_args(&self) -> Vec<String> {
+        args::redact(&self.process_args())
+    }
+
     /// Specify the filename template. Only relevant for downloading.
     /// (referred to as "output template" by [youtube-dl docs](https://github.com/ytdl-org/youtube-dl#output-template))
     pub fn output_template<S: Into<String>>(&mut self, arg: S) -> &mut Self {
@@ -463,6 +1332,22 @@ impl YoutubeDl {
         self
     }
 
+    /// Append a line formatted with the output template to the file for each download
+    /// (`--print-to-file`), e.g. `after_move:%(id)s\t%(filepath)s` to write a manifest of the
+    /// downloaded files. Can be used multiple times. Only relevant for downloading, and only
+    /// supported by yt-dlp.
+    pub fn print_to_file<S: Into<String>, P: AsRef<Path>>(
+        &mut self,
+        template: S,
+        path: P,
+    ) -> &mut Self {
+        self.print_to_file.push((
+            template.into(),
+            path.as_ref().to_string_lossy().into_owned(),
+        ));
+        self
+    }
+
     /// Specify the output directory. Only relevant for downloading.
     /// (the `-P` command line switch)
     pub fn output_directory<S: Into<String>>(&mut self, arg: S) -> &mut Self {
@@ -470,6 +1355,7 @@ impl YoutubeDl {
         self
     }
 
+    /// Print the raw JSON output to stderr before parsing it (test builds only).
     #[cfg(test)]
     pub fn debug(&mut self, arg: bool) -> &mut Self {
         self.debug = arg;
@@ -482,324 +1368,759 @@ impl YoutubeDl {
         self
     }
 
-    fn path(&self) -> &Path {
-        match &self.youtube_dl_path {
-            Some(path) => path,
-            None => Path::new("yt-dlp"),
-        }
+    /// Save the JSON output of every successful `run`/`run_raw` call to the given directory,
+    /// keyed by a hash of the URL and arguments, so it can later be served with `replay_from`.
+    /// Downloads (`download_to`, `run_and_download`) are not recorded.
+    pub fn record_to<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.record_dir = Some(dir.as_ref().to_owned());
+        self
     }
 
-    fn common_args(&self) -> Vec<&str> {
-        let mut args = vec![];
-        if let Some(format) = &self.format {
-            args.push("-f");
-            args.push(format);
-        }
+    /// Serve `run`/`run_raw` calls from fixtures previously saved with `record_to` instead of
+    /// spawning yt-dlp. Fails with an I/O error if no fixture exists for the URL and arguments.
+    /// Downloads (`download_to`, `run_and_download`) still run yt-dlp.
+    pub fn replay_from<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.replay_dir = Some(dir.as_ref().to_owned());
+        self
+    }
 
-        if self.flat_playlist {
-            args.push("--flat-playlist");
-        }
+    /// Register callbacks that are invoked when yt-dlp is spawned, exits or reports download
+    /// progress. Wrap the hooks in an `Arc` to share them between builders.
+    pub fn hooks<H: Hooks + 'static>(&mut self, hooks: H) -> &mut Self {
+        self.hooks = Some(HooksHandle(Arc::new(hooks)));
+        self
+    }
 
-        if let Some(timeout) = &self.socket_timeout {
-            args.push("--socket-timeout");
-            args.push(timeout);
-        }
+    /// Look up the results of `run` (and `run_raw`) in `cache` before running yt-dlp, and store
+    /// them there afterwards. The cache is shared with all builders it is passed to.
+    pub fn cache(&mut self, cache: &MetadataCache) -> &mut Self {
+        self.cache = Some(cache.clone());
+        self
+    }
 
-        if self.all_formats {
-            args.push("--all-formats");
-        }
+    /// Wait for `limiter` before each start of yt-dlp, so that it is not started too often for
+    /// the same site. The limiter is shared with all builders it is passed to.
+    pub fn limiter(&mut self, limiter: &Limiter) -> &mut Self {
+        self.limiter = Some(limiter.clone());
+        self
+    }
 
-        if let Some((user, password)) = &self.auth {
-            args.push("-u");
-            args.push(user);
-            args.push("-p");
-            args.push(password);
-        }
+    /// Call `callback` with the final path of each file once it is downloaded, postprocessed and
+    /// moved to its destination, e.g. to continue processing it in Rust instead of using `--exec`.
+    /// Only used by `download_to` and the methods based on it.
+    pub fn on_file_downloaded<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: Fn(&Path) + Send + Sync + 'static,
+    {
+        self.on_file_downloaded = Some(FileCallback(Arc::new(callback)));
+        self
+    }
 
-        if let Some(cookie_path) = &self.cookies {
-            args.push("--cookies");
-            args.push(cookie_path);
-        }
+    /// Customize the command before yt-dlp is started, e.g. to set environment variables, the
+    /// working directory or (on Unix) a `pre_exec` function. Used by the synchronous and the
+    /// asynchronous methods, which convert the command to a `tokio::process::Command`.
+    pub fn before_spawn<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: Fn(&mut std::process::Command) + Send + Sync + 'static,
+    {
+        self.before_spawn = Some(SpawnCallback(Arc::new(callback)));
+        self
+    }
 
-        if let Some(cookies_from_browser) = &self.cookies_from_browser {
-            args.push("--cookies-from-browser");
-            args.push(cookies_from_browser);
-        }
+    /// Customize the `tokio::process::Command` before yt-dlp is started by the asynchronous
+    /// methods (after `before_spawn`), e.g. to set `kill_on_drop`.
+    #[cfg(feature = "tokio")]
+    pub fn before_spawn_async<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: Fn(&mut tokio::process::Command) + Send + Sync + 'static,
+    {
+        self.before_spawn_async = Some(AsyncSpawnCallback(Arc::new(callback)));
+        self
+    }
 
-        if let Some(user_agent) = &self.user_agent {
-            args.push("--user-agent");
-            args.push(user_agent);
-        }
+    /// Copy the raw stdout of yt-dlp to `writer` as it is read, e.g. to a log file to diagnose
+    /// output that can't be parsed. The output is still parsed as usual. The writer is shared by
+    /// all clones of the builder.
+    pub fn tee_stdout<W: std::io::Write + Send + 'static>(&mut self, writer: W) -> &mut Self {
+        self.tee_stdout = Some(Tee::new(writer));
+        self
+    }
 
-        if let Some(referer) = &self.referer {
-            args.push("--referer");
-            args.push(referer);
-        }
+    /// Copy the raw stderr of yt-dlp to `writer` as it is read, see `tee_stdout`.
+    pub fn tee_stderr<W: std::io::Write + Send + 'static>(&mut self, writer: W) -> &mut Self {
+        self.tee_stderr = Some(Tee::new(writer));
+        self
+    }
 
-        if self.extract_audio {
-            args.push("--extract-audio");
+    /// Whether to hide the console window of yt-dlp (the `CREATE_NO_WINDOW` creation flag).
+    /// The default is `true`.
+    #[cfg(target_os = "windows")]
+    pub fn hide_console(&mut self, hide_console: bool) -> &mut Self {
+        if hide_console {
+            self.creation_flags |= CREATE_NO_WINDOW;
+        } else {
+            self.creation_flags &= !CREATE_NO_WINDOW;
         }
+        self
+    }
 
-        if let Some(playlist_items) = &self.playlist_items {
-            args.push("--playlist-items");
-            args.push(playlist_items);
-        }
+    /// Run yt-dlp through another command, e.g. `["firejail"]` or `["docker", "exec", "dl"]` to
+    /// run it in a sandbox or container. The prefix comes before the yt-dlp invocation, and all
+    /// paths passed to yt-dlp must be valid where it runs.
+    pub fn command_prefix<I, S>(&mut self, prefix: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.command_prefix = prefix
+            .into_iter()
+            .map(|arg| arg.as_ref().to_string())
+            .collect();
+        self
+    }
 
-        if let Some(max_downloads) = &self.max_downloads {
-            args.push("--max-downloads");
-            args.push(max_downloads);
-        }
+    /// Set the process creation flags used to spawn yt-dlp (e.g. `CREATE_NEW_PROCESS_GROUP`
+    /// to handle Ctrl+Break), replacing the default `CREATE_NO_WINDOW`.
+    #[cfg(target_os = "windows")]
+    pub fn creation_flags(&mut self, creation_flags: u32) -> &mut Self {
+        self.creation_flags = creation_flags;
+        self
+    }
 
-        if let Some(output_template) = &self.output_template {
-            args.push("-o");
-            args.push(output_template);
-        }
+    /// Run yt-dlp as the given user and group, e.g. to drop root privileges in a download server.
+    /// Spawning fails if the current process is not allowed to switch to them.
+    #[cfg(unix)]
+    pub fn run_as(&mut self, uid: u32, gid: u32) -> &mut Self {
+        self.run_as = Some((uid, gid));
+        self
+    }
 
-        if let Some(output_dir) = &self.output_directory {
-            args.push("-P");
-            args.push(output_dir);
-        }
+    /// Run yt-dlp with an adjusted scheduling priority (between -20 and 19, higher values mean
+    /// lower priority), using the `nice` command.
+    #[cfg(unix)]
+    pub fn nice(&mut self, level: i32) -> &mut Self {
+        self.nice = Some(level.to_string());
+        self
+    }
 
-        if let Some(date) = &self.date {
-            args.push("--date");
-            args.push(date);
-        }
+    /// Before downloading, fetch the metadata and check that the output directory has enough
+    /// free space for the selected formats plus `margin` bytes, failing early with
+    /// `Error::InsufficientDiskSpace` otherwise. The check is skipped if yt-dlp reports no file size.
+    pub fn require_free_space(&mut self, margin: u64) -> &mut Self {
+        self.required_free_space = Some(margin);
+        self
+    }
 
-        if let Some(date_after) = &self.date_after {
-            args.push("--dateafter");
-            args.push(date_after);
-        }
+    /// Make `download_to` resumable: entries that were downloaded completely are recorded in
+    /// `state_file` (a yt-dlp download archive) and reported as `DownloadOutcome::AlreadyInArchive`
+    /// by later runs, and partially downloaded entries continue their `.part` files. After a crash,
+    /// calling `download_to` again with the same state file continues the playlist where it stopped.
+    pub fn resume_download<P: AsRef<Path>>(&mut self, state_file: P) -> &mut Self {
+        self.resume_state = Some(state_file.as_ref().to_string_lossy().into_owned());
+        self
+    }
 
-        if let Some(date_before) = &self.date_before {
-            args.push("--datebefore");
-            args.push(date_before);
-        }
+    /// Give each download its own temporary directory for fragments and intermediate files
+    /// (`-P temp:`) and cache (`--cache-dir`), which is removed when the download completes or
+    /// fails. This keeps concurrent downloads of the same video from clobbering each other's
+    /// fragments. With `resume_download`, the directory is `<state_file>.temp` instead, and is
+    /// kept after failures so that the next run can continue the partial downloads in it.
+    pub fn isolated_temp(&mut self, isolated: bool) -> &mut Self {
+        self.isolated_temp = isolated;
+        self
+    }
 
-        if self.ignore_errors {
-            args.push("--ignore-errors");
-        }
+    /// Set what downloads do when a file already exists, instead of relying on yt-dlp's
+    /// defaults. See `CollisionPolicy`.
+    pub fn collision_policy(&mut self, policy: CollisionPolicy) -> &mut Self {
+        self.collision_policy = Some(policy);
+        self
+    }
 
-        for extra_arg in &self.extra_args {
-            args.push(extra_arg);
-        }
+    /// Hash each file downloaded by `download_to`, reporting it as `DownloadOutcome::Verified`
+    /// with its digest instead of `DownloadOutcome::Downloaded`, e.g. to record fixity
+    /// information for archived files. Files that already existed are hashed too, and files
+    /// that can't be hashed are listed in `RunReport::verify_failures`.
+    #[cfg(feature = "checksum")]
+    pub fn verify_downloads(&mut self, algo: HashAlgo) -> &mut Self {
+        self.verify_downloads = Some(algo);
+        self
+    }
+
+    /// Print progress while downloading, so that `RunReport::bytes_downloaded` and
+    /// `RunReport::average_speed` are counted even without `hooks`, e.g. to meter the usage of
+    /// each user of a service.
+    pub fn measure_bandwidth(&mut self, measure_bandwidth: bool) -> &mut Self {
+        self.measure_bandwidth = measure_bandwidth;
+        self
+    }
+
+    /// Download the whole video when the URL is a YouTube clip (see `urls::is_clip`), instead of
+    /// only the section of the clip. This costs an extra yt-dlp call to find the video.
+    pub fn treat_clip_as_full_video(&mut self, full_video: bool) -> &mut Self {
+        self.clip_as_full_video = full_video;
+        self
+    }
+
+    /// Download only a section of the video (`--download-sections`), like `*10:15-15:00` for a
+    /// time range or a regex matching chapter titles. Can be called multiple times.
+    pub fn download_section<S: Into<String>>(&mut self, section: S) -> &mut Self {
+        self.download_sections.push(section.into());
+        self
+    }
+
+    /// Detect throttled downloads in `download_to` and retry them with other player clients,
+    /// see `ThrottleMitigation`.
+    pub fn throttle_mitigation(&mut self, throttle: ThrottleMitigation) -> &mut Self {
+        self.throttle = Some(throttle);
+        self
+    }
+
+    /// Fail with `Error::UnmodeledFields` if yt-dlp's output contains fields that are not part of
+    /// this crate's model, listing all of them. Meant for tests, to notice early when yt-dlp
+    /// adds fields that the model is missing.
+    pub fn strict_model(&mut self, strict: bool) -> &mut Self {
+        self.strict_model = strict;
+        self
+    }
+
+    /// Limit the size of yt-dlp's output. If stdout grows larger than `limit` bytes (e.g. when
+    /// fetching the metadata of a channel with thousands of videos), the process is killed and
+    /// `Error::OutputTooLarge` is returned.
+    pub fn max_stdout_bytes(&mut self, limit: u64) -> &mut Self {
+        self.max_stdout_bytes = Some(limit);
+        self
+    }
+
+    /// Only keep the last `lines` lines of stderr, e.g. for runs that print thousands of fragment
+    /// warnings. The tail is still reported in `Error::ExitCode`, and hooks see every line.
+    /// Error messages, the verbose header and throttling warnings are kept in addition to the
+    /// tail, so that `RunReport`, `ErrorKind`, `diagnostics` and `throttle_mitigation` still
+    /// see them.
+    pub fn capture_stderr_lines(&mut self, lines: usize) -> &mut Self {
+        self.capture_stderr_lines = Some(lines);
+        self
+    }
+
+    /// Run yt-dlp in verbose mode (`-v`) and parse the header it prints (yt-dlp, Python and
+    /// ffmpeg versions, extractor) into `RunReport::diagnostics`, e.g. to attach it to bug reports.
+    pub fn diagnostics(&mut self, diagnostics: bool) -> &mut Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// A copy of this builder with all options, but targeting a different URL.
+    fn with_url(&self, url: &str) -> YoutubeDl {
+        let mut builder = self.clone();
+        builder.url = url.to_string();
+        builder
+    }
+
+    /// The program and the arguments that come before yt-dlp's options.
+    fn program(&self) -> Vec<&OsStr> {
+        let mut program: Vec<&OsStr> = self.command_prefix.iter().map(OsStr::new).collect();
+        #[cfg(unix)]
+        if let Some(level) = &self.nice {
+            program.extend([OsStr::new("nice"), OsStr::new("-n"), OsStr::new(level)]);
+        }
+        match &self.invocation {
+            Some(BinaryInvocation::Executable(path)) => program.push(path.as_os_str()),
+            Some(BinaryInvocation::PythonModule { interpreter }) => program.extend([
+                interpreter.as_os_str(),
+                OsStr::new("-m"),
+                OsStr::new(self.flavor.module()),
+            ]),
+            None => program.push(OsStr::new(self.flavor.executable())),
+        }
+        program
+    }
+
+    fn common_args(&self) -> ArgSet<'_> {
+        let mut args = ArgSet::new();
+        if self.flavor == Flavor::YoutubeDl {
+            args.legacy();
+        }
+        if self.diagnostics {
+            args.flag("-v");
+        }
+
+        if self.no_plugins {
+            args.flag("--no-plugin-dirs");
+        }
+
+        for plugin_dir in &self.plugin_dirs {
+            args.repeated("--plugin-dirs", plugin_dir);
+        }
+
+        if let Some(format) = &self.format {
+            args.option("-f", format);
+        }
+
+        if self.flat_playlist {
+            args.flag("--flat-playlist");
+        }
+
+        if let Some(timeout) = &self.socket_timeout {
+            args.option("--socket-timeout", timeout);
+        }
+
+        if self.all_formats {
+            args.flag("--all-formats");
+        }
+
+        if let Some(check_formats) = self.check_formats {
+            args.flag(check_formats.flag());
+        }
+
+        if let Some(chunk_size) = &self.http_chunk_size {
+            args.option("--http-chunk-size", chunk_size);
+        }
+
+        if let Some(buffer_size) = &self.buffer_size {
+            args.option("--buffer-size", buffer_size);
+        }
+
+        if self.no_resize_buffer {
+            args.flag("--no-resize-buffer");
+        }
+
+        if let Some((user, password)) = &self.auth {
+            args.option("-u", user);
+            args.option("-p", password);
+        }
+
+        if let Some(cookie_path) = &self.cookies {
+            args.option("--cookies", cookie_path);
+        }
+
+        if let Some(cookies_from_browser) = &self.cookies_from_browser {
+            args.option("--cookies-from-browser", cookies_from_browser);
+        }
+
+        if let Some(player_clients) = &self.player_clients {
+            args.repeated("--extractor-args", player_clients);
+        }
+
+        if let Some(user_agent) = &self.user_agent {
+            args.option("--user-agent", user_agent);
+        }
+
+        if let Some(referer) = &self.referer {
+            args.option("--referer", referer);
+        }
+
+        if self.extract_audio || self.loudness.is_some() {
+            args.flag("--extract-audio");
+        }
+
+        if let Some(audio_format) = &self.audio_format {
+            args.option("--audio-format", audio_format);
+        } else if self.loudness.is_some() {
+            args.option("--audio-format", LOUDNESS_AUDIO_FORMAT);
+        }
+
+        if let Some(loudness) = &self.loudness {
+            args.repeated("--postprocessor-args", loudness);
+        }
+
+        if let Some(ffmpeg_location) = &self.ffmpeg_location {
+            args.option("--ffmpeg-location", ffmpeg_location);
+        }
+
+        if self.ignore_no_formats_error {
+            args.flag("--ignore-no-formats-error");
+        }
+
+        if self.allow_unplayable_formats {
+            args.flag("--allow-unplayable-formats");
+        }
+
+        if self.audio_multistreams {
+            args.flag("--audio-multistreams");
+        }
+
+        if self.video_multistreams {
+            args.flag("--video-multistreams");
+        }
+
+        if let Some(compat_options) = &self.compat_options {
+            args.option("--compat-options", compat_options);
+        }
+
+        for postprocessor in &self.postprocessors {
+            args.repeated("--use-postprocessor", postprocessor);
+        }
+
+        for link_format in &self.write_links {
+            args.flag(link_format.flag());
+        }
+
+        if let Some(playlist_items) = &self.playlist_items {
+            args.option("--playlist-items", playlist_items);
+        }
+
+        if let Some(max_downloads) = &self.max_downloads {
+            args.option("--max-downloads", max_downloads);
+        }
+
+        if let Some(output_template) = &self.output_template {
+            args.option("-o", output_template);
+        }
+
+        for section in &self.download_sections {
+            args.repeated("--download-sections", section);
+        }
+
+        // youtube-dl has no `-P`, the folder is only used for downloads (see `download_location`).
+        if let Some(output_dir) = &self.output_directory {
+            if self.flavor != Flavor::YoutubeDl {
+                args.option("-P", output_dir);
+            }
+        }
+
+        if let Some((temp, cache_dir)) = &self.temp_paths {
+            if self.flavor != Flavor::YoutubeDl {
+                args.option("-P", temp);
+            }
+            args.option("--cache-dir", cache_dir);
+        }
+
+        if let Some(date) = &self.date {
+            args.option("--date", date);
+        }
+
+        if let Some(date_after) = &self.date_after {
+            args.option("--dateafter", date_after);
+        }
+
+        if let Some(date_before) = &self.date_before {
+            args.option("--datebefore", date_before);
+        }
+
+        if self.ignore_errors {
+            args.flag("--ignore-errors");
+        }
+
+        args.extra(&self.extra_args);
 
         args
     }
 
     fn process_args(&self) -> Vec<&str> {
         let mut args = self.common_args();
+        args.flag("-J");
+        args.positional(&self.url);
+        let args = args.into_vec();
+        trace::args(&args);
 
-        if let Some(output_dir) = &self.output_directory {
-            args.push("-P");
-            args.push(output_dir);
+        args
+    }
+
+    /// Where to download to, passed to `process_download_args`: the folder, or for youtube-dl
+    /// (which has no `-P`) the output template inside the folder.
+    fn download_location(&self, folder: &Path) -> String {
+        match self.flavor {
+            Flavor::YoutubeDl => {
+                let template = self
+                    .output_template
+                    .as_deref()
+                    .unwrap_or(LEGACY_OUTPUT_TEMPLATE);
+                folder.join(template).to_string_lossy().into_owned()
+            }
+            Flavor::YtDlp => folder.to_string_lossy().into_owned(),
         }
+    }
 
-        args.push("-J");
-        args.push(&self.url);
-        log::debug!("youtube-dl arguments: {:?}", args);
+    fn process_download_args<'a>(&'a self, location: &'a str) -> Vec<&'a str> {
+        let mut args = self.common_args();
+        let legacy = self.flavor == Flavor::YoutubeDl;
+
+        if legacy {
+            args.option("-o", location);
+        } else {
+            args.option("-P", location);
+        }
+        if self.simulate {
+            args.flag("--simulate");
+        } else if !legacy {
+            args.flag("--no-simulate");
+        }
+        if self.skip_download {
+            args.flag("--skip-download");
+        }
+        if let Some(flag) = self.collision_policy.and_then(CollisionPolicy::flag) {
+            args.flag(flag);
+        }
+        if let Some(state_file) = &self.resume_state {
+            args.option("--download-archive", state_file);
+            args.flag("--continue");
+        }
+        if let Some(throttle) = &self.throttle {
+            args.option("--throttled-rate", throttle.rate());
+        }
+        // The progress lines are also used to detect throttling and to measure bandwidth.
+        if self.hooks.is_some() || self.throttle.is_some() || self.measure_bandwidth {
+            args.flag("--newline");
+            if !legacy {
+                args.option("--progress-template", progress::PROGRESS_TEMPLATE);
+            }
+        } else {
+            args.flag("--no-progress");
+        }
+        if self.on_file_downloaded.is_some() && !legacy {
+            // `--print` implies `--quiet`, which would hide the output parsed for `RunReport`.
+            args.flag("--no-quiet");
+            args.repeated("--print", progress::FILE_TEMPLATE);
+        }
+        for (template, path) in &self.print_to_file {
+            args.repeated_pair("--print-to-file", template, path);
+        }
+        // A `Probe` downloads from the metadata it extracted before.
+        match &self.load_info_json {
+            Some(info_file) => args.option("--load-info-json", info_file),
+            None => args.positional(&self.url),
+        }
+        let args = args.into_vec();
+        trace::args(&args);
 
         args
     }
 
-    fn process_download_args<'a>(&'a self, folder: &'a str) -> Vec<&'a str> {
+    fn process_download_json_args<'a>(&'a self, location: &'a str) -> Vec<&'a str> {
         let mut args = self.common_args();
 
-        args.push("-P");
-        args.push(folder);
-        args.push("--no-simulate");
-        args.push("--no-progress");
-        args.push(&self.url);
-        log::debug!("youtube-dl arguments: {:?}", args);
+        if self.flavor == Flavor::YoutubeDl {
+            args.option("-o", location);
+            args.flag("--print-json");
+        } else {
+            args.option("-P", location);
+            args.flag("--no-simulate");
+            args.flag("-J");
+        }
+        if let Some(flag) = self.collision_policy.and_then(CollisionPolicy::flag) {
+            args.flag(flag);
+        }
+        for (template, path) in &self.print_to_file {
+            args.repeated_pair("--print-to-file", template, path);
+        }
+        args.positional(&self.url);
+        let args = args.into_vec();
+        trace::args(&args);
 
         args
     }
 
-    fn run_process(&self, args: Vec<&str>) -> Result<ProcessResult, Error> {
-        use std::io::Read;
+    fn command(&self, args: Vec<&str>) -> std::process::Command {
         use std::process::{Command, Stdio};
-        use wait_timeout::ChildExt;
 
-        let path = self.path();
-        #[cfg(not(target_os = "windows"))]
-        let mut child = Command::new(path)
+        let program = self.program();
+        let mut command = Command::new(program[0]);
+        command.args(&program[1..]);
+        #[cfg(unix)]
+        if let Some((uid, gid)) = self.run_as {
+            use std::os::unix::process::CommandExt;
+            command.uid(uid).gid(gid);
+        }
+
+        command
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .args(args)
-            .spawn()?;
+            .args(args);
         #[cfg(target_os = "windows")]
-        let mut child = Command::new(path)
-            .creation_flags(CREATE_NO_WINDOW)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .args(args)
-            .spawn()?;
-
-        // Continually read from stdout so that it does not fill up with large output and hang forever.
-        // We don't need to do this for stderr since only stdout has potentially giant JSON.
-        let mut stdout = Vec::new();
-        let child_stdout = child.stdout.take();
-        std::io::copy(&mut child_stdout.unwrap(), &mut stdout)?;
-
-        let exit_code = if let Some(timeout) = self.process_timeout {
-            match child.wait_timeout(timeout)? {
-                Some(status) => status,
-                None => {
-                    child.kill()?;
-                    return Err(Error::ProcessTimeout);
-                }
-            }
-        } else {
-            child.wait()?
-        };
-
-        let mut stderr = vec![];
-        if let Some(mut reader) = child.stderr {
-            reader.read_to_end(&mut stderr)?;
+        command.creation_flags(self.creation_flags);
+        if let Some(callback) = &self.before_spawn {
+            (callback.0)(&mut command);
         }
+        command
+    }
 
-        Ok(ProcessResult {
-            stdout,
-            stderr,
-            exit_code,
-        })
+    fn complete(&self, result: &ProcessResult, duration: Duration) {
+        trace::stderr(&result.stderr);
+        trace::finished(result.exit_code.code(), result.stdout.len(), duration);
+        if let Some(hooks) = &self.hooks {
+            hooks
+                .0
+                .on_complete(&self.url, result.exit_code, duration, result.stdout.len());
+        }
     }
 
-    #[cfg(feature = "tokio")]
-    async fn run_process_async(&self, args: Vec<&str>) -> Result<ProcessResult, Error> {
-        use std::process::Stdio;
-        use tokio::io::AsyncReadExt;
-        use tokio::process::Command;
-        use tokio::time::timeout;
-
-        let path = self.path();
-        #[cfg(not(target_os = "windows"))]
-        let mut child = Command::new(path)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .args(args)
-            .spawn()?;
-        #[cfg(target_os = "windows")]
-        let mut child = Command::new(path)
-            .creation_flags(CREATE_NO_WINDOW)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .args(args)
-            .spawn()?;
-
-        // Continually read from stdout so that it does not fill up with large output and hang forever.
-        // We don't need to do this for stderr since only stdout has potentially giant JSON.
-        let mut stdout = Vec::new();
-        let child_stdout = child.stdout.take();
-        tokio::io::copy(&mut child_stdout.unwrap(), &mut stdout).await?;
-
-        let exit_code = if let Some(dur) = self.process_timeout {
-            match timeout(dur, child.wait()).await {
-                Ok(n) => n?,
-                Err(_) => {
-                    child.kill().await?;
-                    return Err(Error::ProcessTimeout);
+    fn process_json_output(&self, stdout: Vec<u8>) -> Result<YoutubeDlOutput, Error> {
+        #[cfg(test)]
+        if self.debug {
+            eprintln!("{}", String::from_utf8_lossy(&stdout));
+        }
+
+        trace::parse_span(stdout.len()).in_scope(|| {
+            if self.strict_model {
+                let value: Value = serde_json::from_slice(&stdout)?;
+                let fields = strict::unmodeled_fields(&value);
+                if !fields.is_empty() {
+                    return Err(Error::UnmodeledFields { fields });
                 }
             }
-        } else {
-            child.wait().await?
-        };
-        let mut stderr = vec![];
-        if let Some(mut reader) = child.stderr {
-            reader.read_to_end(&mut stderr).await?;
+            parse_json_output(&stdout)
+        })
+    }
+
+    fn run_json(&self) -> Result<Vec<u8>, Error> {
+        let args = self.process_args();
+        if let Some(stdout) = self.cache.as_ref().and_then(|cache| cache.get(self, &args)) {
+            return Ok(stdout);
+        }
+        let stdout = self.run_json_with(args.clone())?;
+        if let Some(cache) = &self.cache {
+            cache.insert(self, &args, &stdout);
         }
+        Ok(stdout)
+    }
 
-        Ok(ProcessResult {
-            stdout,
+    /// The standard output of a run, or the error if yt-dlp failed (unless errors are ignored).
+    fn json_output(&self, result: ProcessResult) -> Result<Vec<u8>, Error> {
+        let ProcessResult {
             stderr,
+            stdout,
             exit_code,
-        })
+        } = result;
+        if exit_code.success() || self.ignore_errors {
+            Ok(stdout)
+        } else {
+            let stderr = String::from_utf8_lossy(&stderr).into_owned();
+            Err(Error::from_exit_status(exit_code, stderr))
+        }
     }
 
-    fn process_json_output(&self, stdout: Vec<u8>) -> Result<YoutubeDlOutput, Error> {
-        use serde_json::json;
+    fn run_json_with(&self, args: Vec<&str>) -> Result<Vec<u8>, Error> {
+        if let Some(dir) = &self.replay_dir {
+            return fixtures::load(dir, &args);
+        }
 
-        #[cfg(test)]
-        if self.debug {
-            let string = std::str::from_utf8(&stdout).expect("invalid utf-8 output");
-            eprintln!("{}", string);
+        let stdout = self.json_output(self.run_process(args.clone())?)?;
+        if let Some(dir) = &self.record_dir {
+            fixtures::store(dir, &args, &stdout)?;
         }
+        Ok(stdout)
+    }
 
-        let value: Value = serde_json::from_reader(stdout.as_slice())?;
+    #[cfg(feature = "tokio")]
+    async fn run_json_async(&self) -> Result<Vec<u8>, Error> {
+        let args = self.process_args();
+        if let Some(stdout) = self.cache.as_ref().and_then(|cache| cache.get(self, &args)) {
+            return Ok(stdout);
+        }
+        let stdout = self.run_json_with_async(args.clone()).await?;
+        if let Some(cache) = &self.cache {
+            cache.insert(self, &args, &stdout);
+        }
+        Ok(stdout)
+    }
 
-        let is_playlist = value["_type"] == json!("playlist");
-        if is_playlist {
-            let playlist: Playlist = serde_json::from_value(value)?;
-            Ok(YoutubeDlOutput::Playlist(Box::new(playlist)))
-        } else {
-            let video: SingleVideo = serde_json::from_value(value)?;
-            Ok(YoutubeDlOutput::SingleVideo(Box::new(video)))
+    #[cfg(feature = "tokio")]
+    async fn run_json_with_async(&self, args: Vec<&str>) -> Result<Vec<u8>, Error> {
+        if let Some(dir) = &self.replay_dir {
+            return fixtures::load(dir, &args);
+        }
+
+        let stdout = self.json_output(self.run_process_async(args.clone()).await?)?;
+        if let Some(dir) = &self.record_dir {
+            fixtures::store(dir, &args, &stdout)?;
         }
+        Ok(stdout)
     }
 
     /// Run yt-dlp with the arguments specified through the builder and parse its
     /// JSON ouput into `YoutubeDlOutput`. Note: This can fail when the JSON output
     /// is not compatible with the struct definitions in this crate.
     pub fn run(&self) -> Result<YoutubeDlOutput, Error> {
-        let args = self.process_args();
-        let ProcessResult {
-            stderr,
-            stdout,
-            exit_code,
-        } = self.run_process(args)?;
-
-        if exit_code.success() || self.ignore_errors {
-            self.process_json_output(stdout)
-        } else {
-            let stderr = String::from_utf8(stderr).unwrap_or_default();
-            Err(Error::ExitCode {
-                code: exit_code.code().unwrap_or(1),
-                stderr,
-            })
+        let stdout = self.run_json()?;
+        self.process_json_output(stdout)
+    }
+
+    /// Download to the specified destination folder and return the parsed JSON output in one
+    /// yt-dlp call. Unlike `run`, the output includes the post-download fields like
+    /// `SingleVideo::requested_downloads`, which contain the final path of each file.
+    /// Note that progress is not reported to `hooks` in this mode. Like `download_to`, this always
+    /// runs yt-dlp: the download is neither served by `replay_from` nor saved by `record_to`.
+    pub fn run_and_download(&self, folder: impl AsRef<Path>) -> Result<YoutubeDlOutput, Error> {
+        let isolated = self.isolate()?;
+        let this = isolated.as_ref().map_or(self, |(builder, _)| builder);
+        let clip = this.resolve_clip()?;
+        let this = clip.as_ref().unwrap_or(this);
+        let renamed = this.avoid_collisions(folder.as_ref())?;
+        let this = renamed.as_ref().unwrap_or(this);
+        if let Some(margin) = this.required_free_space {
+            let output = this.run()?;
+            disk::check_free_space(folder.as_ref(), &output, margin)?;
         }
+
+        let location = this.download_location(folder.as_ref());
+        let result = this.run_process(this.process_download_json_args(&location))?;
+        let output = this.process_json_output(this.json_output(result)?)?;
+        this.make_output_previews(&output);
+        Ok(output)
     }
 
     /// Run yt-dlp with the arguments through the builder and parse its JSON output
     /// into a `serde_json::Value`. This is meant as a fallback for when the JSON
     /// output is not compatible with the struct definitions in this crate.
     pub fn run_raw(&self) -> Result<Value, Error> {
-        let args = self.process_args();
-        let ProcessResult {
-            stderr,
-            stdout,
-            exit_code,
-        } = self.run_process(args)?;
+        let stdout = self.run_json()?;
+        let value: Value =
+            trace::parse_span(stdout.len()).in_scope(|| serde_json::from_slice(&stdout))?;
+        Ok(value)
+    }
 
-        if exit_code.success() || self.ignore_errors {
-            let value: Value = serde_json::from_reader(stdout.as_slice())?;
-            Ok(value)
-        } else {
-            let stderr = String::from_utf8(stderr).unwrap_or_default();
-            Err(Error::ExitCode {
-                code: exit_code.code().unwrap_or(1),
-                stderr,
-            })
-        }
+    /// Run yt-dlp with the arguments through the builder and return its raw standard output,
+    /// without decoding or parsing it (e.g. when the output is not valid UTF-8).
+    pub fn run_raw_bytes(&self) -> Result<Vec<u8>, Error> {
+        self.run_json()
+    }
+
+    /// The user agent yt-dlp sends with its requests (`--dump-user-agent`), taking
+    /// `user_agent` into account.
+    pub fn dump_user_agent(&self) -> Result<String, Error> {
+        let mut args = self.common_args();
+        args.flag("--dump-user-agent");
+        let stdout = self.run_json_with(args.into_vec())?;
+        Ok(String::from_utf8_lossy(&stdout).trim().to_string())
     }
 
     /// Run yt-dlp asynchronously with the arguments specified through the builder.
     #[cfg(feature = "tokio")]
     pub async fn run_async(&self) -> Result<YoutubeDlOutput, Error> {
-        let args = self.process_args();
-        let ProcessResult {
-            stderr,
-            stdout,
-            exit_code,
-        } = self.run_process_async(args).await?;
+        let stdout = self.run_json_async().await?;
+        self.process_json_output(stdout)
+    }
 
-        if exit_code.success() || self.ignore_errors {
-            self.process_json_output(stdout)
-        } else {
-            let stderr = String::from_utf8(stderr).unwrap_or_default();
-            Err(Error::ExitCode {
-                code: exit_code.code().unwrap_or(1),
-                stderr,
-            })
+    /// Download to the specified destination folder and return the parsed JSON output
+    /// asynchronously. See `run_and_download` for details.
+    #[cfg(feature = "tokio")]
+    pub async fn run_and_download_async(
+        &self,
+        folder: impl AsRef<Path>,
+    ) -> Result<YoutubeDlOutput, Error> {
+        let isolated = self.isolate()?;
+        let this = isolated.as_ref().map_or(self, |(builder, _)| builder);
+        let clip = this.resolve_clip_async().await?;
+        let this = clip.as_ref().unwrap_or(this);
+        let renamed = this.avoid_collisions_async(folder.as_ref()).await?;
+        let this = renamed.as_ref().unwrap_or(this);
+        if let Some(margin) = this.required_free_space {
+            let output = this.run_async().await?;
+            disk::check_free_space(folder.as_ref(), &output, margin)?;
         }
+
+        let location = this.download_location(folder.as_ref());
+        let args = this.process_download_json_args(&location);
+        let result = this.run_process_async(args).await?;
+        let output = this.process_json_output(this.json_output(result)?)?;
+        this.make_output_previews_async(&output).await;
+        Ok(output)
+    }
+
+    /// Run yt-dlp asynchronously and return its raw standard output. See `run_raw_bytes`.
+    #[cfg(feature = "tokio")]
+    pub async fn run_raw_bytes_async(&self) -> Result<Vec<u8>, Error> {
+        self.run_json_async().await
     }
 
     /// Run yt-dlp asynchronously with the arguments through the builder and parse its JSON output
@@ -807,42 +2128,322 @@ impl YoutubeDl {
     /// output is not compatible with the struct definitions in this crate.
     #[cfg(feature = "tokio")]
     pub async fn run_raw_async(&self) -> Result<Value, Error> {
-        let args = self.process_args();
+        let stdout = self.run_json_async().await?;
+        let value: Value =
+            trace::parse_span(stdout.len()).in_scope(|| serde_json::from_slice(&stdout))?;
+        Ok(value)
+    }
+
+    fn download_report(&self, result: ProcessResult) -> Result<RunReport, Error> {
         let ProcessResult {
             stderr,
             stdout,
             exit_code,
-        } = self.run_process_async(args).await?;
+        } = result;
+
+        let stderr = String::from_utf8_lossy(&stderr).into_owned();
+        let exit_reason = ExitReason::from_status(exit_code);
+        if exit_reason.is_success() || self.ignore_errors {
+            let stdout = String::from_utf8_lossy(&stdout);
+            let mut report = RunReport::from_output(&stdout, &stderr);
+            report.exit_reason = exit_reason;
+            if self.diagnostics {
+                report.diagnostics = Some(Diagnostics::parse(&stdout, &stderr));
+            }
+            #[cfg(feature = "checksum")]
+            if let Some(algo) = self.verify_downloads {
+                report.verify(algo);
+            }
+            Ok(report)
+        } else {
+            Err(Error::from_exit_status(exit_code, stderr))
+        }
+    }
+
+    /// Download the file to the specified destination folder and report what happened
+    /// to each entry. With `ignore_errors`, failed entries are reported as `DownloadOutcome::Failed`
+    /// instead of failing with `Error::ExitCode`. When the download is stopped early by
+    /// `--max-downloads` or a `--break-*` option, `RunReport::exit_reason` is `ExitReason::Stopped`.
+    pub fn download_to(&self, folder: impl AsRef<Path>) -> Result<RunReport, Error> {
+        let isolated = self.isolate()?;
+        let this = isolated.as_ref().map_or(self, |(builder, _)| builder);
+        let clip = this.resolve_clip()?;
+        let this = clip.as_ref().unwrap_or(this);
+        let renamed = this.avoid_collisions(folder.as_ref())?;
+        let this = renamed.as_ref().unwrap_or(this);
+        if let Some(margin) = this.required_free_space {
+            let output = this.run()?;
+            disk::check_free_space(folder.as_ref(), &output, margin)?;
+        }
+
+        let location = this.download_location(folder.as_ref());
+        let args = this.process_download_args(&location);
+        let mut result = this.run_process(args)?;
+        for builder in this.throttle_fallbacks() {
+            if !this.is_throttled(&result) {
+                break;
+            }
+            log::info!("download of {} was throttled, retrying", this.url);
+            result = builder.run_process(builder.process_download_args(&location))?;
+        }
+        let mut report = this.download_report(result)?;
+        this.make_previews(&mut report);
+        Ok(report)
+    }
+
+    /// Download the file to the specified destination folder asynchronously.
+    /// See `download_to` for details.
+    #[cfg(feature = "tokio")]
+    pub async fn download_to_async(&self, folder: impl AsRef<Path>) -> Result<RunReport, Error> {
+        let isolated = self.isolate()?;
+        let this = isolated.as_ref().map_or(self, |(builder, _)| builder);
+        let clip = this.resolve_clip_async().await?;
+        let this = clip.as_ref().unwrap_or(this);
+        let renamed = this.avoid_collisions_async(folder.as_ref()).await?;
+        let this = renamed.as_ref().unwrap_or(this);
+        if let Some(margin) = this.required_free_space {
+            let output = this.run_async().await?;
+            disk::check_free_space(folder.as_ref(), &output, margin)?;
+        }
+
+        let location = this.download_location(folder.as_ref());
+        let args = this.process_download_args(&location);
+        let mut result = this.run_process_async(args).await?;
+        for builder in this.throttle_fallbacks() {
+            if !this.is_throttled(&result) {
+                break;
+            }
+            log::info!("download of {} was throttled, retrying", this.url);
+            result = builder
+                .run_process_async(builder.process_download_args(&location))
+                .await?;
+        }
+        let mut report = this.download_report(result)?;
+        this.make_previews_async(&mut report).await;
+        Ok(report)
+    }
+
+    fn is_throttled(&self, result: &ProcessResult) -> bool {
+        let throttle = match &self.throttle {
+            Some(throttle) => throttle,
+            None => return false,
+        };
+        let stdout = String::from_utf8_lossy(&result.stdout);
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        throttle.is_throttled(&stdout, &stderr)
+    }
+
+    /// Copies of the builder that use the fallback player clients of the `ThrottleMitigation`,
+    /// each tried before the player clients that are already set (e.g. for the age gate).
+    fn throttle_fallbacks(&self) -> Vec<YoutubeDl> {
+        let clients = self
+            .throttle
+            .as_ref()
+            .map_or(&[][..], |throttle| throttle.fallback_clients());
+        clients
+            .iter()
+            .map(|client| {
+                let mut builder = self.clone();
+                builder.add_player_clients(&[client.as_str()], true);
+                builder
+            })
+            .collect()
+    }
+}
+
+/// Abstraction over fetching the metadata for a URL. `YoutubeDl` implements this by
+/// running yt-dlp with its configured options, which allows swapping in a fake
+/// implementation (like `mock::MockFetcher`) in tests.
+pub trait MetadataFetcher {
+    /// Fetch and parse the metadata for the given URL.
+    fn fetch(&self, url: &str) -> Result<YoutubeDlOutput, Error>;
+}
+
+/// Uses the builder as a template: all options are kept, only the URL is replaced.
+impl MetadataFetcher for YoutubeDl {
+    fn fetch(&self, url: &str) -> Result<YoutubeDlOutput, Error> {
+        self.with_url(url).run()
+    }
+}
+
+impl SingleVideo {
+    /// Create a builder that downloads this video again with the same format, e.g. to
+    /// download a video later from metadata that was stored in a database. Returns `None`
+    /// if the metadata does not contain the `webpage_url`.
+    pub fn to_builder(&self) -> Option<YoutubeDl> {
+        let mut builder = YoutubeDl::new(self.webpage_url.as_deref()?);
+        if let Some(format_id) = &self.format_id {
+            builder.format(format_id);
+        }
+        Some(builder)
+    }
+}
+
+fn read_all(mut reader: impl std::io::Read) -> Result<Vec<u8>, Error> {
+    let mut output = Vec::new();
+    std::io::copy(&mut reader, &mut output)?;
+    Ok(output)
+}
+
+fn read_lines(
+    reader: impl std::io::Read,
+    mut on_line: impl FnMut(&[u8]),
+) -> Result<Vec<u8>, Error> {
+    use std::io::{BufRead, BufReader};
+
+    let mut reader = BufReader::new(reader);
+    let mut output = Vec::new();
+    let mut start = 0;
+    while reader.read_until(b'\n', &mut output)? > 0 {
+        on_line(&output[start..]);
+        start = output.len();
+    }
+    Ok(output)
+}
+
+/// The last lines read by `read_tail`, and the lines that the output is parsed for.
+struct Tail {
+    /// The last lines, with their line numbers.
+    lines: VecDeque<(usize, Vec<u8>)>,
+    /// The lines that are parsed (see `is_parsed`), which are kept regardless of the limit.
+    parsed: Vec<(usize, Vec<u8>)>,
+    limit: usize,
+    count: usize,
+    /// Whether the `[debug]` header of verbose mode is still being read.
+    in_header: bool,
+}
+
+impl Tail {
+    fn new(limit: usize) -> Self {
+        Tail {
+            lines: VecDeque::new(),
+            parsed: vec![],
+            limit,
+            count: 0,
+            in_header: true,
+        }
+    }
 
-        if exit_code.success() || self.ignore_errors {
-            let value: Value = serde_json::from_reader(stdout.as_slice())?;
-            Ok(value)
-        } else {
-            let stderr = String::from_utf8(stderr).unwrap_or_default();
-            Err(Error::ExitCode {
-                code: exit_code.code().unwrap_or(1),
-                stderr,
-            })
+    /// Whether the line is needed by the parsers of stderr: error messages (`RunReport`,
+    /// `ErrorKind`, `Error::NotYetAvailable`), the verbose header and extractor (`Diagnostics`,
+    /// `Plugins`) and throttling warnings (`ThrottleMitigation`).
+    fn is_parsed(&mut self, line: &str) -> bool {
+        if let Some(debug) = line.strip_prefix("[debug] ") {
+            let parsed = self.in_header;
+            if debug.starts_with("Loaded ") && debug.ends_with(" extractors") {
+                self.in_header = false;
+            }
+            return parsed;
         }
+        line.starts_with("ERROR:")
+            || (line.starts_with("WARNING:") && line.contains("throttl"))
+            || line.contains("] Extracting URL: ")
     }
 
-    /// Download the file to the specified destination folder.
-    pub fn download_to(&self, folder: impl AsRef<Path>) -> Result<(), Error> {
-        let folder_str = folder.as_ref().to_string_lossy();
-        let args = self.process_download_args(&folder_str);
-        self.run_process(args)?;
+    /// Keep the line, returning the buffer of a dropped line to read the next one into.
+    fn push(&mut self, line: Vec<u8>) -> Vec<u8> {
+        self.count += 1;
+        if self.is_parsed(String::from_utf8_lossy(&line).trim()) {
+            self.parsed.push((self.count, line));
+            return Vec::new();
+        }
+        if self.limit == 0 {
+            return line;
+        }
+        let mut buffer = if self.lines.len() == self.limit {
+            self.lines
+                .pop_front()
+                .map(|(_, line)| line)
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        self.lines.push_back((self.count, line));
+        buffer.clear();
+        buffer
+    }
 
-        Ok(())
+    fn into_output(self) -> Vec<u8> {
+        let mut lines: Vec<_> = self.parsed.into_iter().chain(self.lines).collect();
+        lines.sort_by_key(|(number, _)| *number);
+        lines.into_iter().flat_map(|(_, line)| line).collect()
     }
+}
 
-    /// Download the file to the specified destination folder asynchronously.
-    #[cfg(feature = "tokio")]
-    pub async fn download_to_async(&self, folder: impl AsRef<Path>) -> Result<(), Error> {
-        let folder_str = folder.as_ref().to_string_lossy();
-        let args = self.process_download_args(&folder_str);
-        self.run_process_async(args).await?;
+/// Like `read_lines`, but only return the last `limit` lines.
+fn read_tail(
+    reader: impl std::io::Read,
+    limit: usize,
+    mut on_line: impl FnMut(&[u8]),
+) -> Result<Vec<u8>, Error> {
+    use std::io::{BufRead, BufReader};
+
+    let mut reader = BufReader::new(reader);
+    let mut tail = Tail::new(limit);
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        on_line(&line);
+        line = tail.push(line);
+        line.clear();
+    }
+    Ok(tail.into_output())
+}
+
+#[cfg(feature = "tokio")]
+async fn read_tail_async(
+    reader: impl tokio::io::AsyncRead + Unpin,
+    limit: usize,
+    mut on_line: impl FnMut(&[u8]),
+) -> Result<Vec<u8>, Error> {
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    let mut reader = BufReader::new(reader);
+    let mut tail = Tail::new(limit);
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line).await? > 0 {
+        on_line(&line);
+        line = tail.push(line);
+        line.clear();
+    }
+    Ok(tail.into_output())
+}
+
+#[cfg(feature = "tokio")]
+async fn read_all_async(mut reader: impl tokio::io::AsyncRead + Unpin) -> Result<Vec<u8>, Error> {
+    let mut output = Vec::new();
+    tokio::io::copy(&mut reader, &mut output).await?;
+    Ok(output)
+}
+
+#[cfg(feature = "tokio")]
+async fn read_lines_async(
+    reader: impl tokio::io::AsyncRead + Unpin,
+    mut on_line: impl FnMut(&[u8]),
+) -> Result<Vec<u8>, Error> {
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    let mut reader = BufReader::new(reader);
+    let mut output = Vec::new();
+    let mut start = 0;
+    while reader.read_until(b'\n', &mut output).await? > 0 {
+        on_line(&output[start..]);
+        start = output.len();
+    }
+    Ok(output)
+}
+
+fn parse_json_output(stdout: &[u8]) -> Result<YoutubeDlOutput, Error> {
+    use serde_json::json;
 
-        Ok(())
+    let value: Value = serde_json::from_slice(stdout)?;
+
+    let is_playlist = value["_type"] == json!("playlist");
+    if is_playlist {
+        let playlist: Playlist = serde_json::from_value(value)?;
+        Ok(YoutubeDlOutput::Playlist(Box::new(playlist)))
+    } else {
+        let video: SingleVideo = serde_json::from_value(value)?;
+        Ok(YoutubeDlOutput::SingleVideo(Box::new(video)))
     }
 }
 
@@ -854,10 +2455,14 @@ struct ProcessResult {
 
 #[cfg(test)]
 mod tests {
-    use crate::{Protocol, SearchOptions, YoutubeDl};
+    use crate::{
+        BinaryInvocation, Bytes, CheckFormats, CompatOption, Error, Flavor, LinkFormat,
+        LoudnessTarget, MetadataCache, PostprocessorStage, Protocol, SearchOptions, SingleVideo,
+        YoutubeDl,
+    };
 
-    use std::path::Path;
-    use std::time::Duration;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, Instant};
 
     #[test]
     fn test_youtube_url() {
@@ -982,6 +2587,1042 @@ mod tests {
         assert!(matches!(unknown_protocol, Protocol::Unknown));
     }
 
+    /// Writes a shell script that stands in for yt-dlp.
+    #[cfg(unix)]
+    pub(crate) fn fake_yt_dlp(dir: &Path, script: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join("yt-dlp");
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_to_builder() {
+        let video = SingleVideo {
+            id: "abc".into(),
+            webpage_url: Some("https://www.youtube.com/watch?v=abc".into()),
+            format_id: Some("137+140".into()),
+            ..Default::default()
+        };
+        let builder = video.to_builder().unwrap();
+        let args = builder.process_download_args(".");
+        assert_eq!(args.last(), Some(&"https://www.youtube.com/watch?v=abc"));
+        assert!(args.windows(2).any(|w| w == ["-f", "137+140"]));
+
+        let video = SingleVideo {
+            webpage_url: None,
+            ..video
+        };
+        assert!(video.to_builder().is_none());
+    }
+
+    #[test]
+    fn test_simulate_and_skip_download() {
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        assert!(builder
+            .process_download_args(".")
+            .contains(&"--no-simulate"));
+
+        builder.skip_download(true).extra_arg("--write-info-json");
+        let args = builder.process_download_args(".");
+        assert!(args.contains(&"--no-simulate"));
+        assert!(args.contains(&"--skip-download"));
+
+        builder.simulate(true);
+        let args = builder.process_download_args(".");
+        assert!(args.contains(&"--simulate"));
+        assert!(!args.contains(&"--no-simulate"));
+    }
+
+    #[test]
+    fn test_write_link() {
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder
+            .write_link(LinkFormat::Url)
+            .write_link(LinkFormat::Desktop)
+            .write_link(LinkFormat::Url);
+        let args = builder.process_download_args(".");
+        assert_eq!(
+            args.iter()
+                .filter(|arg| arg.starts_with("--write-"))
+                .collect::<Vec<_>>(),
+            [&"--write-url-link", &"--write-desktop-link"]
+        );
+    }
+
+    #[test]
+    fn test_compat_options() {
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.compat_options(&[
+            CompatOption::NoLiveChat,
+            CompatOption::NoKeepSubs,
+            CompatOption::Custom("-multistreams".into()),
+        ]);
+        let args = builder.process_args();
+        assert!(args.windows(2).any(|w| w
+            == [
+                "--compat-options",
+                "no-live-chat,no-keep-subs,-multistreams"
+            ]));
+
+        builder.compat_options(&[]);
+        assert!(!builder.process_args().contains(&"--compat-options"));
+    }
+
+    #[test]
+    fn test_buffer_options() {
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder
+            .http_chunk_size(Bytes::mib(10))
+            .buffer_size(Bytes::kib(16))
+            .no_resize_buffer(true);
+        let args = builder.process_download_args(".");
+        assert!(args
+            .windows(2)
+            .any(|w| w == ["--http-chunk-size", "10485760"]));
+        assert!(args.windows(2).any(|w| w == ["--buffer-size", "16384"]));
+        assert!(args.contains(&"--no-resize-buffer"));
+    }
+
+    #[test]
+    fn test_multistreams() {
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.format("bv+ba+ba.2").audio_multistreams(true);
+        let args = builder.process_args();
+        assert!(args.contains(&"--audio-multistreams"));
+        assert!(!args.contains(&"--video-multistreams"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_list_plugins() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = r#"for arg; do [ "$prev" = "--plugin-dirs" ] && echo "[debug] Plugin directories: ['$arg']" >&2; prev=$arg; done
+echo '[debug] Extractor Plugins: SampleIE (sample)' >&2
+exit 2"#;
+        let path = fake_yt_dlp(dir.path(), script);
+
+        let plugins = YoutubeDl::new("")
+            .youtube_dl_path(path)
+            .no_plugins(true)
+            .plugin_dirs(["/opt/plugins"])
+            .list_plugins()
+            .unwrap();
+        assert_eq!(plugins.directories, [Path::new("/opt/plugins")]);
+        assert_eq!(plugins.extractors, ["SampleIE"]);
+    }
+
+    #[test]
+    fn test_normalize_audio() {
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.normalize_audio(LoudnessTarget::PODCAST);
+        let args = builder.process_download_args(".");
+        assert!(args.contains(&"--extract-audio"));
+        assert!(args.windows(2).any(|w| w == ["--audio-format", "mp3"]));
+        assert!(args.windows(2).any(|w| w
+            == [
+                "--postprocessor-args",
+                "ExtractAudio+ffmpeg_o:-af loudnorm=I=-16:TP=-1.5:LRA=11"
+            ]));
+
+        builder.audio_format("flac");
+        let args = builder.process_download_args(".");
+        assert!(args.windows(2).any(|w| w == ["--audio-format", "flac"]));
+        assert!(!args.contains(&"mp3"));
+    }
+
+    #[test]
+    fn test_use_postprocessor() {
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder
+            .use_postprocessor(
+                "ModifyChapters",
+                PostprocessorStage::PostProcess,
+                [("remove_chapters_patterns", "Intro")],
+            )
+            .use_postprocessor(
+                "SamplePlugin",
+                PostprocessorStage::AfterMove,
+                None::<(&str, &str)>,
+            );
+        let args = builder.process_download_args(".");
+        let postprocessors: Vec<_> = args
+            .windows(2)
+            .filter(|w| w[0] == "--use-postprocessor")
+            .map(|w| w[1])
+            .collect();
+        assert_eq!(
+            postprocessors,
+            [
+                "ModifyChapters:when=post_process;remove_chapters_patterns=Intro",
+                "SamplePlugin:when=after_move"
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_on_file_downloaded() {
+        use std::path::PathBuf;
+        use std::sync::{Arc, Mutex};
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = r#"echo "[download] Destination: $2/a.webm"
+echo "[youtube_dl_rs:file] $2/a.webm"
+echo "[youtube_dl_rs:file] $2/b.mp4""#;
+        let path = fake_yt_dlp(dir.path(), script);
+        let files = Arc::new(Mutex::new(Vec::<PathBuf>::new()));
+
+        let recorded = files.clone();
+        YoutubeDl::new("https://example.com/playlist")
+            .youtube_dl_path(path)
+            .on_file_downloaded(move |file| recorded.lock().unwrap().push(file.to_owned()))
+            .download_to(dir.path())
+            .unwrap();
+
+        assert_eq!(
+            *files.lock().unwrap(),
+            [dir.path().join("a.webm"), dir.path().join("b.mp4")]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_tee_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(
+            dir.path(),
+            r#"echo '{"id": "abc"}'; echo 'WARNING: something' >&2"#,
+        );
+        let stdout_log = dir.path().join("stdout.log");
+        let stderr_log = dir.path().join("stderr.log");
+        let output = YoutubeDl::new("https://www.youtube.com/watch?v=abc")
+            .youtube_dl_path(path)
+            .tee_stdout(std::fs::File::create(&stdout_log).unwrap())
+            .tee_stderr(std::fs::File::create(&stderr_log).unwrap())
+            .run()
+            .unwrap();
+
+        assert_eq!(output.into_single_video().unwrap().id, "abc");
+        assert_eq!(
+            std::fs::read_to_string(stdout_log).unwrap(),
+            "{\"id\": \"abc\"}\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(stderr_log).unwrap(),
+            "WARNING: something\n"
+        );
+    }
+
+    #[cfg(all(unix, feature = "tokio"))]
+    #[tokio::test]
+    async fn test_tee_output_async() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(
+            dir.path(),
+            r#"echo '{"id": "abc"}'; echo 'WARNING: something' >&2"#,
+        );
+        let stdout_log = dir.path().join("stdout.log");
+        let stderr_log = dir.path().join("stderr.log");
+        YoutubeDl::new("https://www.youtube.com/watch?v=abc")
+            .youtube_dl_path(path)
+            .tee_stdout(std::fs::File::create(&stdout_log).unwrap())
+            .tee_stderr(std::fs::File::create(&stderr_log).unwrap())
+            .run_async()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(stdout_log).unwrap(),
+            "{\"id\": \"abc\"}\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(stderr_log).unwrap(),
+            "WARNING: something\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_before_spawn() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(dir.path(), r#"echo "{\"id\": \"$VIDEO_ID\"}""#);
+        let output = YoutubeDl::new("https://www.youtube.com/watch?v=abc")
+            .youtube_dl_path(path)
+            .before_spawn(|command| {
+                command.env("VIDEO_ID", "abc");
+            })
+            .run()
+            .unwrap();
+        assert_eq!(output.into_single_video().unwrap().id, "abc");
+    }
+
+    #[cfg(all(unix, feature = "tokio"))]
+    #[tokio::test]
+    async fn test_before_spawn_async() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(dir.path(), r#"echo "{\"id\": \"$A$B\"}""#);
+        let output = YoutubeDl::new("https://www.youtube.com/watch?v=abc")
+            .youtube_dl_path(path)
+            .before_spawn(|command| {
+                command.env("A", "ab");
+            })
+            .before_spawn_async(|command| {
+                command.env("B", "c");
+            })
+            .run_async()
+            .await
+            .unwrap();
+        assert_eq!(output.into_single_video().unwrap().id, "abc");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_throttle_mitigation() {
+        use crate::{DownloadOutcome, ThrottleMitigation};
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = r#"case "$*" in
+    *player_client=tv*) echo "[download] Destination: /downloads/abc.mp4" ;;
+    *) echo "WARNING: [youtube] abc: You may experience throttling for some formats" >&2 ;;
+esac"#;
+        let path = fake_yt_dlp(dir.path(), script);
+        let mut throttle = ThrottleMitigation::new(100_000);
+        throttle.fallback_client("tv");
+        let report = YoutubeDl::new("https://www.youtube.com/watch?v=abc")
+            .youtube_dl_path(path)
+            .throttle_mitigation(throttle)
+            .download_to(dir.path())
+            .unwrap();
+        assert_eq!(
+            report.outcomes,
+            [DownloadOutcome::Downloaded("/downloads/abc.mp4".into())]
+        );
+    }
+
+    #[test]
+    fn test_throttle_fallbacks_keep_player_clients() {
+        use crate::{AgeGateBypass, ThrottleMitigation};
+
+        let mut throttle = ThrottleMitigation::new(100_000);
+        throttle
+            .fallback_client("tv")
+            .fallback_client("web_embedded");
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder
+            .use_age_gate_bypass(AgeGateBypass::EmbeddedClient)
+            .throttle_mitigation(throttle);
+        let clients: Vec<_> = builder
+            .throttle_fallbacks()
+            .into_iter()
+            .map(|fallback| fallback.player_clients.unwrap())
+            .collect();
+        assert_eq!(
+            clients,
+            [
+                "youtube:player_client=tv,web_embedded,default",
+                "youtube:player_client=web_embedded,default"
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resume_download() {
+        use crate::DownloadOutcome;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = r#"while [ $# -gt 0 ]; do
+    [ "$1" = --download-archive ] && archive="$2"
+    shift
+done
+if grep -q abc "$archive" 2>/dev/null; then
+    echo "[download] abc has already been recorded in the archive"
+else
+    echo "[download] Destination: /downloads/abc.mp4"
+    echo "youtube abc" >> "$archive"
+fi"#;
+        let path = fake_yt_dlp(dir.path(), script);
+        let mut builder = YoutubeDl::new("https://example.com/playlist");
+        builder
+            .youtube_dl_path(path)
+            .resume_download(dir.path().join("state.txt"));
+
+        let report = builder.download_to(dir.path()).unwrap();
+        assert_eq!(
+            report.outcomes,
+            [DownloadOutcome::Downloaded("/downloads/abc.mp4".into())]
+        );
+        let report = builder.download_to(dir.path()).unwrap();
+        assert_eq!(
+            report.outcomes,
+            [DownloadOutcome::AlreadyInArchive("abc".into())]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stopped_download() {
+        use crate::ExitReason;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(
+            dir.path(),
+            "echo 'Maximum number of downloads reached' >&2; exit 101",
+        );
+        let report = YoutubeDl::new("https://example.com/playlist")
+            .youtube_dl_path(&path)
+            .max_downloads(1)
+            .download_to(dir.path())
+            .unwrap();
+        assert_eq!(report.exit_reason, ExitReason::Stopped);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(dir.path(), "echo 'error: no such option' >&2; exit 2");
+        let err = YoutubeDl::new("https://example.com/playlist")
+            .youtube_dl_path(&path)
+            .download_to(dir.path())
+            .unwrap_err();
+        assert_eq!(err.exit_reason(), Some(ExitReason::InvalidOptions));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_killed_by_signal() {
+        use crate::ExitReason;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(dir.path(), "kill -9 $$");
+        let mut builder = YoutubeDl::new("https://example.com/video");
+        builder.youtube_dl_path(&path);
+        let err = builder.run().unwrap_err();
+        assert!(matches!(err, Error::Killed { signal: 9, .. }));
+        assert_eq!(err.exit_reason(), Some(ExitReason::Signal(9)));
+
+        let report = builder.ignore_errors(true).download_to(dir.path()).unwrap();
+        assert_eq!(report.exit_reason, ExitReason::Signal(9));
+    }
+
+    #[test]
+    fn test_age_gate_bypass() {
+        use crate::AgeGateBypass;
+
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.use_age_gate_bypass(AgeGateBypass::CookiesFromBrowser("firefox".into()));
+        let args = builder.effective_args();
+        assert!(args
+            .windows(2)
+            .any(|pair| pair == ["--cookies-from-browser", "firefox"]));
+
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder
+            .use_age_gate_bypass(AgeGateBypass::EmbeddedClient)
+            .extra_arg_pair("--extractor-args", "youtube:skip=dash");
+        assert_eq!(
+            builder.effective_args(),
+            [
+                "--extractor-args",
+                "youtube:player_client=web_embedded,default",
+                "--extractor-args",
+                "youtube:skip=dash",
+                "-J",
+                "https://www.youtube.com/watch?v=abc"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unplayable_options() {
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.ignore_no_formats_error(true);
+        let args = builder.process_args();
+        assert!(args.contains(&"--ignore-no-formats-error"));
+        assert!(!args.contains(&"--allow-unplayable-formats"));
+
+        builder.allow_unplayable_formats(true);
+        assert!(builder
+            .process_args()
+            .contains(&"--allow-unplayable-formats"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_nice() {
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.nice(10);
+        let command = builder.command(vec!["-J"]);
+        assert_eq!(command.get_program(), "nice");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            ["-n", "10", "yt-dlp", "-J"]
+        );
+    }
+
+    #[test]
+    fn test_command_prefix() {
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.command_prefix(["docker", "exec", "dl"]);
+        let command = builder.command(vec!["-J"]);
+        assert_eq!(command.get_program(), "docker");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            ["exec", "dl", "yt-dlp", "-J"]
+        );
+    }
+
+    #[test]
+    fn test_python_module_invocation() {
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.invocation(BinaryInvocation::PythonModule {
+            interpreter: PathBuf::from("python3"),
+        });
+        let command = builder.command(vec!["-J"]);
+        assert_eq!(command.get_program(), "python3");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            ["-m", "yt_dlp", "-J"]
+        );
+    }
+
+    #[test]
+    fn test_effective_args() {
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder
+            .format("best")
+            .output_directory("out")
+            .extra_arg("-f")
+            .extra_arg("bv+ba");
+        assert_eq!(
+            builder.effective_args(),
+            [
+                "-f",
+                "bv+ba",
+                "-P",
+                "out",
+                "-J",
+                "https://www.youtube.com/watch?v=abc"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extra_arg_pairs() {
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.extra_arg_pair("--audio-format", "mp3").extra_args([
+            "--embed-metadata",
+            "--parse-metadata",
+            "title:%(artist)s",
+        ]);
+        let args = builder.process_args();
+        assert_eq!(
+            &args[..5],
+            [
+                "--audio-format",
+                "mp3",
+                "--embed-metadata",
+                "--parse-metadata",
+                "title:%(artist)s"
+            ]
+        );
+
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder
+            .extra_arg_pair("--extractor-args", "youtube:player_client=tv")
+            .extra_arg_pair("--extractor-args", "youtube:skip=dash");
+        assert_eq!(
+            builder.effective_args(),
+            [
+                "--extractor-args",
+                "youtube:player_client=tv",
+                "--extractor-args",
+                "youtube:skip=dash",
+                "-J",
+                "https://www.youtube.com/watch?v=abc"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_args() {
+        struct ExtractorArgs(Vec<&'static str>);
+
+        impl crate::IntoYtDlpArgs for ExtractorArgs {
+            fn into_args(self) -> Vec<String> {
+                self.0
+                    .into_iter()
+                    .flat_map(|arg| ["--extractor-args".to_string(), arg.to_string()])
+                    .collect()
+            }
+        }
+
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.apply_args(ExtractorArgs(vec![
+            "youtube:player_client=tv",
+            "youtube:skip=dash",
+        ]));
+        assert_eq!(
+            builder.effective_args(),
+            [
+                "--extractor-args",
+                "youtube:player_client=tv",
+                "--extractor-args",
+                "youtube:skip=dash",
+                "-J",
+                "https://www.youtube.com/watch?v=abc"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_legacy_flavor() {
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.flavor(Flavor::YoutubeDl).output_directory("out");
+        assert_eq!(builder.program(), ["youtube-dl"]);
+        assert!(!builder.process_args().contains(&"-P"));
+
+        let location = builder.download_location(Path::new("downloads"));
+        let args = builder.process_download_args(&location);
+        assert!(!args.contains(&"-P"));
+        assert!(!args.contains(&"--no-simulate"));
+        let output = args.iter().position(|a| *a == "-o").unwrap();
+        assert_eq!(
+            Path::new(args[output + 1]),
+            Path::new("downloads").join("%(title)s-%(id)s.%(ext)s")
+        );
+
+        builder.invocation(BinaryInvocation::PythonModule {
+            interpreter: PathBuf::from("python3"),
+        });
+        assert_eq!(builder.program(), ["python3", "-m", "youtube_dl"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_metadata_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let counter = dir.path().join("runs");
+        let script = format!(
+            "echo run >> '{}'\necho '{{\"id\": \"abc\"}}'",
+            counter.display()
+        );
+        let path = fake_yt_dlp(dir.path(), &script);
+
+        let cache = MetadataCache::new(10);
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.youtube_dl_path(path).cache(&cache);
+        builder.run().unwrap();
+        builder.clone().run().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&counter).unwrap().lines().count(),
+            1
+        );
+
+        builder.format("best").run().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&counter).unwrap().lines().count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_redacted_debug() {
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder
+            .auth("user", "hunter2")
+            .cookies("secret-cookies.txt")
+            .extra_arg_pair("--video-password", "letmein");
+        let debug = format!("{:?}", builder);
+        assert!(debug.contains("user"));
+        for secret in ["hunter2", "secret-cookies", "letmein"] {
+            assert!(!debug.contains(secret));
+            assert!(!builder.redacted_args().iter().any(|a| a.contains(secret)));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_timeout_while_reading() {
+        let dir = tempfile::tempdir().unwrap();
+        // Keeps stdout open without printing anything.
+        let path = fake_yt_dlp(dir.path(), "exec sleep 30");
+        let start = Instant::now();
+        let result = YoutubeDl::new("https://www.youtube.com/watch?v=abc")
+            .youtube_dl_path(path)
+            .process_timeout(Duration::from_millis(200))
+            .run();
+        assert!(matches!(result, Err(Error::ProcessTimeout)));
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_no_hooks_after_timeout() {
+        use crate::Hooks;
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct Lines(Mutex<Vec<String>>);
+
+        impl Hooks for Lines {
+            fn on_stderr_line(&self, _url: &str, line: &str) {
+                self.0.lock().unwrap().push(line.to_string());
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        // The subshell keeps stderr open and writes to it after the timeout.
+        let path = fake_yt_dlp(
+            dir.path(),
+            "(sleep 0.4; echo 'WARNING: late' >&2) &\nexec sleep 30",
+        );
+        let lines = Arc::new(Lines::default());
+        let result = YoutubeDl::new("https://www.youtube.com/watch?v=abc")
+            .youtube_dl_path(path)
+            .process_timeout(Duration::from_millis(200))
+            .hooks(lines.clone())
+            .run();
+        assert!(matches!(result, Err(Error::ProcessTimeout)));
+        std::thread::sleep(Duration::from_millis(600));
+        assert!(lines.0.lock().unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(
+            dir.path(),
+            "printf 'caf\\351\\n'\nprintf 'ERROR: caf\\351\\n' >&2\nexit $1",
+        );
+        // The exit code is the first argument.
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.youtube_dl_path(&path).extra_arg("0");
+        assert_eq!(builder.run_raw_bytes().unwrap(), b"caf\xe9\n");
+
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.youtube_dl_path(&path).extra_arg("1");
+        match builder.run_raw_bytes() {
+            Err(Error::ExitCode { stderr, .. }) => assert_eq!(stderr, "ERROR: caf\u{fffd}\n"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_playlist_with_invalid_entries() {
+        let json = br#"{
+            "_type": "playlist",
+            "id": "PL1",
+            "entries": [
+                {"id": "a", "title": "Available"},
+                null,
+                {"title": "[Private video]"}
+            ]
+        }"#;
+        let playlist = super::parse_json_output(json)
+            .unwrap()
+            .into_playlist()
+            .unwrap();
+        assert_eq!(playlist.entries.as_ref().unwrap().len(), 1);
+        assert_eq!(playlist.invalid_entries.len(), 1);
+        assert_eq!(playlist.invalid_entries[0]["title"], "[Private video]");
+
+        let roundtrip: crate::Playlist =
+            serde_json::from_value(serde_json::to_value(&playlist).unwrap()).unwrap();
+        assert_eq!(roundtrip, playlist);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_strict_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(dir.path(), r#"echo '{"id": "abc", "brand_new": 1}'"#);
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.youtube_dl_path(path);
+        assert!(builder.run().is_ok());
+
+        match builder.strict_model(true).run() {
+            Err(Error::UnmodeledFields { fields }) => assert_eq!(fields, ["brand_new"]),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_check_formats() {
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        assert!(!builder.process_args().iter().any(|a| a.contains("check")));
+
+        builder.check_formats(CheckFormats::All);
+        assert!(builder.process_args().contains(&"--check-all-formats"));
+    }
+
+    #[test]
+    fn test_dedupe_entries() {
+        use std::collections::HashSet;
+
+        let video = |id: &str| SingleVideo {
+            id: id.into(),
+            title: Some(format!("Video {}", id)),
+            ..Default::default()
+        };
+        let mut entries = vec![video("a"), video("b"), video("a")];
+        assert_eq!(entries[0], entries[2]);
+        assert_ne!(entries[0], entries[1]);
+
+        let mut seen = HashSet::new();
+        entries.retain(|entry| seen.insert(entry.id.clone()));
+        assert_eq!(entries, [video("a"), video("b")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_max_stdout_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(dir.path(), "yes '{\"id\": \"abc\"}'");
+
+        let result = YoutubeDl::new("https://example.com/channel")
+            .youtube_dl_path(path)
+            .max_stdout_bytes(1024)
+            .process_timeout(Duration::from_secs(10))
+            .run();
+        assert!(matches!(result, Err(Error::OutputTooLarge { limit: 1024 })));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fallback_paths() {
+        let stable = tempfile::tempdir().unwrap();
+        let nightly = tempfile::tempdir().unwrap();
+        let stable = fake_yt_dlp(
+            stable.path(),
+            "echo 'ERROR: [youtube] abc: Unable to extract initial data; please report this issue on https://github.com/yt-dlp/yt-dlp/issues' >&2; exit 1",
+        );
+        let nightly = fake_yt_dlp(nightly.path(), "echo '{\"id\": \"abc\"}'");
+
+        let video = YoutubeDl::new("https://www.youtube.com/watch?v=abc")
+            .youtube_dl_path(&stable)
+            .fallback_paths(vec![&nightly])
+            .run()
+            .unwrap()
+            .into_single_video()
+            .unwrap();
+        assert_eq!(video.id, "abc");
+
+        let unavailable = tempfile::tempdir().unwrap();
+        let unavailable = fake_yt_dlp(
+            unavailable.path(),
+            "echo 'ERROR: [youtube] abc: Video unavailable' >&2; exit 1",
+        );
+        let result = YoutubeDl::new("https://www.youtube.com/watch?v=abc")
+            .youtube_dl_path(unavailable)
+            .fallback_paths(vec![&nightly])
+            .run();
+        assert!(matches!(result, Err(Error::ExitCode { code: 1, .. })));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fallback_paths_after_download() {
+        use crate::Hooks;
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct Retries(Mutex<Vec<u32>>);
+
+        impl Hooks for Retries {
+            fn on_retry(&self, _url: &str, attempt: u32, _error: &Error) {
+                self.0.lock().unwrap().push(attempt);
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("fallback-ran");
+        let stable = fake_yt_dlp(
+            dir.path(),
+            "echo '[download] Destination: /downloads/a.mp4'
+echo 'ERROR: [youtube] b: Unable to extract initial data' >&2; exit 1",
+        );
+        let nightly = tempfile::tempdir().unwrap();
+        let nightly = fake_yt_dlp(nightly.path(), &format!("touch {}", marker.display()));
+        let retries = Arc::new(Retries::default());
+
+        let result = YoutubeDl::new("https://example.com/playlist")
+            .youtube_dl_path(&stable)
+            .fallback_paths([&nightly])
+            .hooks(retries.clone())
+            .download_to(dir.path());
+        assert!(matches!(result, Err(Error::ExitCode { code: 1, .. })));
+        assert!(!marker.exists());
+        assert!(retries.0.lock().unwrap().is_empty());
+
+        let broken = fake_yt_dlp(
+            dir.path(),
+            "echo 'ERROR: [youtube] b: Unable to extract initial data' >&2; exit 1",
+        );
+        YoutubeDl::new("https://example.com/playlist")
+            .youtube_dl_path(broken)
+            .fallback_paths([&nightly])
+            .hooks(retries.clone())
+            .download_to(dir.path())
+            .unwrap();
+        assert!(marker.exists());
+        assert_eq!(*retries.0.lock().unwrap(), [1]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_capture_stderr_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(
+            dir.path(),
+            "for i in $(seq 1 1000); do echo \"WARNING: fragment $i not found\" >&2; done; exit 1",
+        );
+
+        let result = YoutubeDl::new("https://example.com/video")
+            .youtube_dl_path(path)
+            .capture_stderr_lines(2)
+            .run();
+        match result {
+            Err(Error::ExitCode { code: 1, stderr }) => assert_eq!(
+                stderr,
+                "WARNING: fragment 999 not found\nWARNING: fragment 1000 not found\n"
+            ),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let path = fake_yt_dlp(
+            dir.path(),
+            "echo '[debug] yt-dlp version 2024.03.10' >&2
+echo 'ERROR: [youtube] abc: HTTP Error 403: Forbidden' >&2
+for i in $(seq 1 1000); do echo \"WARNING: fragment $i not found\" >&2; done; exit 1",
+        );
+        let result = YoutubeDl::new("https://example.com/video")
+            .youtube_dl_path(path)
+            .capture_stderr_lines(1)
+            .run();
+        let error = result.unwrap_err();
+        assert_eq!(error.kind(), crate::ErrorKind::HttpStatus(403));
+        match error {
+            Error::ExitCode { stderr, .. } => assert_eq!(
+                stderr,
+                "[debug] yt-dlp version 2024.03.10\nERROR: [youtube] abc: HTTP Error 403: Forbidden\nWARNING: fragment 1000 not found\n"
+            ),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_json_schema() {
+        let schema = serde_json::to_value(schemars::schema_for!(crate::YoutubeDlOutput)).unwrap();
+        let definitions = &schema["$defs"];
+        assert_eq!(
+            definitions["SingleVideo"]["required"],
+            serde_json::json!(["id"])
+        );
+        assert!(definitions["Format"]["properties"]["vcodec"].is_object());
+        assert!(definitions["Protocol"].to_string().contains("m3u8_native"));
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_playlist_json_schema() {
+        let schema = serde_json::to_value(schemars::schema_for!(crate::Playlist)).unwrap();
+        assert_eq!(
+            schema["properties"]["entries"]["items"]["$ref"],
+            "#/$defs/SingleVideo"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_and_download() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = r#"echo '{"id": "abc", "requested_downloads": [{"filepath": "'"$2"'/abc.mp4", "_filename": "abc.mp4", "ext": "mp4", "format_id": "137+140", "requested_formats": [{"format_id": "137"}, {"format_id": "140"}]}]}'"#;
+        let path = fake_yt_dlp(dir.path(), script);
+
+        let video = YoutubeDl::new("https://example.com/video")
+            .youtube_dl_path(path)
+            .run_and_download(dir.path())
+            .unwrap()
+            .into_single_video()
+            .unwrap();
+        let download = &video.requested_downloads.unwrap()[0];
+        let expected = dir.path().join("abc.mp4");
+        assert_eq!(download.filepath.as_deref(), expected.to_str());
+        assert_eq!(download.filename.as_deref(), Some("abc.mp4"));
+        assert_eq!(download.requested_formats.as_ref().unwrap().len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_and_download_is_not_replayed() {
+        let dir = tempfile::tempdir().unwrap();
+        let fixtures = dir.path().join("fixtures");
+        let path = fake_yt_dlp(dir.path(), r#"echo '{"id": "abc"}'"#);
+
+        let mut builder = YoutubeDl::new("https://example.com/video");
+        builder.youtube_dl_path(path).record_to(&fixtures);
+        builder.run_and_download(dir.path()).unwrap();
+        assert!(!fixtures.exists());
+
+        // Replay mode has no fixture, but the download still runs.
+        builder.replay_from(&fixtures);
+        let video = builder.run_and_download(dir.path()).unwrap();
+        assert_eq!(video.into_single_video().unwrap().id, "abc");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hooks() {
+        use crate::{Hooks, Progress};
+        use std::process::ExitStatus;
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<String>>);
+
+        impl Hooks for Recorder {
+            fn on_spawn(&self, url: &str, _pid: u32) {
+                self.0.lock().unwrap().push(format!("spawn {}", url));
+            }
+
+            fn on_complete(&self, _url: &str, exit: ExitStatus, _: Duration, stdout_len: usize) {
+                let line = format!("complete {:?} {}", exit.code(), stdout_len > 0);
+                self.0.lock().unwrap().push(line);
+            }
+
+            fn on_progress(&self, _url: &str, progress: &Progress) {
+                let line = format!("progress {:?}", progress.fraction());
+                self.0.lock().unwrap().push(line);
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = "echo '[youtube_dl_rs:progress] downloading 5 10 NA 1.0 5 abc'\n\
+                      echo '[download] 100% of 10.00B'\n\
+                      echo '[youtube_dl_rs:progress] finished 10 10 NA NA NA abc'";
+        let path = fake_yt_dlp(dir.path(), script);
+        let recorder = Arc::new(Recorder::default());
+
+        YoutubeDl::new("https://example.com/video")
+            .youtube_dl_path(path)
+            .hooks(recorder.clone())
+            .download_to(dir.path())
+            .unwrap();
+
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            vec![
+                "spawn https://example.com/video",
+                "progress Some(0.5)",
+                "progress Some(1.0)",
+                "complete Some(0) true",
+            ]
+        );
+    }
+
     #[test]
     fn test_download_to_destination() {
         let dir = tempfile::tempdir().unwrap();
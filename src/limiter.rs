@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::urls::{is_video_id, split_youtube_url};
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Limits how often yt-dlp is started for the same site, to avoid being rate limited or banned
+/// when crawling. Each site (the domain of the URL, or the prefix of a search like `ytsearch`)
+/// has its own token bucket that allows bursts of `requests` invocations and then refills at
+/// `requests` per `period`. Clones share the buckets, so one limiter can be used by many
+/// builders (see `YoutubeDl::limiter`).
+#[derive(Clone, Debug)]
+pub struct Limiter {
+    buckets: Arc<Mutex<HashMap<String, Bucket>>>,
+    requests: u32,
+    period: Duration,
+}
+
+impl Limiter {
+    /// Allow `requests` invocations per `period` for each site.
+    pub fn new(requests: u32, period: Duration) -> Self {
+        assert!(requests > 0, "the limiter must allow at least one request");
+        Self {
+            buckets: Arc::default(),
+            requests,
+            period,
+        }
+    }
+
+    /// Take a token for the site of `url`, returning how long to wait before starting yt-dlp.
+    /// Tokens are reserved in the order this is called, so waiting callers are served fairly.
+    pub(crate) fn reserve(&self, url: &str) -> Duration {
+        let capacity = f64::from(self.requests);
+        let per_second = capacity / self.period.as_secs_f64();
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(site(url)).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let refill = now.duration_since(bucket.updated).as_secs_f64() * per_second;
+        bucket.tokens = (bucket.tokens + refill).min(capacity) - 1.0;
+        bucket.updated = now;
+
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / per_second)
+        }
+    }
+}
+
+/// The site of a URL (used for rate limiting and profiles): the lowercase host without `www.`,
+/// or the search prefix without the count. All YouTube hosts and bare video IDs are
+/// `youtube.com`.
+pub(crate) fn site(url: &str) -> String {
+    let url = url.trim();
+    if split_youtube_url(url).is_some() || is_video_id(url) {
+        return "youtube.com".to_string();
+    }
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest,
+        None => {
+            // e.g. `ytsearch5:query`, unlike a host with a port like `example.com:8080/1`.
+            let prefix = url.split(':').next().unwrap_or_default();
+            if url.contains(':') && !prefix.contains(['.', '/']) {
+                let prefix = prefix.trim_end_matches(|c: char| c.is_ascii_digit());
+                let prefix = prefix.strip_suffix("all").unwrap_or(prefix);
+                return prefix.to_ascii_lowercase();
+            }
+            url
+        }
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = host.rsplit('@').next().unwrap_or_default();
+    let host = host
+        .split(':')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match host.strip_prefix("www.") {
+        Some(host) => host.to_string(),
+        None => host,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{site, Limiter};
+    use std::time::Duration;
+
+    #[test]
+    fn test_site() {
+        assert_eq!(site("https://www.youtube.com/watch?v=abc"), "youtube.com");
+        assert_eq!(site("https://user@vimeo.com:443/123"), "vimeo.com");
+        assert_eq!(site("https://WWW.Vimeo.COM/123"), "vimeo.com");
+        assert_eq!(site("vimeo.com/123"), "vimeo.com");
+        assert_eq!(site("example.com:8080/video"), "example.com");
+
+        // All YouTube URLs share one bucket.
+        assert_eq!(site("youtube.com/watch?v=dQw4w9WgXcQ"), "youtube.com");
+        assert_eq!(site("dQw4w9WgXcQ"), "youtube.com");
+        assert_eq!(site("https://youtu.be/dQw4w9WgXcQ"), "youtube.com");
+        assert_eq!(site("https://m.youtube.com/watch?v=abc"), "youtube.com");
+        assert_eq!(site("https://music.youtube.com/watch?v=abc"), "youtube.com");
+
+        assert_eq!(site("ytsearch:rust"), "ytsearch");
+        assert_eq!(site("ytsearch5:rust"), "ytsearch");
+        assert_eq!(site("ytsearchall:rust"), "ytsearch");
+        assert_eq!(site("ytsearchdate10:rust"), "ytsearchdate");
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let limiter = Limiter::new(2, Duration::from_secs(10));
+        let url = "https://www.youtube.com/watch?v=abc";
+        assert_eq!(limiter.reserve(url), Duration::ZERO);
+        assert_eq!(limiter.reserve(url), Duration::ZERO);
+
+        let wait = limiter.reserve(url);
+        assert!(wait > Duration::from_secs(4) && wait <= Duration::from_secs(5));
+        let wait = limiter.reserve(url);
+        assert!(wait > Duration::from_secs(9) && wait <= Duration::from_secs(10));
+
+        // Other sites have their own bucket.
+        assert_eq!(limiter.reserve("https://vimeo.com/1"), Duration::ZERO);
+    }
+}
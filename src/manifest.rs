@@ -0,0 +1,445 @@
+//! Parsers for the HLS and DASH manifests referenced by `Format::manifest_url`, for players
+//! that select renditions themselves. Only the information needed to pick a rendition is
+//! parsed, not segment lists or DRM information.
+
+use std::collections::HashMap;
+
+use roxmltree::{Document, Node};
+
+use crate::Error;
+
+/// Resolve a URI found in a manifest against the URL of the manifest.
+fn resolve(manifest_url: Option<&str>, uri: &str) -> String {
+    let base = match manifest_url {
+        Some(base) if !uri.contains("://") => base,
+        _ => return uri.to_string(),
+    };
+    let base = base.split(['?', '#']).next().unwrap_or(base);
+    if let Some(path) = uri.strip_prefix('/') {
+        // Keep only the scheme and host.
+        let origin_end = base
+            .find("://")
+            .and_then(|scheme_end| base[scheme_end + 3..].find('/').map(|i| scheme_end + 3 + i))
+            .unwrap_or(base.len());
+        format!("{}/{}", &base[..origin_end], path)
+    } else {
+        let directory_end = base.rfind('/').map_or(0, |i| i + 1);
+        format!("{}{}", &base[..directory_end], uri)
+    }
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidManifest {
+        reason: reason.to_string(),
+    }
+}
+
+/// A frame rate like `30`, `29.97` or `30000/1001`.
+fn parse_frame_rate(value: &str) -> Option<f64> {
+    match value.split_once('/') {
+        Some((numerator, denominator)) => {
+            let denominator: f64 = denominator.parse().ok()?;
+            (denominator != 0.0).then_some(numerator.parse::<f64>().ok()? / denominator)
+        }
+        None => value.parse().ok(),
+    }
+}
+
+/// A variant stream of an HLS master playlist (`#EXT-X-STREAM-INF`).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HlsVariant {
+    /// URL of the media playlist
+    pub uri: String,
+    /// Peak bitrate in bits per second
+    pub bandwidth: Option<u64>,
+    /// Average bitrate in bits per second
+    pub average_bandwidth: Option<u64>,
+    /// Width and height of the video
+    pub resolution: Option<(u32, u32)>,
+    /// Codecs, e.g. `avc1.64001f,mp4a.40.2`
+    pub codecs: Option<String>,
+    /// Frames per second
+    pub frame_rate: Option<f64>,
+    /// Group ID of the audio renditions to play with this variant
+    pub audio: Option<String>,
+    /// Group ID of the subtitle renditions
+    pub subtitles: Option<String>,
+}
+
+/// An alternative rendition of an HLS master playlist (`#EXT-X-MEDIA`), e.g. an audio track in
+/// another language.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HlsRendition {
+    /// `AUDIO`, `VIDEO`, `SUBTITLES` or `CLOSED-CAPTIONS`
+    pub media_type: String,
+    /// The group, referenced by `HlsVariant::audio` or `HlsVariant::subtitles`
+    pub group_id: String,
+    /// Human-readable name
+    pub name: String,
+    /// Language tag, e.g. `en`
+    pub language: Option<String>,
+    /// URL of the media playlist. Missing if the rendition is part of the variant stream.
+    pub uri: Option<String>,
+    /// Whether the rendition should be played without user preferences
+    pub default: bool,
+    /// Whether the rendition may be chosen automatically
+    pub autoselect: bool,
+    /// Number of audio channels, e.g. `2` or `6`
+    pub channels: Option<String>,
+}
+
+/// An HLS master playlist.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HlsMasterPlaylist {
+    /// The variant streams, in the order of the playlist
+    pub variants: Vec<HlsVariant>,
+    /// The alternative renditions
+    pub renditions: Vec<HlsRendition>,
+}
+
+/// Split an attribute list like `BANDWIDTH=1280000,CODECS="avc1,mp4a"` into names and values
+/// (without quotes).
+fn hls_attributes(list: &str) -> HashMap<&str, &str> {
+    let mut attributes = HashMap::new();
+    let mut rest = list;
+    while let Some((name, value)) = rest.split_once('=') {
+        let name = name.trim();
+        let (value, next) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                let (value, next) = quoted.split_once('"').unwrap_or((quoted, ""));
+                (value, next.split_once(',').map_or("", |(_, next)| next))
+            }
+            None => value.split_once(',').unwrap_or((value, "")),
+        };
+        attributes.insert(name, value);
+        rest = next;
+    }
+    attributes
+}
+
+impl HlsMasterPlaylist {
+    /// Parse a master playlist. Relative URIs are resolved against `manifest_url`, if given.
+    pub fn parse(playlist: &str, manifest_url: Option<&str>) -> Result<Self, Error> {
+        let mut lines = playlist.lines().map(str::trim);
+        if lines.next() != Some("#EXTM3U") {
+            return Err(invalid("missing #EXTM3U header"));
+        }
+
+        let mut parsed = HlsMasterPlaylist::default();
+        let mut variant: Option<HlsVariant> = None;
+        for line in lines {
+            if let Some(list) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+                let attributes = hls_attributes(list);
+                let get = |name| attributes.get(name).map(|value| value.to_string());
+                variant = Some(HlsVariant {
+                    uri: String::new(),
+                    bandwidth: attributes.get("BANDWIDTH").and_then(|v| v.parse().ok()),
+                    average_bandwidth: attributes
+                        .get("AVERAGE-BANDWIDTH")
+                        .and_then(|v| v.parse().ok()),
+                    resolution: attributes.get("RESOLUTION").and_then(|v| {
+                        let (width, height) = v.split_once('x')?;
+                        Some((width.parse().ok()?, height.parse().ok()?))
+                    }),
+                    codecs: get("CODECS"),
+                    frame_rate: attributes.get("FRAME-RATE").and_then(|v| v.parse().ok()),
+                    audio: get("AUDIO"),
+                    subtitles: get("SUBTITLES"),
+                });
+            } else if let Some(list) = line.strip_prefix("#EXT-X-MEDIA:") {
+                let attributes = hls_attributes(list);
+                let get = |name| attributes.get(name).map(|value| value.to_string());
+                parsed.renditions.push(HlsRendition {
+                    media_type: get("TYPE").unwrap_or_default(),
+                    group_id: get("GROUP-ID").unwrap_or_default(),
+                    name: get("NAME").unwrap_or_default(),
+                    language: get("LANGUAGE"),
+                    uri: attributes.get("URI").map(|uri| resolve(manifest_url, uri)),
+                    default: attributes.get("DEFAULT") == Some(&"YES"),
+                    autoselect: attributes.get("AUTOSELECT") == Some(&"YES"),
+                    channels: get("CHANNELS"),
+                });
+            } else if !line.is_empty() && !line.starts_with('#') {
+                if let Some(mut variant) = variant.take() {
+                    variant.uri = resolve(manifest_url, line);
+                    parsed.variants.push(variant);
+                } else {
+                    return Err(invalid("not a master playlist"));
+                }
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// A representation of a DASH manifest, with the attributes inherited from its adaptation set.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DashRepresentation {
+    /// The ID of the representation
+    pub id: Option<String>,
+    /// MIME type, e.g. `video/mp4`
+    pub mime_type: Option<String>,
+    /// Codecs, e.g. `avc1.640028`
+    pub codecs: Option<String>,
+    /// Bitrate in bits per second
+    pub bandwidth: Option<u64>,
+    /// Width of the video
+    pub width: Option<u32>,
+    /// Height of the video
+    pub height: Option<u32>,
+    /// Frames per second
+    pub frame_rate: Option<f64>,
+    /// Sampling rate of the audio in Hz
+    pub audio_sampling_rate: Option<u32>,
+    /// Language tag of the adaptation set, e.g. `en`
+    pub language: Option<String>,
+    /// URL of the media, from the `BaseURL` elements of the MPD, period, adaptation set and
+    /// representation, each resolved against the one above it
+    pub base_url: Option<String>,
+}
+
+impl DashRepresentation {
+    /// Whether this is a video representation.
+    pub fn is_video(&self) -> bool {
+        self.mime_type
+            .as_deref()
+            .is_some_and(|mime| mime.starts_with("video/"))
+    }
+
+    /// Whether this is an audio representation.
+    pub fn is_audio(&self) -> bool {
+        self.mime_type
+            .as_deref()
+            .is_some_and(|mime| mime.starts_with("audio/"))
+    }
+}
+
+/// A DASH manifest (MPD).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DashManifest {
+    /// All representations of all periods, in the order of the manifest
+    pub representations: Vec<DashRepresentation>,
+}
+
+/// The child elements with the given name, ignoring namespaces.
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.tag_name().name() == name)
+}
+
+/// The `BaseURL` of an element, resolved against the base URL of its parent, or the parent's
+/// base URL if it has none.
+fn base_url(
+    node: Node<'_, '_>,
+    parent: Option<String>,
+    manifest_url: Option<&str>,
+) -> Option<String> {
+    match children(node, "BaseURL")
+        .next()
+        .and_then(|child| child.text())
+    {
+        Some(text) => Some(resolve(parent.as_deref().or(manifest_url), text.trim())),
+        None => parent,
+    }
+}
+
+impl DashManifest {
+    /// Parse a manifest. Relative base URLs are resolved against `manifest_url`, if given.
+    pub fn parse(manifest: &str, manifest_url: Option<&str>) -> Result<Self, Error> {
+        let document = Document::parse(manifest).map_err(|err| invalid(&err.to_string()))?;
+        let mpd = document.root_element();
+        if mpd.tag_name().name() != "MPD" {
+            return Err(invalid("missing MPD element"));
+        }
+        let mut parsed = DashManifest::default();
+        let mpd_base = base_url(mpd, None, manifest_url);
+        for period in children(mpd, "Period") {
+            let period_base = base_url(period, mpd_base.clone(), manifest_url);
+            for adaptation_set in children(period, "AdaptationSet") {
+                let inherited = DashRepresentation {
+                    base_url: base_url(adaptation_set, period_base.clone(), manifest_url),
+                    ..DashRepresentation::default()
+                }
+                .inherit(adaptation_set);
+                for representation in children(adaptation_set, "Representation") {
+                    let mut new = inherited.clone().inherit(representation);
+                    new.base_url = base_url(representation, new.base_url, manifest_url);
+                    parsed.representations.push(new);
+                }
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+impl DashRepresentation {
+    /// Override the attributes that are set on the tag.
+    fn inherit(mut self, node: Node<'_, '_>) -> Self {
+        let get = |name| node.attribute(name).map(str::to_string);
+        self.id = get("id").or(self.id);
+        self.mime_type = get("mimeType").or(self.mime_type);
+        self.codecs = get("codecs").or(self.codecs);
+        self.bandwidth = get("bandwidth")
+            .and_then(|value| value.parse().ok())
+            .or(self.bandwidth);
+        self.width = get("width")
+            .and_then(|value| value.parse().ok())
+            .or(self.width);
+        self.height = get("height")
+            .and_then(|value| value.parse().ok())
+            .or(self.height);
+        self.frame_rate = get("frameRate")
+            .and_then(|value| parse_frame_rate(&value))
+            .or(self.frame_rate);
+        self.audio_sampling_rate = get("audioSamplingRate")
+            .and_then(|value| value.parse().ok())
+            .or(self.audio_sampling_rate);
+        self.language = get("lang").or(self.language);
+        if self.mime_type.is_none() {
+            // `contentType` is `video` or `audio`, without the container.
+            self.mime_type = get("contentType").map(|content_type| format!("{}/", content_type));
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve, DashManifest, HlsMasterPlaylist};
+
+    #[test]
+    fn test_resolve() {
+        let base = Some("https://cdn.example.com/hls/master.m3u8?token=1");
+        assert_eq!(
+            resolve(base, "720p/index.m3u8"),
+            "https://cdn.example.com/hls/720p/index.m3u8"
+        );
+        assert_eq!(
+            resolve(base, "/other/index.m3u8"),
+            "https://cdn.example.com/other/index.m3u8"
+        );
+        assert_eq!(
+            resolve(base, "https://a.example.com/x"),
+            "https://a.example.com/x"
+        );
+        assert_eq!(resolve(None, "x.m3u8"), "x.m3u8");
+    }
+
+    #[test]
+    fn test_hls_master_playlist() {
+        let playlist = r#"#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID="aac",NAME="English",LANGUAGE="en",DEFAULT=YES,AUTOSELECT=YES,CHANNELS="2",URI="audio/en.m3u8"
+#EXT-X-STREAM-INF:BANDWIDTH=2560000,AVERAGE-BANDWIDTH=2000000,RESOLUTION=1280x720,CODECS="avc1.64001f,mp4a.40.2",FRAME-RATE=29.970,AUDIO="aac"
+720p/index.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=640000,RESOLUTION=640x360
+360p/index.m3u8
+"#;
+        let parsed =
+            HlsMasterPlaylist::parse(playlist, Some("https://cdn.example.com/hls/master.m3u8"))
+                .unwrap();
+
+        assert_eq!(parsed.variants.len(), 2);
+        let variant = &parsed.variants[0];
+        assert_eq!(variant.uri, "https://cdn.example.com/hls/720p/index.m3u8");
+        assert_eq!(variant.bandwidth, Some(2_560_000));
+        assert_eq!(variant.average_bandwidth, Some(2_000_000));
+        assert_eq!(variant.resolution, Some((1280, 720)));
+        assert_eq!(variant.codecs.as_deref(), Some("avc1.64001f,mp4a.40.2"));
+        assert_eq!(variant.frame_rate, Some(29.97));
+        assert_eq!(variant.audio.as_deref(), Some("aac"));
+        assert_eq!(parsed.variants[1].resolution, Some((640, 360)));
+
+        let rendition = &parsed.renditions[0];
+        assert_eq!(rendition.media_type, "AUDIO");
+        assert_eq!(rendition.language.as_deref(), Some("en"));
+        assert!(rendition.default && rendition.autoselect);
+        assert_eq!(
+            rendition.uri.as_deref(),
+            Some("https://cdn.example.com/hls/audio/en.m3u8")
+        );
+
+        assert!(HlsMasterPlaylist::parse("#EXTM3U\n#EXTINF:6,\nseg.ts\n", None).is_err());
+        assert!(HlsMasterPlaylist::parse("<MPD/>", None).is_err());
+    }
+
+    #[test]
+    fn test_dash_manifest() {
+        let manifest = r#"<?xml version="1.0" encoding="UTF-8"?>
+<MPD xmlns="urn:mpeg:dash:schema:mpd:2011" type="static">
+  <Period>
+    <AdaptationSet mimeType="video/mp4" frameRate="30000/1001">
+      <Representation id="137" codecs="avc1.640028" bandwidth="4500000" width="1920" height="1080">
+        <BaseURL>video/137.mp4?a=1&amp;b=2</BaseURL>
+      </Representation>
+      <Representation id="136" codecs="avc1.4d401f" bandwidth="2000000" width="1280" height="720" frameRate="60"/>
+    </AdaptationSet>
+    <AdaptationSet contentType="audio" lang="en">
+      <Representation id="140" mimeType="audio/mp4" codecs="mp4a.40.2" bandwidth="128000" audioSamplingRate="44100"/>
+    </AdaptationSet>
+  </Period>
+</MPD>"#;
+        let parsed =
+            DashManifest::parse(manifest, Some("https://cdn.example.com/dash/manifest.mpd"))
+                .unwrap();
+        let representations = &parsed.representations;
+        assert_eq!(representations.len(), 3);
+
+        let video = &representations[0];
+        assert_eq!(video.id.as_deref(), Some("137"));
+        assert!(video.is_video());
+        assert_eq!((video.width, video.height), (Some(1920), Some(1080)));
+        assert_eq!(video.bandwidth, Some(4_500_000));
+        assert!((video.frame_rate.unwrap() - 29.97).abs() < 0.01);
+        assert_eq!(
+            video.base_url.as_deref(),
+            Some("https://cdn.example.com/dash/video/137.mp4?a=1&b=2")
+        );
+        assert_eq!(representations[1].frame_rate, Some(60.0));
+        assert_eq!(representations[1].base_url, None);
+
+        let audio = &representations[2];
+        assert!(audio.is_audio());
+        assert_eq!(audio.language.as_deref(), Some("en"));
+        assert_eq!(audio.audio_sampling_rate, Some(44100));
+    }
+
+    #[test]
+    fn test_dash_base_urls() {
+        let manifest = r#"<MPD xmlns="urn:mpeg:dash:schema:mpd:2011">
+  <!-- generated by <packager> -->
+  <BaseURL>https://media.example.com/content/</BaseURL>
+  <Period>
+    <BaseURL>period-1/</BaseURL>
+    <AdaptationSet mimeType="video/mp4">
+      <BaseURL>video/</BaseURL>
+      <Representation id="1"><BaseURL>1080.mp4</BaseURL></Representation>
+      <Representation id="2"/>
+    </AdaptationSet>
+    <AdaptationSet mimeType="audio/mp4">
+      <Representation id="3"><BaseURL>/audio.mp4</BaseURL></Representation>
+    </AdaptationSet>
+  </Period>
+</MPD>"#;
+        let parsed =
+            DashManifest::parse(manifest, Some("https://cdn.example.com/dash/manifest.mpd"))
+                .unwrap();
+        let base_urls: Vec<_> = parsed
+            .representations
+            .iter()
+            .map(|representation| representation.base_url.as_deref())
+            .collect();
+        assert_eq!(
+            base_urls,
+            [
+                Some("https://media.example.com/content/period-1/video/1080.mp4"),
+                Some("https://media.example.com/content/period-1/video/"),
+                Some("https://media.example.com/audio.mp4"),
+            ]
+        );
+
+        assert!(DashManifest::parse("<MPD><Period></MPD>", None).is_err());
+    }
+}
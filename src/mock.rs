@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use crate::{Error, MetadataFetcher, Playlist, SingleVideo, YoutubeDlOutput};
+
+#[derive(Clone, Debug)]
+enum MockResponse {
+    Output(YoutubeDlOutput),
+    Failure { code: i32, stderr: String },
+}
+
+/// Returns canned `SingleVideo`/`Playlist` fixtures instead of running yt-dlp.
+/// URLs without a registered response fail like yt-dlp does for unsupported URLs.
+#[derive(Clone, Debug, Default)]
+pub struct MockFetcher {
+    responses: HashMap<String, MockResponse>,
+}
+
+impl MockFetcher {
+    /// Create a fetcher without any registered responses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the given video when `url` is fetched.
+    pub fn with_video(self, url: impl Into<String>, video: SingleVideo) -> Self {
+        self.with_output(url, YoutubeDlOutput::SingleVideo(Box::new(video)))
+    }
+
+    /// Return the given playlist when `url` is fetched.
+    pub fn with_playlist(self, url: impl Into<String>, playlist: Playlist) -> Self {
+        self.with_output(url, YoutubeDlOutput::Playlist(Box::new(playlist)))
+    }
+
+    /// Return the given output when `url` is fetched.
+    pub fn with_output(mut self, url: impl Into<String>, output: YoutubeDlOutput) -> Self {
+        self.responses
+            .insert(url.into(), MockResponse::Output(output));
+        self
+    }
+
+    /// Parse the given yt-dlp JSON output (e.g. a fixture file) and return it when `url` is fetched.
+    pub fn with_json(self, url: impl Into<String>, json: &str) -> Result<Self, Error> {
+        let output = crate::parse_json_output(json.as_bytes())?;
+        Ok(self.with_output(url, output))
+    }
+
+    /// Fail with `Error::ExitCode` when `url` is fetched.
+    pub fn with_failure(
+        mut self,
+        url: impl Into<String>,
+        code: i32,
+        stderr: impl Into<String>,
+    ) -> Self {
+        self.responses.insert(
+            url.into(),
+            MockResponse::Failure {
+                code,
+                stderr: stderr.into(),
+            },
+        );
+        self
+    }
+}
+
+impl MetadataFetcher for MockFetcher {
+    fn fetch(&self, url: &str) -> Result<YoutubeDlOutput, Error> {
+        match self.responses.get(url) {
+            Some(MockResponse::Output(output)) => Ok(output.clone()),
+            Some(MockResponse::Failure { code, stderr }) => Err(Error::ExitCode {
+                code: *code,
+                stderr: stderr.clone(),
+            }),
+            None => Err(Error::ExitCode {
+                code: 1,
+                stderr: format!("ERROR: Unsupported URL: {}", url),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MockFetcher;
+    use crate::{Error, MetadataFetcher, SingleVideo};
+
+    #[test]
+    fn test_mock_returns_fixture() {
+        let video = SingleVideo {
+            id: "dQw4w9WgXcQ".into(),
+            title: Some("Never Gonna Give You Up".into()),
+            ..Default::default()
+        };
+        let fetcher = MockFetcher::new().with_video("https://youtu.be/dQw4w9WgXcQ", video);
+
+        let output = fetcher.fetch("https://youtu.be/dQw4w9WgXcQ").unwrap();
+        assert_eq!(output.into_single_video().unwrap().id, "dQw4w9WgXcQ");
+    }
+
+    #[test]
+    fn test_mock_json_and_failures() {
+        let fetcher = MockFetcher::new()
+            .with_json(
+                "https://example.com/list",
+                r#"{"_type": "playlist", "id": "list", "entries": [{"id": "a"}, null]}"#,
+            )
+            .unwrap()
+            .with_failure("https://example.com/private", 1, "ERROR: Private video");
+
+        let playlist = fetcher
+            .fetch("https://example.com/list")
+            .unwrap()
+            .into_playlist()
+            .unwrap();
+        assert_eq!(playlist.entries.unwrap().len(), 1);
+
+        match fetcher.fetch("https://example.com/private") {
+            Err(Error::ExitCode { code, stderr }) => {
+                assert_eq!(code, 1);
+                assert_eq!(stderr, "ERROR: Private video");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(fetcher.fetch("https://example.com/unknown").is_err());
+    }
+}
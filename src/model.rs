@@ -1,5 +1,7 @@
 //! Structs to represent the output of youtube-dl.
 //! The definitions in this module are mostly auto-generated from the JSON output format of youtube-dl.
+//! New fields of `SingleVideo` and `Format` can be added with the `generate_model` example
+//! (`cargo run --example generate_model -- path/to/yt_dlp/extractor/common.py`).
 
 #![allow(missing_docs)]
 
@@ -7,14 +9,16 @@ use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 use std::collections::BTreeMap;
 
-#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Chapter {
     pub end_time: Option<f64>,
     pub start_time: Option<f64>,
     pub title: Option<String>,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Comment {
     pub author: Option<String>,
     pub author_id: Option<String>,
@@ -25,12 +29,14 @@ pub struct Comment {
     pub timestamp: Option<f64>,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Format {
     pub abr: Option<f64>,
     #[serde(default, deserialize_with = "parse_codec")]
     pub acodec: Option<String>,
     pub asr: Option<f64>,
+    pub columns: Option<i64>,
     pub container: Option<String>,
     pub downloader_options: Option<BTreeMap<String, Value>>,
     pub ext: Option<String>,
@@ -43,7 +49,8 @@ pub struct Format {
     pub fragment_base_url: Option<String>,
     pub fragments: Option<Vec<Fragment>>,
     pub height: Option<f64>,
-    pub http_headers: Option<BTreeMap<String, Option<String>>>,
+    #[serde(default, deserialize_with = "parse_headers")]
+    pub http_headers: Option<BTreeMap<String, String>>,
     pub language: Option<String>,
     pub language_preference: Option<i64>,
     pub manifest_url: Option<String>,
@@ -53,6 +60,7 @@ pub struct Format {
     pub protocol: Option<Protocol>,
     pub quality: Option<f64>,
     pub resolution: Option<String>,
+    pub rows: Option<i64>,
     pub source_preference: Option<i64>,
     pub stretched_ratio: Option<f64>,
     pub tbr: Option<f64>,
@@ -63,7 +71,8 @@ pub struct Format {
     pub width: Option<f64>,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Fragment {
     pub duration: Option<Value>,
     pub filesize: Option<i64>,
@@ -71,14 +80,16 @@ pub struct Fragment {
     pub url: Option<String>,
 }
 
-#[derive(Copy, Clone, Serialize, Deserialize, Debug, Default)]
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct HeatmapSample {
     pub start_time: f64,
     pub end_time: f64,
     pub value: f64,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct JsonOutput {
     pub age_limit: Option<i64>,
     pub album: Option<String>,
@@ -152,10 +163,15 @@ pub struct JsonOutput {
     pub webpage_url: Option<String>,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(from = "RawPlaylist")]
 pub struct Playlist {
-    #[serde(default, deserialize_with = "parse_entries")]
     pub entries: Option<Vec<SingleVideo>>,
+    /// Entries that could not be parsed as a `SingleVideo` (e.g. deleted or private videos
+    /// without an `id`), as they were printed by yt-dlp.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub invalid_entries: Vec<Value>,
     pub extractor: Option<String>,
     pub extractor_key: Option<String>,
     pub id: Option<String>,
@@ -168,7 +184,85 @@ pub struct Playlist {
     pub thumbnails: Option<Vec<Thumbnail>>,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+/// `Playlist` with unparsed entries, so that a single invalid entry doesn't fail the whole playlist.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct RawPlaylist {
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<Vec<SingleVideo>>"))]
+    entries: Option<Vec<Value>>,
+    #[serde(default)]
+    invalid_entries: Vec<Value>,
+    extractor: Option<String>,
+    extractor_key: Option<String>,
+    id: Option<String>,
+    title: Option<String>,
+    uploader: Option<String>,
+    uploader_id: Option<String>,
+    uploader_url: Option<String>,
+    webpage_url: Option<String>,
+    webpage_url_basename: Option<String>,
+    thumbnails: Option<Vec<Thumbnail>>,
+}
+
+impl From<RawPlaylist> for Playlist {
+    fn from(raw: RawPlaylist) -> Self {
+        let mut invalid_entries = raw.invalid_entries;
+        // Video entries can be null in the case of premium videos, those are skipped.
+        let entries = raw.entries.map(|entries| {
+            entries
+                .into_iter()
+                .filter(|entry| !entry.is_null())
+                .filter_map(|entry| match SingleVideo::deserialize(&entry) {
+                    Ok(video) => Some(video),
+                    Err(_) => {
+                        invalid_entries.push(entry);
+                        None
+                    }
+                })
+                .collect()
+        });
+
+        Playlist {
+            entries,
+            invalid_entries,
+            extractor: raw.extractor,
+            extractor_key: raw.extractor_key,
+            id: raw.id,
+            title: raw.title,
+            uploader: raw.uploader,
+            uploader_id: raw.uploader_id,
+            uploader_url: raw.uploader_url,
+            webpage_url: raw.webpage_url,
+            webpage_url_basename: raw.webpage_url_basename,
+            thumbnails: raw.thumbnails,
+        }
+    }
+}
+
+/// A file downloaded by yt-dlp, listed in `SingleVideo::requested_downloads`.
+/// Only present when the video was actually downloaded, see `YoutubeDl::run_and_download`.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RequestedDownload {
+    pub acodec: Option<String>,
+    pub ext: Option<String>,
+    #[serde(rename = "_filename")]
+    pub filename: Option<String>,
+    pub filepath: Option<String>,
+    pub filesize: Option<i64>,
+    pub filesize_approx: Option<f64>,
+    pub format: Option<String>,
+    pub format_id: Option<String>,
+    pub fps: Option<f64>,
+    pub height: Option<f64>,
+    pub requested_formats: Option<Vec<Format>>,
+    pub resolution: Option<String>,
+    pub vcodec: Option<String>,
+    pub width: Option<f64>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SingleVideo {
     pub abr: Option<f64>,
     pub acodec: Option<String>,
@@ -220,13 +314,15 @@ pub struct SingleVideo {
     pub genre: Option<String>,
     pub heatmap: Option<Vec<HeatmapSample>>,
     pub height: Option<f64>,
-    pub http_headers: Option<BTreeMap<String, Option<String>>>,
+    #[serde(default, deserialize_with = "parse_headers")]
+    pub http_headers: Option<BTreeMap<String, String>>,
     pub id: String,
     pub is_live: Option<bool>,
     pub language: Option<String>,
     pub language_preference: Option<i64>,
     pub license: Option<String>,
     pub like_count: Option<i64>,
+    pub live_status: Option<String>,
     pub location: Option<String>,
     pub manifest_url: Option<String>,
     pub no_resume: Option<bool>,
@@ -241,13 +337,18 @@ pub struct SingleVideo {
     pub protocol: Option<Protocol>,
     pub quality: Option<f64>,
     pub release_date: Option<String>,
+    pub release_timestamp: Option<f64>,
     pub release_year: Option<i64>,
     pub repost_count: Option<i64>,
+    pub requested_downloads: Option<Vec<RequestedDownload>>,
+    pub requested_formats: Option<Vec<Format>>,
     pub requested_subtitles: Option<BTreeMap<String, Subtitle>>,
     pub resolution: Option<String>,
     pub season: Option<String>,
     pub season_id: Option<String>,
     pub season_number: Option<i32>,
+    pub section_end: Option<f64>,
+    pub section_start: Option<f64>,
     pub series: Option<String>,
     pub source_preference: Option<i64>,
     pub start_time: Option<String>,
@@ -274,14 +375,16 @@ pub struct SingleVideo {
     pub width: Option<f64>,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Subtitle {
     pub data: Option<String>,
     pub ext: Option<String>,
     pub url: Option<String>,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Thumbnail {
     pub filesize: Option<i64>,
     pub height: Option<f64>,
@@ -291,7 +394,8 @@ pub struct Thumbnail {
     pub width: Option<f64>,
 }
 
-#[derive(Clone, Copy, Serialize, Deserialize, Debug)]
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Protocol {
     #[serde(rename = "http")]
     Http,
@@ -334,6 +438,25 @@ pub enum Protocol {
     Unknown,
 }
 
+/// HTTP headers, where headers without a value are dropped and other values are converted to
+/// strings.
+fn parse_headers<'de, D>(deserializer: D) -> Result<Option<BTreeMap<String, String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let headers: Option<BTreeMap<String, Value>> = Deserialize::deserialize(deserializer)?;
+    Ok(headers.map(|headers| {
+        headers
+            .into_iter()
+            .filter_map(|(name, value)| match value {
+                Value::Null => None,
+                Value::String(value) => Some((name, value)),
+                value => Some((name, value.to_string())),
+            })
+            .collect()
+    }))
+}
+
 // Codec values are set explicitly, and when there is no codec, it is sometimes
 // given as "none" (instead of simply missing from the JSON).
 // Default decoding in this case would result in `Some("none".to_string())`, which is why
@@ -347,16 +470,3 @@ where
         x => x,
     })
 }
-
-// Video entries can be null in the case of premium videos
-// Flattens entries to simplify the type from Option<Vec<Option<SingleVideo>>>> to Option<Vec<SingleVideo>>
-fn parse_entries<'de, D>(deserializer: D) -> Result<Option<Vec<SingleVideo>>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let entries: Option<Vec<Option<SingleVideo>>> = Deserialize::deserialize(deserializer)?;
-    let flattened_entries = entries
-        .map(|vec_option_single_video| vec_option_single_video.into_iter().flatten().collect());
-
-    Ok(flattened_entries)
-}
@@ -0,0 +1,278 @@
+//! Typed values for command line options of yt-dlp.
+
+/// The type of internet shortcut written with `YoutubeDl::write_link`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LinkFormat {
+    /// The shortcut format of the current platform (`--write-link`)
+    Platform,
+    /// Windows `.url` shortcut (`--write-url-link`)
+    Url,
+    /// macOS `.webloc` shortcut (`--write-webloc-link`)
+    Webloc,
+    /// Linux `.desktop` shortcut (`--write-desktop-link`)
+    Desktop,
+}
+
+impl LinkFormat {
+    pub(crate) fn flag(self) -> &'static str {
+        match self {
+            LinkFormat::Platform => "--write-link",
+            LinkFormat::Url => "--write-url-link",
+            LinkFormat::Webloc => "--write-webloc-link",
+            LinkFormat::Desktop => "--write-desktop-link",
+        }
+    }
+}
+
+/// Options to restore legacy behavior of yt-dlp, passed with `YoutubeDl::compat_options`.
+/// See the [yt-dlp documentation](https://github.com/yt-dlp/yt-dlp#differences-in-default-behavior)
+/// for what each option does.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CompatOption {
+    /// `filename`
+    Filename,
+    /// `filename-sanitization`
+    FilenameSanitization,
+    /// `format-sort`
+    FormatSort,
+    /// `format-spec`
+    FormatSpec,
+    /// `multistreams`
+    Multistreams,
+    /// `no-playlist-metafiles`
+    NoPlaylistMetafiles,
+    /// `no-live-chat`
+    NoLiveChat,
+    /// `no-clean-infojson`
+    NoCleanInfojson,
+    /// `no-keep-subs`
+    NoKeepSubs,
+    /// `no-attach-info-json`
+    NoAttachInfoJson,
+    /// `embed-thumbnail-atomicparsley`
+    EmbedThumbnailAtomicparsley,
+    /// `embed-metadata`
+    EmbedMetadata,
+    /// `no-certifi`
+    NoCertifi,
+    /// `no-youtube-channel-redirect`
+    NoYoutubeChannelRedirect,
+    /// `no-youtube-unavailable-videos`
+    NoYoutubeUnavailableVideos,
+    /// `no-youtube-prefer-utc-upload-date`
+    NoYoutubePreferUtcUploadDate,
+    /// `no-external-downloader-progress`
+    NoExternalDownloaderProgress,
+    /// `playlist-match-filter`
+    PlaylistMatchFilter,
+    /// `manifest-filesize-approx`
+    ManifestFilesizeApprox,
+    /// `allow-unsafe-ext`
+    AllowUnsafeExt,
+    /// `prefer-vp9-sort`
+    PreferVp9Sort,
+    /// `mtime-by-default`
+    MtimeByDefault,
+    /// `youtube-dl`: all options for compatibility with youtube-dl
+    YoutubeDl,
+    /// `youtube-dlc`: all options for compatibility with youtube-dlc
+    YoutubeDlc,
+    /// `all`: all compat options (not recommended)
+    All,
+    /// Any other option, e.g. the year aliases like `2023` or a removed option like `-multistreams`.
+    Custom(String),
+}
+
+impl CompatOption {
+    pub(crate) fn as_str(&self) -> &str {
+        match self {
+            CompatOption::Filename => "filename",
+            CompatOption::FilenameSanitization => "filename-sanitization",
+            CompatOption::FormatSort => "format-sort",
+            CompatOption::FormatSpec => "format-spec",
+            CompatOption::Multistreams => "multistreams",
+            CompatOption::NoPlaylistMetafiles => "no-playlist-metafiles",
+            CompatOption::NoLiveChat => "no-live-chat",
+            CompatOption::NoCleanInfojson => "no-clean-infojson",
+            CompatOption::NoKeepSubs => "no-keep-subs",
+            CompatOption::NoAttachInfoJson => "no-attach-info-json",
+            CompatOption::EmbedThumbnailAtomicparsley => "embed-thumbnail-atomicparsley",
+            CompatOption::EmbedMetadata => "embed-metadata",
+            CompatOption::NoCertifi => "no-certifi",
+            CompatOption::NoYoutubeChannelRedirect => "no-youtube-channel-redirect",
+            CompatOption::NoYoutubeUnavailableVideos => "no-youtube-unavailable-videos",
+            CompatOption::NoYoutubePreferUtcUploadDate => "no-youtube-prefer-utc-upload-date",
+            CompatOption::NoExternalDownloaderProgress => "no-external-downloader-progress",
+            CompatOption::PlaylistMatchFilter => "playlist-match-filter",
+            CompatOption::ManifestFilesizeApprox => "manifest-filesize-approx",
+            CompatOption::AllowUnsafeExt => "allow-unsafe-ext",
+            CompatOption::PreferVp9Sort => "prefer-vp9-sort",
+            CompatOption::MtimeByDefault => "mtime-by-default",
+            CompatOption::YoutubeDl => "youtube-dl",
+            CompatOption::YoutubeDlc => "youtube-dlc",
+            CompatOption::All => "all",
+            CompatOption::Custom(option) => option,
+        }
+    }
+}
+
+/// A size in bytes, e.g. for `YoutubeDl::http_chunk_size`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bytes(pub u64);
+
+impl Bytes {
+    /// Size in kibibytes (1024 bytes).
+    pub const fn kib(kib: u64) -> Self {
+        Bytes(kib * 1024)
+    }
+
+    /// Size in mebibytes (1024 × 1024 bytes).
+    pub const fn mib(mib: u64) -> Self {
+        Bytes(mib * 1024 * 1024)
+    }
+}
+
+impl From<u64> for Bytes {
+    fn from(bytes: u64) -> Self {
+        Bytes(bytes)
+    }
+}
+
+/// Whether yt-dlp checks that formats are actually downloadable, see `YoutubeDl::check_formats`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CheckFormats {
+    /// Check the selected formats (`--check-formats`)
+    Selected,
+    /// Check all formats (`--check-all-formats`)
+    All,
+    /// Don't check any formats (`--no-check-formats`)
+    None,
+}
+
+impl CheckFormats {
+    pub(crate) fn flag(self) -> &'static str {
+        match self {
+            CheckFormats::Selected => "--check-formats",
+            CheckFormats::All => "--check-all-formats",
+            CheckFormats::None => "--no-check-formats",
+        }
+    }
+}
+
+/// The stage of the download process at which a postprocessor runs (the `when` parameter of
+/// yt-dlp's `--use-postprocessor` and `--print`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PostprocessorStage {
+    /// Before extracting the video information (`pre_process`)
+    PreProcess,
+    /// After the video is filtered (`after_filter`)
+    AfterFilter,
+    /// After the format is selected (`video`)
+    Video,
+    /// Before downloading (`before_dl`)
+    BeforeDl,
+    /// After downloading and postprocessing (`post_process`), the default
+    PostProcess,
+    /// After moving the file to its final location (`after_move`)
+    AfterMove,
+    /// After downloading and processing all formats of a video (`after_video`)
+    AfterVideo,
+    /// At the end of a playlist (`playlist`)
+    Playlist,
+}
+
+impl PostprocessorStage {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            PostprocessorStage::PreProcess => "pre_process",
+            PostprocessorStage::AfterFilter => "after_filter",
+            PostprocessorStage::Video => "video",
+            PostprocessorStage::BeforeDl => "before_dl",
+            PostprocessorStage::PostProcess => "post_process",
+            PostprocessorStage::AfterMove => "after_move",
+            PostprocessorStage::AfterVideo => "after_video",
+            PostprocessorStage::Playlist => "playlist",
+        }
+    }
+}
+
+/// How to download age-restricted videos, passed to `YoutubeDl::use_age_gate_bypass`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AgeGateBypass {
+    /// Cookies of an account that has confirmed its age, from a file in Netscape format.
+    /// This is the most reliable way.
+    CookiesFile(String),
+    /// Cookies of an account that has confirmed its age, from the browser with this name
+    /// (like `firefox`)
+    CookiesFromBrowser(String),
+    /// Use YouTube's embedded player client, which plays some age-restricted videos without
+    /// signing in (`--extractor-args youtube:player_client=web_embedded,default`). The clients
+    /// are merged with those of other options, like `ThrottleMitigation`.
+    EmbeddedClient,
+}
+
+/// What `download_to` does when a file it would download to already exists, passed to
+/// `YoutubeDl::collision_policy`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CollisionPolicy {
+    /// Download again and overwrite the file (`--force-overwrites`)
+    Overwrite,
+    /// Keep the existing file and don't download it (`--no-overwrites`)
+    Skip,
+    /// Download to a new file with a suffix like ` (1)` added to the name. The file names are
+    /// predicted before downloading, which costs an extra yt-dlp call.
+    RenameWithSuffix,
+}
+
+impl CollisionPolicy {
+    pub(crate) fn flag(self) -> Option<&'static str> {
+        match self {
+            CollisionPolicy::Overwrite => Some("--force-overwrites"),
+            CollisionPolicy::Skip => Some("--no-overwrites"),
+            CollisionPolicy::RenameWithSuffix => None,
+        }
+    }
+}
+
+/// The loudness that audio is normalized to with `YoutubeDl::normalize_audio`, using ffmpeg's
+/// `loudnorm` filter (EBU R128).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoudnessTarget {
+    /// Integrated loudness in LUFS, between -70 and -5
+    pub integrated: f64,
+    /// Maximum true peak in dBTP, between -9 and 0
+    pub true_peak: f64,
+    /// Loudness range in LU, between 1 and 50
+    pub range: f64,
+}
+
+impl LoudnessTarget {
+    /// -16 LUFS, the common target for podcasts
+    pub const PODCAST: Self = LoudnessTarget {
+        integrated: -16.0,
+        true_peak: -1.5,
+        range: 11.0,
+    };
+
+    /// -14 LUFS, the target of most music streaming services
+    pub const STREAMING: Self = LoudnessTarget {
+        integrated: -14.0,
+        true_peak: -1.0,
+        range: 11.0,
+    };
+
+    /// -23 LUFS, the EBU R128 target for broadcasting
+    pub const BROADCAST: Self = LoudnessTarget {
+        integrated: -23.0,
+        true_peak: -1.0,
+        range: 7.0,
+    };
+
+    /// The ffmpeg filter, e.g. `loudnorm=I=-16:TP=-1.5:LRA=11`.
+    pub fn filter(&self) -> String {
+        format!(
+            "loudnorm=I={}:TP={}:LRA={}",
+            self.integrated, self.true_peak, self.range
+        )
+    }
+}
@@ -0,0 +1,322 @@
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::{DownloadOutcome, Error, RunReport, SingleVideo, YoutubeDl, YoutubeDlOutput};
+
+/// Extensions of audio files, which have no picture to make a preview of.
+const AUDIO_EXTENSIONS: &[&str] = &["aac", "flac", "m4a", "mp3", "ogg", "opus", "wav"];
+
+/// An animated GIF preview of a downloaded video, made with `YoutubeDl::generate_preview`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PreviewSpec {
+    /// Where in the video the preview starts
+    pub at: Duration,
+    /// The length of the preview
+    pub duration: Duration,
+    /// The width of the preview in pixels, the height is scaled to keep the aspect ratio
+    pub width: u32,
+}
+
+impl Default for PreviewSpec {
+    fn default() -> Self {
+        PreviewSpec {
+            at: Duration::from_secs(10),
+            duration: Duration::from_secs(3),
+            width: 320,
+        }
+    }
+}
+
+impl PreviewSpec {
+    /// The preview of the video at `media`: `<name>.preview.gif` next to it.
+    fn output_path(media: &Path) -> PathBuf {
+        let stem = media.file_stem().unwrap_or_default().to_string_lossy();
+        media.with_file_name(format!("{}.preview.gif", stem))
+    }
+
+    fn ffmpeg_args(&self, media: &Path, preview: &Path) -> Vec<OsString> {
+        let mut args: Vec<OsString> = vec![
+            "-y".into(),
+            "-loglevel".into(),
+            "error".into(),
+            "-ss".into(),
+            format!("{:.3}", self.at.as_secs_f64()).into(),
+            "-t".into(),
+            format!("{:.3}", self.duration.as_secs_f64()).into(),
+            "-i".into(),
+        ];
+        args.push(media.into());
+        args.push("-vf".into());
+        args.push(format!("fps=10,scale={}:-2:flags=lanczos", self.width).into());
+        args.push("-loop".into());
+        args.push("0".into());
+        args.push(preview.into());
+        args
+    }
+}
+
+/// The videos among the downloaded files (audio files are skipped).
+fn videos<'a>(files: impl Iterator<Item = &'a Path>) -> Vec<PathBuf> {
+    files
+        .filter(|path| {
+            let extension = path.extension().unwrap_or_default().to_string_lossy();
+            !AUDIO_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+        })
+        .map(Path::to_owned)
+        .collect()
+}
+
+/// The files downloaded by a run, see `RunReport::files`.
+fn downloaded(report: &RunReport) -> impl Iterator<Item = &Path> {
+    report.outcomes.iter().filter_map(|outcome| match outcome {
+        DownloadOutcome::Downloaded(path) | DownloadOutcome::Verified(path, _) => {
+            Some(path.as_path())
+        }
+        _ => None,
+    })
+}
+
+/// The files listed in `SingleVideo::requested_downloads` of the output of `run_and_download`.
+fn requested_downloads(output: &YoutubeDlOutput) -> Vec<&Path> {
+    let videos: Vec<&SingleVideo> = match output {
+        YoutubeDlOutput::SingleVideo(video) => vec![video],
+        YoutubeDlOutput::Playlist(playlist) => playlist.entries.iter().flatten().collect(),
+    };
+    videos
+        .into_iter()
+        .flat_map(|video| video.requested_downloads.iter().flatten())
+        .filter_map(|download| download.filepath.as_deref().map(Path::new))
+        .collect()
+}
+
+fn ffmpeg_error(stderr: &[u8]) -> Error {
+    let stderr = String::from_utf8_lossy(stderr);
+    Error::Io(io::Error::other(format!(
+        "ffmpeg failed to make a preview: {}",
+        stderr.trim()
+    )))
+}
+
+/// Where the previews ended up: the finished ones in `RunReport::previews`, the failed ones in
+/// `RunReport::preview_failures`. A failed preview doesn't fail the download.
+fn record(report: &mut RunReport, results: Vec<(PathBuf, Result<PathBuf, Error>)>) {
+    for (media, result) in results {
+        match result {
+            Ok(preview) => report.previews.push((media, preview)),
+            Err(err) => report.preview_failures.push((media, err.to_string())),
+        }
+    }
+}
+
+/// `run_and_download` has no report, failed previews are only logged.
+fn log_failures(results: Vec<(PathBuf, Result<PathBuf, Error>)>) {
+    for (media, result) in results {
+        if let Err(err) = result {
+            log::warn!("failed to make a preview of {}: {}", media.display(), err);
+        }
+    }
+}
+
+impl YoutubeDl {
+    /// The ffmpeg executable: the one passed to `ffmpeg_location`, or `ffmpeg` from the `PATH`.
+    pub(crate) fn ffmpeg(&self) -> PathBuf {
+        let executable = if cfg!(windows) {
+            "ffmpeg.exe"
+        } else {
+            "ffmpeg"
+        };
+        match self.ffmpeg_location.as_deref().map(Path::new) {
+            Some(location) if location.is_dir() => location.join(executable),
+            Some(location) => location.to_owned(),
+            None => PathBuf::from(executable),
+        }
+    }
+
+    /// Make the previews requested with `generate_preview` for the downloaded videos.
+    pub(crate) fn make_previews(&self, report: &mut RunReport) {
+        let results = self.previews_of(videos(downloaded(report)));
+        record(report, results);
+    }
+
+    /// Make the previews for the files downloaded by `run_and_download`.
+    pub(crate) fn make_output_previews(&self, output: &YoutubeDlOutput) {
+        log_failures(self.previews_of(videos(requested_downloads(output).into_iter())));
+    }
+
+    fn previews_of(&self, videos: Vec<PathBuf>) -> Vec<(PathBuf, Result<PathBuf, Error>)> {
+        let spec = match &self.preview {
+            Some(spec) => spec,
+            None => return vec![],
+        };
+        videos
+            .into_iter()
+            .map(|media| {
+                let preview = PreviewSpec::output_path(&media);
+                let result = Command::new(self.ffmpeg())
+                    .args(spec.ffmpeg_args(&media, &preview))
+                    .stdin(Stdio::null())
+                    .output()
+                    .map_err(Error::from)
+                    .and_then(|output| match output.status.success() {
+                        true => Ok(preview),
+                        false => Err(ffmpeg_error(&output.stderr)),
+                    });
+                (media, result)
+            })
+            .collect()
+    }
+
+    /// Make the previews asynchronously, see `make_previews`.
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn make_previews_async(&self, report: &mut RunReport) {
+        let results = self.previews_of_async(videos(downloaded(report))).await;
+        record(report, results);
+    }
+
+    /// Make the previews asynchronously, see `make_output_previews`.
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn make_output_previews_async(&self, output: &YoutubeDlOutput) {
+        let videos = videos(requested_downloads(output).into_iter());
+        log_failures(self.previews_of_async(videos).await);
+    }
+
+    #[cfg(feature = "tokio")]
+    async fn previews_of_async(
+        &self,
+        videos: Vec<PathBuf>,
+    ) -> Vec<(PathBuf, Result<PathBuf, Error>)> {
+        let spec = match &self.preview {
+            Some(spec) => spec,
+            None => return vec![],
+        };
+        let mut results = Vec::with_capacity(videos.len());
+        for media in videos {
+            let preview = PreviewSpec::output_path(&media);
+            let result = tokio::process::Command::new(self.ffmpeg())
+                .args(spec.ffmpeg_args(&media, &preview))
+                .stdin(Stdio::null())
+                .output()
+                .await
+                .map_err(Error::from)
+                .and_then(|output| match output.status.success() {
+                    true => Ok(preview),
+                    false => Err(ffmpeg_error(&output.stderr)),
+                });
+            results.push((media, result));
+        }
+        results
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::PathBuf;
+
+    use crate::tests::fake_yt_dlp;
+    use crate::{PreviewSpec, YoutubeDl};
+
+    #[test]
+    fn test_generate_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(
+            dir.path(),
+            "echo '[info] a: Downloading 1 format(s): 137'
+echo '[download] Destination: /downloads/video.mp4'
+echo '[info] b: Downloading 1 format(s): 140'
+echo '[download] Destination: /downloads/audio.m4a'",
+        );
+        // Records its arguments in the "preview".
+        let ffmpeg = dir.path().join("ffmpeg");
+        let log = dir.path().join("ffmpeg-args");
+        std::fs::write(
+            &ffmpeg,
+            format!("#!/bin/sh\necho \"$@\" > {}\n", log.display()),
+        )
+        .unwrap();
+        std::fs::set_permissions(&ffmpeg, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let report = YoutubeDl::new("https://www.youtube.com/watch?v=abc")
+            .youtube_dl_path(path)
+            .ffmpeg_location(dir.path())
+            .generate_preview(PreviewSpec::default())
+            .download_to("/downloads")
+            .unwrap();
+        assert_eq!(
+            report.previews,
+            [(
+                PathBuf::from("/downloads/video.mp4"),
+                PathBuf::from("/downloads/video.preview.gif")
+            )]
+        );
+        assert_eq!(
+            std::fs::read_to_string(log).unwrap(),
+            "-y -loglevel error -ss 10.000 -t 3.000 -i /downloads/video.mp4 \
+             -vf fps=10,scale=320:-2:flags=lanczos -loop 0 /downloads/video.preview.gif\n"
+        );
+    }
+
+    #[test]
+    fn test_failed_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(
+            dir.path(),
+            "echo '[info] a: Downloading 1 format(s): 137'
+echo '[download] Destination: /downloads/video.mp4'",
+        );
+        let ffmpeg = dir.path().join("ffmpeg");
+        std::fs::write(
+            &ffmpeg,
+            "#!/bin/sh
+echo 'broken' >&2
+exit 1
+",
+        )
+        .unwrap();
+        std::fs::set_permissions(&ffmpeg, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let report = YoutubeDl::new("https://www.youtube.com/watch?v=abc")
+            .youtube_dl_path(path)
+            .ffmpeg_location(dir.path())
+            .generate_preview(PreviewSpec::default())
+            .download_to("/downloads")
+            .unwrap();
+        assert!(report.previews.is_empty());
+        assert_eq!(
+            report.preview_failures,
+            [(
+                PathBuf::from("/downloads/video.mp4"),
+                "io error: ffmpeg failed to make a preview: broken".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_run_and_download_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(
+            dir.path(),
+            r#"echo '{"id": "abc", "requested_downloads": [{"filepath": "/downloads/abc.mp4"}]}'"#,
+        );
+        let ffmpeg = dir.path().join("ffmpeg");
+        let log = dir.path().join("ffmpeg-args");
+        std::fs::write(
+            &ffmpeg,
+            format!("#!/bin/sh\necho \"$@\" > {}\n", log.display()),
+        )
+        .unwrap();
+        std::fs::set_permissions(&ffmpeg, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        YoutubeDl::new("https://www.youtube.com/watch?v=abc")
+            .youtube_dl_path(path)
+            .ffmpeg_location(dir.path())
+            .generate_preview(PreviewSpec::default())
+            .run_and_download(dir.path())
+            .unwrap();
+        let args = std::fs::read_to_string(log).unwrap();
+        assert!(args.ends_with("/downloads/abc.preview.gif\n"));
+    }
+}
@@ -0,0 +1,167 @@
+//! Extracting the metadata first and downloading later, without extracting it again.
+
+use std::io::Write;
+use std::path::Path;
+
+use serde_json::Value;
+use tempfile::NamedTempFile;
+
+use crate::{Error, Format, RunReport, SingleVideo, YoutubeDl, YoutubeDlOutput};
+
+/// The result of `YoutubeDl::probe`: the extracted metadata, which can be shown to the user
+/// (e.g. in a confirmation dialog) before downloading it with `Probe::download`.
+///
+/// ```rust,no_run
+/// # fn example() -> Result<(), youtube_dl::Error> {
+/// use youtube_dl::YoutubeDl;
+///
+/// let probe = YoutubeDl::new("https://www.youtube.com/watch?v=VFbhKZFzbzk")
+///     .format("bv*+ba/b")
+///     .probe()?;
+/// println!("download {:?}?", probe.filename());
+/// let report = probe.download("downloads")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Probe {
+    builder: YoutubeDl,
+    info: Value,
+    output: YoutubeDlOutput,
+}
+
+impl Probe {
+    /// The parsed metadata.
+    pub fn output(&self) -> &YoutubeDlOutput {
+        &self.output
+    }
+
+    /// The metadata of the video, if the URL is not a playlist.
+    pub fn video(&self) -> Option<&SingleVideo> {
+        match &self.output {
+            YoutubeDlOutput::SingleVideo(video) => Some(video),
+            YoutubeDlOutput::Playlist(_) => None,
+        }
+    }
+
+    /// The formats yt-dlp selected for the video, e.g. a video and an audio format that are
+    /// merged after downloading.
+    pub fn formats(&self) -> Vec<&Format> {
+        let video = match self.video() {
+            Some(video) => video,
+            None => return vec![],
+        };
+        if let Some(formats) = &video.requested_formats {
+            return formats.iter().collect();
+        }
+        video
+            .formats
+            .iter()
+            .flatten()
+            .filter(|format| format.format_id.is_some() && format.format_id == video.format_id)
+            .collect()
+    }
+
+    /// The name of the file the video will be downloaded to, relative to the download folder.
+    pub fn filename(&self) -> Option<&str> {
+        self.info
+            .get("_filename")
+            .or_else(|| self.info.get("filename"))
+            .and_then(Value::as_str)
+    }
+
+    /// Download what was probed to the folder, using the extracted metadata instead of
+    /// extracting it again (with `--load-info-json`). The direct URLs of the formats expire after
+    /// a while, so this should not be called hours after probing. See `YoutubeDl::download_to`.
+    pub fn download(&self, folder: impl AsRef<Path>) -> Result<RunReport, Error> {
+        let (builder, _info_file) = self.download_builder()?;
+        builder.download_to(folder)
+    }
+
+    /// Download what was probed asynchronously, see `download`.
+    #[cfg(feature = "tokio")]
+    pub async fn download_async(&self, folder: impl AsRef<Path>) -> Result<RunReport, Error> {
+        let (builder, _info_file) = self.download_builder()?;
+        builder.download_to_async(folder).await
+    }
+
+    /// A copy of the builder that loads the metadata from a temporary file, which is removed when
+    /// it is dropped.
+    fn download_builder(&self) -> Result<(YoutubeDl, NamedTempFile), Error> {
+        let mut info_file = tempfile::Builder::new()
+            .prefix("youtube-dl-rs-")
+            .suffix(".info.json")
+            .tempfile()?;
+        serde_json::to_writer(&mut info_file, &self.info)?;
+        info_file.flush()?;
+
+        let mut builder = self.builder.clone();
+        builder.load_info_json = Some(info_file.path().to_string_lossy().into_owned());
+        Ok((builder, info_file))
+    }
+}
+
+impl YoutubeDl {
+    /// Extract the metadata like `run`, keeping it to download it later with `Probe::download`
+    /// without extracting it again.
+    pub fn probe(&self) -> Result<Probe, Error> {
+        let stdout = self.run_raw_bytes()?;
+        self.to_probe(stdout)
+    }
+
+    /// Extract the metadata asynchronously, see `probe`.
+    #[cfg(feature = "tokio")]
+    pub async fn probe_async(&self) -> Result<Probe, Error> {
+        let stdout = self.run_raw_bytes_async().await?;
+        self.to_probe(stdout)
+    }
+
+    fn to_probe(&self, stdout: Vec<u8>) -> Result<Probe, Error> {
+        let info = serde_json::from_slice(&stdout)?;
+        let output = self.process_json_output(stdout)?;
+        Ok(Probe {
+            builder: self.clone(),
+            info,
+            output,
+        })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::tests::fake_yt_dlp;
+    use crate::{DownloadOutcome, YoutubeDl};
+
+    #[test]
+    fn test_probe() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = r#"case "$*" in
+    *--load-info-json*)
+        info=$(echo "$*" | sed 's/.*--load-info-json \([^ ]*\).*/\1/')
+        grep -q '"_filename":"abc.mp4"' "$info" && echo "[download] Destination: /downloads/abc.mp4" ;;
+    *)
+        echo '{"id": "abc", "_filename": "abc.mp4", "format_id": "137+140",
+            "requested_formats": [{"format_id": "137"}, {"format_id": "140"}]}' ;;
+esac"#;
+        let path = fake_yt_dlp(dir.path(), script);
+        let probe = YoutubeDl::new("https://www.youtube.com/watch?v=abc")
+            .youtube_dl_path(path)
+            .probe()
+            .unwrap();
+
+        assert_eq!(probe.video().unwrap().id, "abc");
+        assert_eq!(probe.filename(), Some("abc.mp4"));
+        let format_ids: Vec<_> = probe
+            .formats()
+            .iter()
+            .map(|format| format.format_id.as_deref().unwrap())
+            .collect();
+        assert_eq!(format_ids, ["137", "140"]);
+
+        let report = probe.download(dir.path()).unwrap();
+        assert_eq!(
+            report.outcomes,
+            [DownloadOutcome::Downloaded("/downloads/abc.mp4".into())]
+        );
+    }
+}
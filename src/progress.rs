@@ -0,0 +1,298 @@
+use std::time::Duration;
+
+/// Prefix of the progress lines printed with `PROGRESS_TEMPLATE`.
+const PROGRESS_PREFIX: &str = "[youtube_dl_rs:progress]";
+
+/// Passed to `--progress-template`, prints one machine-readable line per progress update.
+/// yt-dlp prints `NA` for missing values.
+pub(crate) const PROGRESS_TEMPLATE: &str = "download:[youtube_dl_rs:progress] %(progress.status)s %(progress.downloaded_bytes)s %(progress.total_bytes)s %(progress.total_bytes_estimate)s %(progress.speed)s %(progress.eta)s %(info.id)s %(progress.elapsed)s";
+
+/// Prefix of the lines printed with `FILE_TEMPLATE`.
+const FILE_PREFIX: &str = "[youtube_dl_rs:file] ";
+
+/// Passed to `--print`, prints the final path of each file after it was moved to its destination.
+pub(crate) const FILE_TEMPLATE: &str = "after_move:[youtube_dl_rs:file] %(filepath)s";
+
+/// Parse a line printed with `FILE_TEMPLATE`, returning the path of the downloaded file.
+pub(crate) fn parse_file_line(line: &str) -> Option<&str> {
+    line.trim_end_matches(['\r', '\n'])
+        .strip_prefix(FILE_PREFIX)
+        .filter(|path| !path.is_empty() && *path != "NA")
+}
+
+/// A progress update of a running download.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Progress {
+    /// ID of the video being downloaded.
+    pub video_id: Option<String>,
+    /// Status reported by yt-dlp, usually `downloading` or `finished`.
+    pub status: String,
+    /// Number of bytes downloaded so far.
+    pub downloaded_bytes: Option<u64>,
+    /// Total size of the file, or an estimate if the exact size is unknown.
+    pub total_bytes: Option<u64>,
+    /// Download speed in bytes per second.
+    pub speed: Option<f64>,
+    /// Estimated time until the download is finished.
+    pub eta: Option<Duration>,
+}
+
+impl Progress {
+    /// The fraction (between 0.0 and 1.0) of the file that has been downloaded, if the total size is known.
+    pub fn fraction(&self) -> Option<f64> {
+        match (self.downloaded_bytes, self.total_bytes) {
+            (Some(downloaded), Some(total)) if total > 0 => {
+                Some((downloaded as f64 / total as f64).min(1.0))
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse a line printed with the crate's progress template. Returns `None` for all other lines.
+    pub(crate) fn parse_line(line: &str) -> Option<Progress> {
+        let rest = line.trim().strip_prefix(PROGRESS_PREFIX)?;
+        let mut fields = rest.split_whitespace();
+        let status = fields.next()?.to_string();
+        let downloaded_bytes = parse_number(fields.next()?).map(|n| n as u64);
+        let total_bytes = parse_number(fields.next()?).map(|n| n as u64);
+        let total_bytes_estimate = parse_number(fields.next()?).map(|n| n as u64);
+        let speed = parse_number(fields.next()?);
+        let eta = parse_number(fields.next()?).map(Duration::from_secs_f64);
+        let video_id = fields.next().filter(|id| *id != "NA").map(String::from);
+
+        Some(Progress {
+            video_id,
+            status,
+            downloaded_bytes,
+            total_bytes: total_bytes.or(total_bytes_estimate),
+            speed,
+            eta,
+        })
+    }
+
+    /// Parse youtube-dl's regular progress output (with `--newline`), e.g.
+    /// `[download]  45.3% of 10.00MiB at  1.00MiB/s ETA 00:05`. Returns `None` for all other lines.
+    pub(crate) fn parse_legacy_line(line: &str) -> Option<Progress> {
+        let rest = line.trim().strip_prefix("[download]")?;
+        let mut fields = rest.split_whitespace();
+        let percent: f64 = fields.next()?.strip_suffix('%')?.parse().ok()?;
+        if fields.next()? != "of" {
+            return None;
+        }
+        let total_bytes = parse_size(fields.next()?.trim_start_matches('~'));
+
+        let mut progress = Progress {
+            video_id: None,
+            status: "downloading".into(),
+            downloaded_bytes: total_bytes.map(|total| (total as f64 * percent / 100.0) as u64),
+            total_bytes,
+            speed: None,
+            eta: None,
+        };
+        while let Some(field) = fields.next() {
+            match field {
+                "at" => {
+                    progress.speed = fields
+                        .next()
+                        .and_then(|s| s.strip_suffix("/s"))
+                        .and_then(parse_size)
+                        .map(|s| s as f64)
+                }
+                "ETA" => progress.eta = fields.next().and_then(parse_duration),
+                "in" => progress.status = "finished".into(),
+                _ => {}
+            }
+        }
+        Some(progress)
+    }
+}
+
+/// Parse the line printed when a file has finished downloading, returning the bytes that were
+/// transferred and the time it took: a `finished` line of the progress template, or youtube-dl's
+/// `[download] 100% of 10.00MiB in 00:05`. Files that were not transferred (e.g. because they
+/// already existed) have no downloaded bytes or time and are skipped.
+pub(crate) fn parse_finished_line(line: &str) -> Option<(u64, Duration)> {
+    if let Some(rest) = line.trim().strip_prefix(PROGRESS_PREFIX) {
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+        if fields.first() != Some(&"finished") {
+            return None;
+        }
+        let bytes = parse_number(fields.get(1)?)?;
+        let elapsed = Duration::try_from_secs_f64(parse_number(fields.get(7)?)?).ok()?;
+        return Some((bytes as u64, elapsed));
+    }
+
+    let progress = Progress::parse_legacy_line(line)?;
+    if progress.status != "finished" {
+        return None;
+    }
+    let elapsed = line
+        .split_whitespace()
+        .skip_while(|field| *field != "in")
+        .nth(1)
+        .and_then(parse_duration)?;
+    Some((progress.total_bytes?, elapsed))
+}
+
+/// Parse a size like `10.00MiB`, as printed by youtube-dl.
+fn parse_size(size: &str) -> Option<u64> {
+    let split = size.find(|c: char| c.is_ascii_alphabetic())?;
+    let (number, unit) = size.split_at(split);
+    let exponent = match unit {
+        "B" => 0,
+        "KiB" => 1,
+        "MiB" => 2,
+        "GiB" => 3,
+        "TiB" => 4,
+        _ => return None,
+    };
+    let number = parse_number(number)?;
+    Some((number * 1024f64.powi(exponent)) as u64)
+}
+
+/// Parse a duration like `01:05` or `1:02:03`.
+fn parse_duration(duration: &str) -> Option<Duration> {
+    let mut seconds = 0;
+    for part in duration.split(':') {
+        seconds = seconds * 60 + part.parse::<u64>().ok()?;
+    }
+    Some(Duration::from_secs(seconds))
+}
+
+/// Names of yt-dlp's postprocessors, as printed in the `[Name] message` lines of its output.
+const POSTPROCESSORS: &[&str] = &[
+    "ExtractAudio",
+    "Merger",
+    "VideoConvertor",
+    "VideoRemuxer",
+    "Metadata",
+    "MoveFiles",
+    "ModifyChapters",
+    "SplitChapters",
+    "SponsorBlock",
+    "ThumbnailsConvertor",
+    "SubtitlesConvertor",
+    "Exec",
+    "Concat",
+    "XAttrMetadata",
+];
+
+/// Parse a `[Postprocessor] message` line, returning the postprocessor name and the message.
+pub(crate) fn parse_postprocessor_line(line: &str) -> Option<(&str, &str)> {
+    let rest = line.trim().strip_prefix('[')?;
+    let (name, message) = rest.split_once("] ")?;
+    let is_postprocessor =
+        POSTPROCESSORS.contains(&name) || name.starts_with("Fixup") || name.starts_with("Embed");
+    is_postprocessor.then_some((name, message))
+}
+
+fn parse_number(field: &str) -> Option<f64> {
+    field
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite() && *n >= 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_finished_line, parse_postprocessor_line, Progress};
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_progress_line() {
+        let progress = Progress::parse_line(
+            "[youtube_dl_rs:progress] downloading 1024 NA 4096.5 512.25 6 dQw4w9WgXcQ\n",
+        )
+        .unwrap();
+        assert_eq!(progress.status, "downloading");
+        assert_eq!(progress.downloaded_bytes, Some(1024));
+        assert_eq!(progress.total_bytes, Some(4096));
+        assert_eq!(progress.speed, Some(512.25));
+        assert_eq!(progress.eta, Some(Duration::from_secs(6)));
+        assert_eq!(progress.video_id.as_deref(), Some("dQw4w9WgXcQ"));
+        assert_eq!(progress.fraction(), Some(0.25));
+
+        let finished =
+            Progress::parse_line("[youtube_dl_rs:progress] finished 10 10 NA NA NA NA").unwrap();
+        assert_eq!(finished.fraction(), Some(1.0));
+        assert_eq!(finished.video_id, None);
+
+        assert!(Progress::parse_line("[download] Destination: video.webm").is_none());
+    }
+
+    #[test]
+    fn test_parse_legacy_line() {
+        let progress =
+            Progress::parse_legacy_line("[download]  25.0% of 4.00KiB at  1.50KiB/s ETA 01:05")
+                .unwrap();
+        assert_eq!(progress.status, "downloading");
+        assert_eq!(progress.downloaded_bytes, Some(1024));
+        assert_eq!(progress.total_bytes, Some(4096));
+        assert_eq!(progress.speed, Some(1536.0));
+        assert_eq!(progress.eta, Some(Duration::from_secs(65)));
+
+        let finished = Progress::parse_legacy_line("[download] 100% of 10.00MiB in 00:05").unwrap();
+        assert_eq!(finished.status, "finished");
+        assert_eq!(finished.fraction(), Some(1.0));
+
+        let unknown = Progress::parse_legacy_line(
+            "[download]   1.0% of ~2.00GiB at Unknown speed ETA Unknown ETA",
+        )
+        .unwrap();
+        assert_eq!(unknown.total_bytes, Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(unknown.speed, None);
+        assert_eq!(unknown.eta, None);
+
+        assert!(Progress::parse_legacy_line("[download] Destination: video.webm").is_none());
+    }
+
+    #[test]
+    fn test_parse_finished_line() {
+        assert_eq!(
+            parse_finished_line("[youtube_dl_rs:progress] finished 2048 NA NA NA NA abc 1.5"),
+            Some((2048, Duration::from_secs_f64(1.5)))
+        );
+        // A file that already existed
+        assert_eq!(
+            parse_finished_line("[youtube_dl_rs:progress] finished NA 1024 NA NA NA abc NA"),
+            None
+        );
+        assert_eq!(
+            parse_finished_line("[youtube_dl_rs:progress] finished 1024 1024 NA NA NA abc NA"),
+            None
+        );
+        assert_eq!(
+            parse_finished_line("[download] 100% of   10.00MiB in 00:00:12 at 853.33KiB/s"),
+            Some((10 * 1024 * 1024, Duration::from_secs(12)))
+        );
+        assert_eq!(parse_finished_line("[download] 100% of 10.00MiB"), None);
+        assert_eq!(
+            parse_finished_line("[youtube_dl_rs:progress] downloading 10 20 NA NA NA abc 1"),
+            None
+        );
+        assert_eq!(
+            parse_finished_line("[download]  50.0% of 10.00MiB at 1.00MiB/s ETA 00:05"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_postprocessor_line() {
+        assert_eq!(
+            parse_postprocessor_line("[Merger] Merging formats into \"video.mkv\"\n"),
+            Some(("Merger", "Merging formats into \"video.mkv\""))
+        );
+        assert_eq!(
+            parse_postprocessor_line("[FixupM4a] Correcting container"),
+            Some(("FixupM4a", "Correcting container"))
+        );
+        assert_eq!(
+            parse_postprocessor_line("[youtube] abc: Downloading webpage"),
+            None
+        );
+        assert_eq!(
+            parse_postprocessor_line("[download] Destination: a.webm"),
+            None
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::process::ExitStatus;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+
+use crate::{Hooks, Progress};
+
+const TEMPLATE: &str =
+    "{msg:30!} [{bar:30}] {bytes:>10}/{total_bytes:10} {bytes_per_sec:>12} {eta:>4}";
+
+/// Renders one progress bar per running download (in a `MultiProgress`). Register the same
+/// adapter (it is cheap to clone) as hooks on all builders that should be displayed together:
+///
+/// ```no_run
+/// # use youtube_dl::{ProgressBarAdapter, YoutubeDl};
+/// let bars = ProgressBarAdapter::new();
+/// YoutubeDl::new("https://www.youtube.com/watch?v=VFbhKZFzbzk")
+///     .hooks(bars.clone())
+///     .download_to(".")
+///     .unwrap();
+/// ```
+#[derive(Clone)]
+pub struct ProgressBarAdapter {
+    multi: MultiProgress,
+    style: ProgressStyle,
+    bars: Arc<Mutex<HashMap<String, ProgressBar>>>,
+}
+
+impl ProgressBarAdapter {
+    /// Create an adapter that draws to stderr with the default style.
+    pub fn new() -> Self {
+        Self::with_multi_progress(MultiProgress::new())
+    }
+
+    /// Create an adapter that adds its bars to an existing `MultiProgress`.
+    pub fn with_multi_progress(multi: MultiProgress) -> Self {
+        let style = ProgressStyle::with_template(TEMPLATE)
+            .expect("invalid progress bar template")
+            .progress_chars("=> ");
+        ProgressBarAdapter {
+            multi,
+            style,
+            bars: Arc::default(),
+        }
+    }
+
+    /// Use a custom style for the progress bars.
+    pub fn style(&mut self, style: ProgressStyle) -> &mut Self {
+        self.style = style;
+        self
+    }
+
+    /// The `MultiProgress` the bars are drawn with, e.g. to print log lines without
+    /// breaking the display.
+    pub fn multi_progress(&self) -> &MultiProgress {
+        &self.multi
+    }
+
+    fn with_bar(&self, url: &str, f: impl FnOnce(&ProgressBar)) {
+        let mut bars = self.bars.lock().unwrap();
+        let bar = bars.entry(url.to_string()).or_insert_with(|| {
+            let bar = self.multi.add(ProgressBar::new(0));
+            bar.set_style(self.style.clone());
+            bar.set_message(url.to_string());
+            bar
+        });
+        f(bar);
+    }
+}
+
+impl Default for ProgressBarAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ProgressBarAdapter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressBarAdapter")
+            .field("multi", &self.multi)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Hooks for ProgressBarAdapter {
+    fn on_spawn(&self, url: &str, _pid: u32) {
+        self.with_bar(url, |_| {});
+    }
+
+    fn on_complete(&self, url: &str, exit: ExitStatus, _duration: Duration, _stdout_len: usize) {
+        if let Some(bar) = self.bars.lock().unwrap().remove(url) {
+            if exit.success() {
+                bar.finish();
+            } else {
+                bar.abandon_with_message(format!("{} (failed)", bar.message()));
+            }
+        }
+    }
+
+    fn on_progress(&self, url: &str, progress: &Progress) {
+        self.with_bar(url, |bar| {
+            if let Some(id) = &progress.video_id {
+                bar.set_message(id.clone());
+            }
+            if let Some(total) = progress.total_bytes {
+                bar.set_length(total);
+            }
+            if let Some(downloaded) = progress.downloaded_bytes {
+                bar.set_position(downloaded);
+            }
+        });
+    }
+
+    fn on_postprocess(&self, url: &str, postprocessor: &str, _message: &str) {
+        self.with_bar(url, |bar| {
+            let id = bar.message();
+            let id = id.split(" (").next().unwrap_or_default().to_string();
+            bar.set_message(format!("{} ({})", id, postprocessor));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProgressBarAdapter;
+    use crate::{Hooks, Progress};
+    use indicatif::{MultiProgress, ProgressDrawTarget};
+
+    #[test]
+    fn test_progress_bars() {
+        let adapter = ProgressBarAdapter::with_multi_progress(MultiProgress::with_draw_target(
+            ProgressDrawTarget::hidden(),
+        ));
+        let progress = Progress {
+            video_id: Some("abc".into()),
+            status: "downloading".into(),
+            downloaded_bytes: Some(5),
+            total_bytes: Some(10),
+            speed: None,
+            eta: None,
+        };
+        adapter.on_spawn("https://example.com/a", 1);
+        adapter.on_progress("https://example.com/a", &progress);
+        adapter.on_postprocess("https://example.com/a", "Merger", "Merging formats");
+
+        let bars = adapter.bars.lock().unwrap();
+        let bar = &bars["https://example.com/a"];
+        assert_eq!(bar.position(), 5);
+        assert_eq!(bar.length(), Some(10));
+        assert_eq!(bar.message(), "abc (Merger)");
+    }
+}
@@ -0,0 +1,106 @@
+use crate::Format;
+
+/// Quality of a format, parsed from its `format_note` (like `1080p60 HDR` or `720p Premium`) by
+/// `Format::quality_info`, e.g. to show quality badges.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QualityInfo {
+    /// The resolution (`1080p`), or the note without the other markers for formats without one
+    /// (`medium` for audio formats on YouTube)
+    pub label: Option<String>,
+    /// Frames per second, from the note (`1080p60`) or else from `Format::fps`
+    pub fps: Option<u32>,
+    /// Whether the format is HDR
+    pub hdr: bool,
+    /// Whether the format is only available with a premium subscription
+    pub premium: bool,
+}
+
+impl QualityInfo {
+    /// Parse a `format_note`. Parts that are not recognized are kept in the label.
+    pub fn parse(note: &str) -> Self {
+        let mut info = QualityInfo::default();
+        let mut rest = vec![];
+        for part in note.split([' ', ',']).filter(|part| !part.is_empty()) {
+            if part.eq_ignore_ascii_case("hdr") {
+                info.hdr = true;
+            } else if part.eq_ignore_ascii_case("premium") {
+                info.premium = true;
+            } else if let Some((resolution, fps)) = parse_resolution(part) {
+                info.label = Some(resolution.to_string());
+                info.fps = fps;
+            } else {
+                rest.push(part);
+            }
+        }
+        if info.label.is_none() && !rest.is_empty() {
+            info.label = Some(rest.join(" "));
+        }
+        info
+    }
+}
+
+/// Split a part like `1080p60` into the resolution and the frame rate.
+fn parse_resolution(part: &str) -> Option<(&str, Option<u32>)> {
+    let end = part.find('p')?;
+    let (height, fps) = (&part[..end], &part[end + 1..]);
+    if height.is_empty() || !height.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let fps = match fps {
+        "" => None,
+        fps => Some(fps.parse().ok()?),
+    };
+    Some((&part[..=end], fps))
+}
+
+impl Format {
+    /// The quality parsed from `format_note`, see `QualityInfo`. Returns `None` if the format has
+    /// no note.
+    pub fn quality_info(&self) -> Option<QualityInfo> {
+        let mut info = QualityInfo::parse(self.format_note.as_deref()?);
+        if info.fps.is_none() {
+            info.fps = self
+                .fps
+                .filter(|fps| fps.is_finite() && *fps > 0.0)
+                .map(|fps| fps.round() as u32);
+        }
+        Some(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QualityInfo;
+    use crate::Format;
+
+    #[test]
+    fn test_parse() {
+        let info = QualityInfo::parse("1080p60 HDR");
+        assert_eq!(info.label.as_deref(), Some("1080p"));
+        assert_eq!(info.fps, Some(60));
+        assert!(info.hdr);
+        assert!(!info.premium);
+
+        let info = QualityInfo::parse("1080p Premium");
+        assert_eq!(info.label.as_deref(), Some("1080p"));
+        assert_eq!(info.fps, None);
+        assert!(info.premium);
+
+        let info = QualityInfo::parse("medium, DASH audio");
+        assert_eq!(info.label.as_deref(), Some("medium DASH audio"));
+        assert_eq!(QualityInfo::parse("Premium").label, None);
+    }
+
+    #[test]
+    fn test_quality_info() {
+        let format = Format {
+            format_note: Some("720p".into()),
+            fps: Some(29.97),
+            ..Default::default()
+        };
+        let info = format.quality_info().unwrap();
+        assert_eq!(info.label.as_deref(), Some("720p"));
+        assert_eq!(info.fps, Some(30));
+        assert_eq!(Format::default().quality_info(), None);
+    }
+}
@@ -0,0 +1,297 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::time::Duration;
+
+use crate::progress::parse_finished_line;
+use crate::{Diagnostics, Digest};
+
+/// What happened to a single entry when downloading with `YoutubeDl::download_to`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DownloadOutcome {
+    /// The entry was downloaded to this path (after merging/postprocessing).
+    Downloaded(PathBuf),
+    /// The entry was downloaded to this path and hashed, with `YoutubeDl::verify_downloads`.
+    Verified(PathBuf, Digest),
+    /// The entry was skipped because it is recorded in the download archive.
+    /// Contains the title (or ID) printed by yt-dlp.
+    AlreadyInArchive(String),
+    /// The entry was skipped because the file already exists. With `YoutubeDl::verify_downloads`,
+    /// the existing file is hashed as well.
+    AlreadyExists(PathBuf, Option<Digest>),
+    /// Downloading the entry failed with this error message.
+    Failed(String),
+}
+
+/// The meaning of yt-dlp's exit code, as documented by yt-dlp.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ExitReason {
+    /// Exit code 0
+    #[default]
+    Success,
+    /// Exit code 1: an error occurred (e.g. a video is unavailable)
+    Error,
+    /// Exit code 2: invalid command line options
+    InvalidOptions,
+    /// Exit code 100: yt-dlp updated itself and must be restarted
+    Updated,
+    /// Exit code 101: the download was stopped early by `--max-downloads`, `--break-on-existing`
+    /// or another `--break-*` option. Usually this is not an error.
+    Stopped,
+    /// Any other exit code
+    Other(i32),
+    /// The process was terminated by this signal instead of exiting (e.g. `SIGKILL` from the OOM
+    /// killer). Only on Unix.
+    Signal(i32),
+}
+
+impl ExitReason {
+    /// The reason for the given exit code.
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            0 => ExitReason::Success,
+            1 => ExitReason::Error,
+            2 => ExitReason::InvalidOptions,
+            100 => ExitReason::Updated,
+            101 => ExitReason::Stopped,
+            code => ExitReason::Other(code),
+        }
+    }
+
+    /// The reason for the exit status of the process, `Signal` if it has no exit code.
+    pub fn from_status(status: ExitStatus) -> Self {
+        match status.code() {
+            Some(code) => Self::from_code(code),
+            None => ExitReason::Signal(signal(status)),
+        }
+    }
+
+    /// Whether yt-dlp finished without errors (`Success` or `Stopped`).
+    pub fn is_success(self) -> bool {
+        matches!(self, ExitReason::Success | ExitReason::Stopped)
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn signal(status: ExitStatus) -> i32 {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal().unwrap_or_default()
+}
+
+#[cfg(not(unix))]
+pub(crate) fn signal(_status: ExitStatus) -> i32 {
+    0
+}
+
+/// Summary of a download run, returned by `YoutubeDl::download_to`.
+#[derive(Clone, Debug, Default)]
+pub struct RunReport {
+    /// The outcome of each entry, in the order yt-dlp processed them.
+    pub outcomes: Vec<DownloadOutcome>,
+    /// Why yt-dlp exited. This is `Success` or `Stopped`, unless errors were ignored with
+    /// `YoutubeDl::ignore_errors`.
+    pub exit_reason: ExitReason,
+    /// Information about yt-dlp's environment, if enabled with `YoutubeDl::diagnostics`.
+    pub diagnostics: Option<Diagnostics>,
+    /// The total size of the files downloaded in this run. Only counted when progress is
+    /// printed, with `YoutubeDl::measure_bandwidth` or `YoutubeDl::hooks`.
+    pub bytes_downloaded: u64,
+    /// The total time spent downloading the files counted in `bytes_downloaded`.
+    pub download_time: Duration,
+    /// The downloaded videos and their previews, made with `YoutubeDl::generate_preview`.
+    pub previews: Vec<(PathBuf, PathBuf)>,
+    /// The files that could not be hashed by `YoutubeDl::verify_downloads`, with the error. They
+    /// keep their outcome, the download itself succeeded.
+    pub verify_failures: Vec<(PathBuf, String)>,
+    /// The videos for which `YoutubeDl::generate_preview` failed, with the error.
+    pub preview_failures: Vec<(PathBuf, String)>,
+}
+
+impl RunReport {
+    /// Paths of all files that were downloaded or already existed.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.outcomes.iter().filter_map(|outcome| match outcome {
+            DownloadOutcome::Downloaded(path)
+            | DownloadOutcome::Verified(path, _)
+            | DownloadOutcome::AlreadyExists(path, _) => Some(path.as_path()),
+            _ => None,
+        })
+    }
+
+    /// The average download speed in bytes per second, if anything was downloaded.
+    pub fn average_speed(&self) -> Option<f64> {
+        let seconds = self.download_time.as_secs_f64();
+        (self.bytes_downloaded > 0 && seconds > 0.0).then(|| self.bytes_downloaded as f64 / seconds)
+    }
+
+    /// Whether any entry failed.
+    pub fn has_failures(&self) -> bool {
+        self.outcomes
+            .iter()
+            .any(|outcome| matches!(outcome, DownloadOutcome::Failed(_)))
+    }
+
+    pub(crate) fn from_output(stdout: &str, stderr: &str) -> Self {
+        let mut outcomes = parse_stdout(stdout);
+        outcomes.extend(
+            stderr
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix("ERROR: "))
+                .map(|message| DownloadOutcome::Failed(message.to_string())),
+        );
+        let mut report = RunReport {
+            outcomes,
+            ..Default::default()
+        };
+        for (bytes, elapsed) in stdout.lines().filter_map(parse_finished_line) {
+            report.bytes_downloaded += bytes;
+            report.download_time += elapsed;
+        }
+        report
+    }
+}
+
+fn unquote(s: &str) -> &str {
+    s.trim()
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(s.trim())
+}
+
+#[derive(Default)]
+struct Entry {
+    path: Option<PathBuf>,
+    exists: bool,
+}
+
+impl Entry {
+    fn finish(self, outcomes: &mut Vec<DownloadOutcome>) {
+        match (self.path, self.exists) {
+            (Some(path), true) => outcomes.push(DownloadOutcome::AlreadyExists(path, None)),
+            (Some(path), false) => outcomes.push(DownloadOutcome::Downloaded(path)),
+            (None, _) => {}
+        }
+    }
+}
+
+/// Follows the messages yt-dlp prints for each entry: `[info]` starts a new entry, and the
+/// destination is updated by each step that creates a new file (download, merge, postprocessors).
+fn parse_stdout(stdout: &str) -> Vec<DownloadOutcome> {
+    let mut outcomes = vec![];
+    let mut entry: Option<Entry> = None;
+
+    for line in stdout.lines().map(str::trim) {
+        let (tag, message) = match line
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once("] "))
+        {
+            Some(parts) => parts,
+            None => continue,
+        };
+
+        match tag {
+            "info" if message.contains(": Downloading ") => {
+                if let Some(previous) = entry.replace(Entry::default()) {
+                    previous.finish(&mut outcomes);
+                }
+            }
+            "download" => {
+                if let Some(title) =
+                    message.strip_suffix(" has already been recorded in the archive")
+                {
+                    outcomes.push(DownloadOutcome::AlreadyInArchive(title.to_string()));
+                } else if let Some(path) = message
+                    .strip_suffix(" has already been downloaded and merged")
+                    .or_else(|| message.strip_suffix(" has already been downloaded"))
+                {
+                    let current = entry.get_or_insert_with(Entry::default);
+                    current.path = Some(PathBuf::from(path));
+                    current.exists = true;
+                } else if let Some(path) = message.strip_prefix("Destination: ") {
+                    entry.get_or_insert_with(Entry::default).path = Some(PathBuf::from(path));
+                }
+            }
+            "Merger" => {
+                if let Some(path) = message.strip_prefix("Merging formats into ") {
+                    entry.get_or_insert_with(Entry::default).path =
+                        Some(PathBuf::from(unquote(path)));
+                }
+            }
+            "MoveFiles" => {
+                if let Some((_, to)) = message
+                    .strip_prefix("Moving file ")
+                    .and_then(|rest| rest.split_once(" to "))
+                {
+                    entry.get_or_insert_with(Entry::default).path =
+                        Some(PathBuf::from(unquote(to)));
+                }
+            }
+            _ => {
+                // e.g. `[ExtractAudio] Destination: x.mp3` or
+                // `[VideoRemuxer] Remuxing video from webm to mp4; Destination: x.mp4`
+                if let Some((_, path)) = message.split_once("Destination: ") {
+                    entry.get_or_insert_with(Entry::default).path = Some(PathBuf::from(path));
+                }
+            }
+        }
+    }
+
+    if let Some(last) = entry {
+        last.finish(&mut outcomes);
+    }
+    outcomes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DownloadOutcome, ExitReason, RunReport};
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_outcomes() {
+        let stdout = r#"[youtube:tab] Extracting URL: https://www.youtube.com/playlist?list=abc
+[download] Downloading item 1 of 4
+[download] First video has already been recorded in the archive
+[download] Downloading item 2 of 4
+[youtube] Extracting URL: https://www.youtube.com/watch?v=b
+[info] b: Downloading 1 format(s): 137+140
+[download] Destination: out/Second.f137.mp4
+[download] 100% of   10.00MiB in 00:00:01 at 10.00MiB/s
+[download] Destination: out/Second.f140.m4a
+[Merger] Merging formats into "out/Second.mp4"
+[download] Downloading item 3 of 4
+[info] c: Downloading 1 format(s): 251
+[download] out/Third.webm has already been downloaded
+[download] Downloading item 4 of 4
+[info] d: Downloading 1 format(s): 251
+[download] Destination: out/Fourth.webm
+[ExtractAudio] Destination: out/Fourth.mp3
+"#;
+        let stderr = "WARNING: something\nERROR: [youtube] e: Video unavailable\n";
+        let report = RunReport::from_output(stdout, stderr);
+
+        assert_eq!(
+            report.outcomes,
+            vec![
+                DownloadOutcome::AlreadyInArchive("First video".into()),
+                DownloadOutcome::Downloaded(PathBuf::from("out/Second.mp4")),
+                DownloadOutcome::AlreadyExists(PathBuf::from("out/Third.webm"), None),
+                DownloadOutcome::Downloaded(PathBuf::from("out/Fourth.mp3")),
+                DownloadOutcome::Failed("[youtube] e: Video unavailable".into()),
+            ]
+        );
+        assert_eq!(report.files().count(), 3);
+        assert!(report.has_failures());
+        assert_eq!(report.bytes_downloaded, 10 * 1024 * 1024);
+        assert_eq!(report.download_time, Duration::from_secs(1));
+        assert_eq!(report.average_speed(), Some(10.0 * 1024.0 * 1024.0));
+    }
+
+    #[test]
+    fn test_exit_reason() {
+        assert_eq!(ExitReason::from_code(101), ExitReason::Stopped);
+        assert!(ExitReason::from_code(101).is_success());
+        assert_eq!(ExitReason::from_code(2), ExitReason::InvalidOptions);
+        assert!(!ExitReason::from_code(137).is_success());
+    }
+}
@@ -0,0 +1,535 @@
+//! Running the yt-dlp process. The steps around it (rate limiting, tracing, hooks) are written
+//! once in `YoutubeDl::run_with`, only spawning and waiting for the process differ between the
+//! blocking and the tokio implementation of `ProcessRunner`.
+
+use std::future::Future;
+use std::io::Read;
+use std::path::Path;
+use std::pin::pin;
+use std::process::Child;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use crate::hooks::{FileCallback, HooksHandle};
+use crate::progress::{self, Progress};
+#[cfg(feature = "tokio")]
+use crate::tee::AsyncTeeReader;
+use crate::tee::{Tee, TeeReader};
+use crate::trace::{self, Instrument};
+use crate::{
+    read_all, read_lines, read_tail, BinaryInvocation, Error, Flavor, ProcessResult, RunReport,
+    YoutubeDl,
+};
+#[cfg(feature = "tokio")]
+use crate::{read_all_async, read_lines_async, read_tail_async};
+
+pub(crate) trait ProcessRunner {
+    type Child;
+
+    /// Wait, e.g. for the rate limiter or before a retry.
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()>;
+
+    /// Start the process, returning it with its ID.
+    fn spawn(&self, builder: &YoutubeDl, args: Vec<&str>) -> Result<(Self::Child, u32), Error>;
+
+    /// Collect the output of the process and wait for it to exit, within `process_timeout`.
+    fn wait(
+        &self,
+        builder: &YoutubeDl,
+        child: Self::Child,
+    ) -> impl Future<Output = Result<ProcessResult, Error>>;
+}
+
+/// Runs the process with the standard library, reading its output on threads. Its futures never
+/// wait, run them with `block_on`.
+pub(crate) struct Blocking;
+
+impl ProcessRunner for Blocking {
+    type Child = Child;
+
+    async fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+
+    fn spawn(&self, builder: &YoutubeDl, args: Vec<&str>) -> Result<(Self::Child, u32), Error> {
+        let child = builder
+            .command(args)
+            .spawn()
+            .map_err(|err| builder.spawn_error(err))?;
+        let id = child.id();
+        Ok((child, id))
+    }
+
+    async fn wait(
+        &self,
+        builder: &YoutubeDl,
+        mut child: Self::Child,
+    ) -> Result<ProcessResult, Error> {
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+        wait_blocking(builder, child, stdout, stderr, None)
+    }
+}
+
+/// Read `stdout` and `stderr` of the process on threads and wait for it to exit, within
+/// `process_timeout`. The process is killed when `cancelled` is set.
+pub(crate) fn wait_blocking<O, E>(
+    builder: &YoutubeDl,
+    child: Child,
+    stdout: O,
+    stderr: E,
+    cancelled: Option<&AtomicBool>,
+) -> Result<ProcessResult, Error>
+where
+    O: Read + Send + 'static,
+    E: Read + Send + 'static,
+{
+    let reader = OutputReader::new(builder);
+    let deadline = builder
+        .process_timeout
+        .map(|timeout| Instant::now() + timeout);
+    let result = wait_for(&reader, deadline, cancelled, child, stdout, stderr);
+    // The reader threads outlive a process that timed out, its output is not reported anymore.
+    reader.close();
+    result
+}
+
+/// The process is always waited for, also when it is killed after an error.
+fn wait_for<O, E>(
+    reader: &OutputReader,
+    deadline: Option<Instant>,
+    cancelled: Option<&AtomicBool>,
+    mut child: Child,
+    stdout: O,
+    stderr: E,
+) -> Result<ProcessResult, Error>
+where
+    O: Read + Send + 'static,
+    E: Read + Send + 'static,
+{
+    use wait_timeout::ChildExt;
+
+    // Continually read from stdout and stderr so that neither fills up and blocks the
+    // process forever. The readers run on their own threads, so that the timeout also
+    // applies when yt-dlp hangs without closing its output.
+    let stdout = spawn_reader(reader, stdout, OutputReader::read_stdout);
+    let stderr = spawn_reader(reader, stderr, OutputReader::read_stderr);
+
+    let stdout = match receive_until(&stdout, deadline, cancelled) {
+        Some(stdout) => stdout,
+        None => return Err(timed_out(&mut child)),
+    };
+    if stdout.is_err() {
+        // Stop the process, otherwise stderr stays open until it finishes.
+        stop(&mut child);
+    }
+    let stderr = match receive_until(&stderr, deadline, cancelled) {
+        Some(stderr) => stderr,
+        None => return Err(timed_out(&mut child)),
+    };
+    let (stdout, stderr) = match (stdout, stderr) {
+        (Ok(stdout), Ok(stderr)) => (stdout, stderr),
+        (Err(err), _) | (_, Err(err)) => {
+            stop(&mut child);
+            return Err(err);
+        }
+    };
+
+    let exit_code = match deadline {
+        Some(deadline) => {
+            match child.wait_timeout(deadline.saturating_duration_since(Instant::now()))? {
+                Some(status) => status,
+                None => return Err(timed_out(&mut child)),
+            }
+        }
+        None => child.wait()?,
+    };
+
+    Ok(ProcessResult {
+        stdout,
+        stderr,
+        exit_code,
+    })
+}
+
+/// Kill the process and wait for it, so that it doesn't stay around as a zombie.
+fn stop(child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Also used when the run was cancelled, nobody receives the error then.
+fn timed_out(child: &mut Child) -> Error {
+    stop(child);
+    Error::ProcessTimeout
+}
+
+/// Read the output of the child on a new thread, sending the result to the returned channel.
+/// The thread is detached, so that a hanging child can't block the caller.
+fn spawn_reader<R: Read + Send + 'static>(
+    reader: &OutputReader,
+    output: R,
+    read: fn(&OutputReader, R) -> Result<Vec<u8>, Error>,
+) -> mpsc::Receiver<Result<Vec<u8>, Error>> {
+    let (sender, receiver) = mpsc::channel();
+    let reader = reader.clone();
+    thread::spawn(move || {
+        // The receiver is gone if the process timed out.
+        let _ = sender.send(read(&reader, output));
+    });
+    receiver
+}
+
+/// Runs the process with tokio.
+#[cfg(feature = "tokio")]
+pub(crate) struct Tokio;
+
+#[cfg(feature = "tokio")]
+impl ProcessRunner for Tokio {
+    type Child = tokio::process::Child;
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+
+    fn spawn(&self, builder: &YoutubeDl, args: Vec<&str>) -> Result<(Self::Child, u32), Error> {
+        let mut command = tokio::process::Command::from(builder.command(args));
+        if let Some(callback) = &builder.before_spawn_async {
+            (callback.0)(&mut command);
+        }
+        let child = command.spawn().map_err(|err| builder.spawn_error(err))?;
+        let id = child.id().unwrap_or_default();
+        Ok((child, id))
+    }
+
+    async fn wait(
+        &self,
+        builder: &YoutubeDl,
+        mut child: Self::Child,
+    ) -> Result<ProcessResult, Error> {
+        use tokio::time::timeout;
+
+        // Continually read from stdout and stderr so that neither fills up and blocks the process forever.
+        let child_stdout = child.stdout.take().unwrap();
+        let child_stderr = child.stderr.take().unwrap();
+        let reader = OutputReader::new(builder);
+        let run = async {
+            let (stdout, stderr) = match tokio::try_join!(
+                reader.read_stdout_async(child_stdout),
+                reader.read_stderr_async(child_stderr)
+            ) {
+                Ok(output) => output,
+                Err(err @ Error::OutputTooLarge { .. }) => {
+                    child.kill().await?;
+                    return Err(err);
+                }
+                Err(err) => return Err(err),
+            };
+            let exit_code = child.wait().await?;
+            Ok(ProcessResult {
+                stdout,
+                stderr,
+                exit_code,
+            })
+        };
+
+        // The timeout also applies to reading, in case yt-dlp hangs without closing its output.
+        match builder.process_timeout {
+            Some(dur) => match timeout(dur, run).await {
+                Ok(result) => result,
+                Err(_) => {
+                    child.kill().await?;
+                    Err(Error::ProcessTimeout)
+                }
+            },
+            None => run.await,
+        }
+    }
+}
+
+impl YoutubeDl {
+    pub(crate) fn run_process(&self, args: Vec<&str>) -> Result<ProcessResult, Error> {
+        block_on(self.run_with(&Blocking, args))
+    }
+
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn run_process_async(&self, args: Vec<&str>) -> Result<ProcessResult, Error> {
+        self.run_with(&Tokio, args).await
+    }
+
+    /// Run the process, then again with each of the `fallback_paths` while it fails with an
+    /// extractor error before producing any output.
+    pub(crate) async fn run_with<R: ProcessRunner>(
+        &self,
+        runner: &R,
+        args: Vec<&str>,
+    ) -> Result<ProcessResult, Error> {
+        let mut result = self.run_once(runner, args.clone()).await?;
+        for (attempt, path) in (1..).zip(&self.fallback_paths) {
+            if result.exit_code.success()
+                || !is_extractor_error(&result.stderr)
+                || produced_output(&result.stdout)
+            {
+                break;
+            }
+            log::warn!(
+                "extractor error for {}, retrying with {}",
+                self.url,
+                path.display()
+            );
+            if let Some(hooks) = &self.hooks {
+                let stderr = String::from_utf8_lossy(&result.stderr).into_owned();
+                let error = Error::from_exit_status(result.exit_code, stderr);
+                hooks.0.on_retry(&self.url, attempt, &error);
+            }
+            let mut fallback = self.clone();
+            fallback.invocation = Some(BinaryInvocation::Executable(path.clone()));
+            result = fallback.run_once(runner, args.clone()).await?;
+        }
+        Ok(result)
+    }
+
+    async fn run_once<R: ProcessRunner>(
+        &self,
+        runner: &R,
+        args: Vec<&str>,
+    ) -> Result<ProcessResult, Error> {
+        if let Some(limiter) = &self.limiter {
+            runner.sleep(limiter.reserve(&self.url)).await;
+        }
+        let span = trace::process_span(&self.url, &args);
+        async move {
+            let start = Instant::now();
+            let (child, id) = trace::spawn_span().in_scope(|| runner.spawn(self, args))?;
+            if let Some(hooks) = &self.hooks {
+                hooks.0.on_spawn(&self.url, id);
+            }
+            let result = runner
+                .wait(self, child)
+                .instrument(trace::wait_span())
+                .await?;
+            self.complete(&result, start.elapsed());
+            Ok(result)
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+/// What the output of the process is read with, so that the reader threads only take these
+/// parts of the builder instead of a clone of all of it.
+#[derive(Clone)]
+pub(crate) struct OutputReader {
+    url: String,
+    flavor: Flavor,
+    hooks: Option<HooksHandle>,
+    on_file_downloaded: Option<FileCallback>,
+    tee_stdout: Option<Tee>,
+    tee_stderr: Option<Tee>,
+    max_stdout_bytes: Option<u64>,
+    capture_stderr_lines: Option<usize>,
+    /// Cleared by `close`, after which no more lines are passed to the callbacks.
+    open: Arc<AtomicBool>,
+}
+
+impl OutputReader {
+    pub(crate) fn new(builder: &YoutubeDl) -> Self {
+        OutputReader {
+            url: builder.url.clone(),
+            flavor: builder.flavor,
+            hooks: builder.hooks.clone(),
+            on_file_downloaded: builder.on_file_downloaded.clone(),
+            tee_stdout: builder.tee_stdout.clone(),
+            tee_stderr: builder.tee_stderr.clone(),
+            max_stdout_bytes: builder.max_stdout_bytes,
+            capture_stderr_lines: builder.capture_stderr_lines,
+            open: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Stop calling the callbacks, for readers that are still running after the run is over.
+    pub(crate) fn close(&self) {
+        self.open.store(false, Ordering::Relaxed);
+    }
+
+    fn read_stdout(&self, reader: impl Read) -> Result<Vec<u8>, Error> {
+        let reader = TeeReader::new(reader, self.tee_stdout.as_ref());
+        // Read one byte more than the limit to detect when it's exceeded.
+        let reader = reader.take(self.max_stdout_bytes.map_or(u64::MAX, |limit| limit + 1));
+        let stdout = if self.hooks.is_some() || self.on_file_downloaded.is_some() {
+            read_lines(reader, |line| self.handle_stdout_line(line))?
+        } else {
+            read_all(reader)?
+        };
+        self.check_stdout_size(stdout)
+    }
+
+    fn check_stdout_size(&self, stdout: Vec<u8>) -> Result<Vec<u8>, Error> {
+        match self.max_stdout_bytes {
+            Some(limit) if stdout.len() as u64 > limit => Err(Error::OutputTooLarge { limit }),
+            _ => Ok(stdout),
+        }
+    }
+
+    fn read_stderr(&self, reader: impl Read) -> Result<Vec<u8>, Error> {
+        let reader = TeeReader::new(reader, self.tee_stderr.as_ref());
+        match (self.capture_stderr_lines, &self.hooks) {
+            (Some(lines), _) => read_tail(reader, lines, |line| self.handle_stderr_tail(line)),
+            (None, Some(hooks)) => read_lines(reader, |line| self.handle_stderr_line(hooks, line)),
+            (None, None) => read_all(reader),
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    async fn read_stdout_async(
+        &self,
+        reader: impl tokio::io::AsyncRead + Unpin,
+    ) -> Result<Vec<u8>, Error> {
+        use tokio::io::AsyncReadExt;
+
+        let mut tee = AsyncTeeReader::new(reader, self.tee_stdout.as_ref());
+        let reader = (&mut tee).take(self.max_stdout_bytes.map_or(u64::MAX, |limit| limit + 1));
+        let stdout = if self.hooks.is_some() || self.on_file_downloaded.is_some() {
+            read_lines_async(reader, |line| self.handle_stdout_line(line)).await?
+        } else {
+            read_all_async(reader).await?
+        };
+        tee.finish().await;
+        self.check_stdout_size(stdout)
+    }
+
+    #[cfg(feature = "tokio")]
+    async fn read_stderr_async(
+        &self,
+        reader: impl tokio::io::AsyncRead + Unpin,
+    ) -> Result<Vec<u8>, Error> {
+        let mut tee = AsyncTeeReader::new(reader, self.tee_stderr.as_ref());
+        let reader = &mut tee;
+        let stderr = match (self.capture_stderr_lines, &self.hooks) {
+            (Some(lines), _) => {
+                read_tail_async(reader, lines, |line| self.handle_stderr_tail(line)).await
+            }
+            (None, Some(hooks)) => {
+                read_lines_async(reader, |line| self.handle_stderr_line(hooks, line)).await
+            }
+            (None, None) => read_all_async(reader).await,
+        };
+        tee.finish().await;
+        stderr
+    }
+
+    fn handle_stdout_line(&self, line: &[u8]) {
+        if !self.open.load(Ordering::Relaxed) {
+            return;
+        }
+        let line = String::from_utf8_lossy(line);
+        if let Some(path) = progress::parse_file_line(&line) {
+            if let Some(callback) = &self.on_file_downloaded {
+                (callback.0)(Path::new(path));
+            }
+            return;
+        }
+
+        let hooks = match &self.hooks {
+            Some(hooks) => hooks,
+            None => return,
+        };
+        let progress = match self.flavor {
+            Flavor::YoutubeDl => Progress::parse_legacy_line(&line),
+            Flavor::YtDlp => Progress::parse_line(&line),
+        };
+        if let Some(progress) = progress {
+            hooks.0.on_progress(&self.url, &progress);
+        } else if let Some((postprocessor, message)) = progress::parse_postprocessor_line(&line) {
+            hooks.0.on_postprocess(&self.url, postprocessor, message);
+        }
+    }
+
+    fn handle_stderr_line(&self, hooks: &HooksHandle, line: &[u8]) {
+        if !self.open.load(Ordering::Relaxed) {
+            return;
+        }
+        let line = String::from_utf8_lossy(line);
+        hooks.0.on_stderr_line(&self.url, line.trim_end());
+    }
+
+    fn handle_stderr_tail(&self, line: &[u8]) {
+        if let Some(hooks) = &self.hooks {
+            self.handle_stderr_line(hooks, line);
+        }
+    }
+}
+
+/// Whether yt-dlp failed because of a bug in an extractor, which it asks to report upstream
+/// (often because the site changed, and fixed in a newer version).
+fn is_extractor_error(stderr: &[u8]) -> bool {
+    let stderr = String::from_utf8_lossy(stderr);
+    stderr.contains("please report this issue on") || stderr.contains("Unable to extract")
+}
+
+/// Whether a run printed downloaded files or JSON entries, which a fallback run would repeat.
+fn produced_output(stdout: &[u8]) -> bool {
+    let stdout = String::from_utf8_lossy(stdout);
+    stdout.lines().any(|line| line.starts_with('{'))
+        || RunReport::from_output(&stdout, "").files().next().is_some()
+}
+
+/// Wakes the thread running `block_on`.
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Run a future of the `Blocking` runner on the current thread. Its futures complete on the
+/// first poll, but a future that does wait only parks the thread until it is woken.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// How often `receive_until` checks whether the run was cancelled.
+const CANCEL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Receive from the channel until the deadline, returning `None` if it passed or the run was
+/// `cancelled`.
+fn receive_until<T>(
+    receiver: &mpsc::Receiver<T>,
+    deadline: Option<Instant>,
+    cancelled: Option<&AtomicBool>,
+) -> Option<T> {
+    loop {
+        if cancelled.is_some_and(|cancelled| cancelled.load(Ordering::Relaxed)) {
+            return None;
+        }
+        let timeout = match (deadline, cancelled) {
+            (Some(deadline), None) => deadline.saturating_duration_since(Instant::now()),
+            (Some(deadline), Some(_)) => {
+                CANCEL_INTERVAL.min(deadline.saturating_duration_since(Instant::now()))
+            }
+            (None, Some(_)) => CANCEL_INTERVAL,
+            (None, None) => return Some(receiver.recv().expect("output reader panicked")),
+        };
+        match receiver.recv_timeout(timeout) {
+            Ok(value) => return Some(value),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    return None;
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => panic!("output reader panicked"),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! A `tower::Service` that runs yt-dlp, so that rate limiting, retries, timeouts and concurrency
+//! limits from the tower ecosystem can be layered around yt-dlp invocations.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tower_service::Service;
+
+use crate::{Error, YoutubeDl, YoutubeDlOutput};
+
+/// Runs each requested builder with `YoutubeDl::run_async`. The service is always ready, use
+/// tower's `ConcurrencyLimit` or `RateLimit` layers to limit the number of yt-dlp processes.
+///
+/// ```rust,no_run
+/// # async fn example() -> Result<(), youtube_dl::Error> {
+/// use tower_service::Service;
+/// use youtube_dl::service::YoutubeDlService;
+/// use youtube_dl::YoutubeDl;
+///
+/// let mut service = YoutubeDlService::new();
+/// let output = service
+///     .call(YoutubeDl::new("https://www.youtube.com/watch?v=VFbhKZFzbzk"))
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct YoutubeDlService {
+    _private: (),
+}
+
+impl YoutubeDlService {
+    /// Create the service.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Service<YoutubeDl> for YoutubeDlService {
+    type Response = YoutubeDlOutput;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<YoutubeDlOutput, Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: YoutubeDl) -> Self::Future {
+        Box::pin(async move { request.run_async().await })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::YoutubeDlService;
+    use crate::tests::fake_yt_dlp;
+    use crate::YoutubeDl;
+    use tower_service::Service;
+
+    #[tokio::test]
+    async fn test_service() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(dir.path(), r#"echo '{"id": "abc"}'"#);
+        let mut builder = YoutubeDl::new("https://www.youtube.com/watch?v=abc");
+        builder.youtube_dl_path(path);
+
+        let output = YoutubeDlService::new().call(builder).await.unwrap();
+        assert_eq!(output.into_single_video().unwrap().id, "abc");
+    }
+}
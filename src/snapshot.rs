@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{Error, YoutubeDlOutput};
+
+/// The version of the model (`SingleVideo`, `Playlist`, ...) stored by
+/// `YoutubeDlOutput::to_versioned_json`. It is incremented when a change of the model needs
+/// stored JSON to be migrated, and the migration is added to `migrate`.
+pub const MODEL_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct SnapshotRef<'a> {
+    model_version: u32,
+    crate_version: &'static str,
+    output: &'a YoutubeDlOutput,
+}
+
+#[derive(Deserialize)]
+struct Snapshot {
+    model_version: u32,
+    output: Value,
+}
+
+impl YoutubeDlOutput {
+    /// Serialize to JSON tagged with `MODEL_VERSION` (and the crate version, for reference), for
+    /// storing the metadata long-term. Read it with `from_versioned_json`.
+    pub fn to_versioned_json(&self) -> Result<String, Error> {
+        let snapshot = SnapshotRef {
+            model_version: MODEL_VERSION,
+            crate_version: env!("CARGO_PKG_VERSION"),
+            output: self,
+        };
+        Ok(serde_json::to_string(&snapshot)?)
+    }
+
+    /// Parse JSON written by `to_versioned_json`, migrating it if it was written with an older
+    /// model version. Fails with `Error::UnsupportedModelVersion` if it was written by a newer
+    /// version of this crate.
+    pub fn from_versioned_json(json: &str) -> Result<Self, Error> {
+        let snapshot: Snapshot = serde_json::from_str(json)?;
+        let output = migrate(snapshot.model_version, snapshot.output)?;
+        Ok(serde_json::from_value(output)?)
+    }
+}
+
+/// Upgrade the serialized output from `version` to `MODEL_VERSION`, one version at a time.
+fn migrate(version: u32, output: Value) -> Result<Value, Error> {
+    match version {
+        MODEL_VERSION => Ok(output),
+        version => Err(Error::UnsupportedModelVersion { version }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, SingleVideo, YoutubeDlOutput};
+
+    #[test]
+    fn test_versioned_json() {
+        let output = YoutubeDlOutput::SingleVideo(Box::new(SingleVideo {
+            id: "abc".into(),
+            title: Some("Title".into()),
+            ..Default::default()
+        }));
+        let json = output.to_versioned_json().unwrap();
+        assert!(json.starts_with(r#"{"model_version":1,"crate_version":""#));
+        assert_eq!(YoutubeDlOutput::from_versioned_json(&json).unwrap(), output);
+
+        let newer = json.replacen(r#""model_version":1"#, r#""model_version":99"#, 1);
+        assert!(matches!(
+            YoutubeDlOutput::from_versioned_json(&newer),
+            Err(Error::UnsupportedModelVersion { version: 99 })
+        ));
+    }
+}
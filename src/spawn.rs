@@ -0,0 +1,103 @@
+use std::env;
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{Error, YoutubeDl};
+
+impl YoutubeDl {
+    /// `Error::SpawnFailed` with the details needed to tell why the program could not be
+    /// started: where it was looked for, and whether it was found but is not executable.
+    pub(crate) fn spawn_error(&self, error: io::Error) -> Error {
+        let program = Path::new(self.program()[0]);
+        let is_bare = program
+            .parent()
+            .map_or(true, |parent| parent.as_os_str().is_empty());
+        let search_path = if is_bare { env::var_os("PATH") } else { None };
+        let found = match &search_path {
+            Some(search_path) => find_in_path(program, search_path),
+            None => Some(program.to_path_buf()).filter(|path| path.is_file()),
+        };
+        let not_executable = found.as_deref().is_some_and(|path| !is_executable(path));
+        Error::SpawnFailed {
+            program: found.unwrap_or_else(|| program.to_path_buf()),
+            search_path,
+            not_executable,
+            error,
+        }
+    }
+}
+
+/// The first file named `program` in the directories of `search_path`.
+fn find_in_path(program: &Path, search_path: &OsString) -> Option<PathBuf> {
+    env::split_paths(search_path).find_map(|dir| {
+        let candidate = dir.join(program);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        let candidate = candidate.with_extension("exe");
+        (cfg!(windows) && candidate.is_file()).then_some(candidate)
+    })
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    true
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs;
+
+    use crate::{Error, YoutubeDl};
+
+    #[test]
+    fn test_not_executable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("yt-dlp");
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+
+        let error = YoutubeDl::new("https://example.com/video")
+            .youtube_dl_path(&path)
+            .run()
+            .unwrap_err();
+        match &error {
+            Error::SpawnFailed {
+                program,
+                search_path: None,
+                not_executable: true,
+                ..
+            } => assert_eq!(program, &path),
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(error.to_string().ends_with("(the file is not executable)"));
+    }
+
+    #[test]
+    fn test_not_in_path() {
+        let error = YoutubeDl::new("https://example.com/video")
+            .youtube_dl_path("youtube-dl-rs-missing-binary")
+            .run()
+            .unwrap_err();
+        match error {
+            Error::SpawnFailed {
+                program,
+                search_path: Some(_),
+                not_executable: false,
+                error,
+            } => {
+                assert_eq!(program.as_os_str(), "youtube-dl-rs-missing-binary");
+                assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+}
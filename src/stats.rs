@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::{Playlist, SingleVideo};
+
+/// Totals over the entries of a playlist, returned by `Playlist::stats`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlaylistStats {
+    /// Number of entries (not counting invalid entries)
+    pub entries: usize,
+    /// Sum of the durations of all entries with a known duration
+    pub total_duration: Duration,
+    /// Number of entries without a known duration
+    pub unknown_duration: usize,
+    /// Estimated total download size in bytes, see `Playlist::estimated_total_size`
+    pub estimated_total_size: Option<u64>,
+    /// Number of entries per uploader (or channel, if the uploader is unknown)
+    pub uploaders: BTreeMap<String, usize>,
+    /// The earliest upload date (`YYYYMMDD`) of the entries
+    pub first_upload_date: Option<String>,
+    /// The latest upload date (`YYYYMMDD`) of the entries
+    pub last_upload_date: Option<String>,
+}
+
+fn duration(entry: &SingleVideo) -> Option<Duration> {
+    Duration::try_from_secs_f64(entry.duration.as_ref()?.as_f64()?).ok()
+}
+
+impl Playlist {
+    /// Compute statistics over the entries, e.g. for reporting on archived playlists.
+    pub fn stats(&self) -> PlaylistStats {
+        let entries = self.entries.as_deref().unwrap_or_default();
+        let mut stats = PlaylistStats {
+            entries: entries.len(),
+            estimated_total_size: self.estimated_total_size(),
+            ..Default::default()
+        };
+        for entry in entries {
+            match duration(entry) {
+                Some(duration) => {
+                    stats.total_duration = stats.total_duration.saturating_add(duration)
+                }
+                None => stats.unknown_duration += 1,
+            }
+            if let Some(uploader) = entry.uploader.as_ref().or(entry.channel.as_ref()) {
+                *stats.uploaders.entry(uploader.clone()).or_default() += 1;
+            }
+            // Dates are formatted as `YYYYMMDD`, so they can be compared as strings.
+            if let Some(date) = &entry.upload_date {
+                if stats
+                    .first_upload_date
+                    .as_ref()
+                    .map_or(true, |first| date < first)
+                {
+                    stats.first_upload_date = Some(date.clone());
+                }
+                if stats
+                    .last_upload_date
+                    .as_ref()
+                    .map_or(true, |last| date > last)
+                {
+                    stats.last_upload_date = Some(date.clone());
+                }
+            }
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::{Playlist, SingleVideo};
+
+    fn entry(uploader: &str, duration: Option<f64>, date: &str) -> SingleVideo {
+        SingleVideo {
+            uploader: Some(uploader.into()),
+            duration: duration.map(|duration| json!(duration)),
+            upload_date: Some(date.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let playlist = Playlist {
+            entries: Some(vec![
+                entry("a", Some(60.0), "20210105"),
+                entry("b", Some(30.5), "20191231"),
+                entry("a", None, "20230301"),
+            ]),
+            ..Default::default()
+        };
+        let stats = playlist.stats();
+        assert_eq!(stats.entries, 3);
+        assert_eq!(stats.total_duration, Duration::from_secs_f64(90.5));
+        assert_eq!(stats.unknown_duration, 1);
+        assert_eq!(stats.uploaders["a"], 2);
+        assert_eq!(stats.uploaders["b"], 1);
+        assert_eq!(stats.first_upload_date.as_deref(), Some("20191231"));
+        assert_eq!(stats.last_upload_date.as_deref(), Some("20230301"));
+    }
+
+    #[test]
+    fn test_stats_with_huge_durations() {
+        let playlist = Playlist {
+            entries: Some(vec![
+                entry("a", Some(1e19), "20210105"),
+                entry("a", Some(1e19), "20210105"),
+                entry("a", Some(1e300), "20210105"),
+            ]),
+            ..Default::default()
+        };
+        let stats = playlist.stats();
+        assert_eq!(stats.total_duration, Duration::MAX);
+        assert_eq!(stats.unknown_duration, 1);
+    }
+}
@@ -0,0 +1,213 @@
+//! Storyboards: sprite sheets of frames for seeking previews.
+
+use crate::{Format, Protocol, SingleVideo};
+
+/// A storyboard of a video, parsed from a storyboard format (protocol `mhtml`, e.g. `sb0` on
+/// YouTube). Each sheet is an image with `rows` × `columns` tiles, one frame every
+/// `interval` seconds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Storyboard {
+    /// ID of the format
+    pub format_id: Option<String>,
+    /// Width of a tile in pixels
+    pub width: u32,
+    /// Height of a tile in pixels
+    pub height: u32,
+    /// Number of rows of tiles on each sheet
+    pub rows: u32,
+    /// Number of columns of tiles on each sheet
+    pub columns: u32,
+    /// Seconds between two tiles
+    pub interval: f64,
+    /// The sheets, in order
+    pub sheets: Vec<StoryboardSheet>,
+}
+
+/// One image of a `Storyboard`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StoryboardSheet {
+    /// URL of the image
+    pub url: String,
+    /// The part of the video covered by the sheet, in seconds
+    pub duration: f64,
+}
+
+/// The position of a frame on a sheet, returned by `Storyboard::tile_at`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tile<'a> {
+    /// URL of the sheet
+    pub url: &'a str,
+    /// Left edge of the tile in pixels
+    pub x: u32,
+    /// Top edge of the tile in pixels
+    pub y: u32,
+    /// Width of the tile in pixels
+    pub width: u32,
+    /// Height of the tile in pixels
+    pub height: u32,
+}
+
+impl Storyboard {
+    /// Parse a storyboard format. Returns `None` for other formats, or if the size of the tiles
+    /// or the sheets are missing. Sheets without a URL or a positive duration are skipped.
+    pub fn from_format(format: &Format) -> Option<Self> {
+        if format.protocol != Some(Protocol::Mhtml) {
+            return None;
+        }
+        let positive = |n: i64| u32::try_from(n).ok().filter(|n| *n > 0);
+        let pixels =
+            |n: f64| (n.is_finite() && n >= 1.0 && n <= f64::from(u32::MAX)).then_some(n as u32);
+        let rows = format.rows.and_then(positive)?;
+        let columns = format.columns.and_then(positive)?;
+        let sheets: Vec<StoryboardSheet> = format
+            .fragments
+            .as_deref()?
+            .iter()
+            .filter_map(|fragment| {
+                let duration = fragment.duration.as_ref()?.as_f64()?;
+                let valid = duration.is_finite() && duration > 0.0;
+                Some(StoryboardSheet {
+                    url: fragment.url.clone()?,
+                    duration: valid.then_some(duration)?,
+                })
+            })
+            .collect();
+        // Only the last sheet may have fewer tiles, so the first one gives the interval.
+        let interval = match format.fps {
+            Some(fps) if fps > 0.0 => 1.0 / fps,
+            _ => sheets.first()?.duration / (f64::from(rows) * f64::from(columns)),
+        };
+        Some(Storyboard {
+            format_id: format.format_id.clone(),
+            width: format.width.and_then(pixels)?,
+            height: format.height.and_then(pixels)?,
+            rows,
+            columns,
+            interval,
+            sheets,
+        })
+    }
+
+    /// The tile showing the frame at the position (in seconds), or `None` if the position is
+    /// outside of the video.
+    pub fn tile_at(&self, timestamp: f64) -> Option<Tile<'_>> {
+        // Also false for NaN.
+        let valid = timestamp >= 0.0 && self.interval > 0.0;
+        if !valid {
+            return None;
+        }
+        let columns = u64::from(self.columns);
+        let tiles = u64::from(self.rows) * columns;
+        if tiles == 0 {
+            return None;
+        }
+        let mut start = 0.0;
+        for sheet in &self.sheets {
+            if timestamp < start + sheet.duration {
+                let index = (((timestamp - start) / self.interval) as u64).min(tiles - 1);
+                return Some(Tile {
+                    url: &sheet.url,
+                    x: u32::try_from(index % columns * u64::from(self.width)).ok()?,
+                    y: u32::try_from(index / columns * u64::from(self.height)).ok()?,
+                    width: self.width,
+                    height: self.height,
+                });
+            }
+            start += sheet.duration;
+        }
+        None
+    }
+}
+
+impl SingleVideo {
+    /// The storyboards of the video, from the smallest to the largest tiles.
+    pub fn storyboards(&self) -> Vec<Storyboard> {
+        let mut storyboards: Vec<Storyboard> = self
+            .formats
+            .iter()
+            .flatten()
+            .filter_map(Storyboard::from_format)
+            .collect();
+        storyboards
+            .sort_by_key(|storyboard| u64::from(storyboard.width) * u64::from(storyboard.height));
+        storyboards
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{Storyboard, StoryboardSheet, Tile};
+    use crate::SingleVideo;
+
+    #[test]
+    fn test_tile_at() {
+        let video: SingleVideo = serde_json::from_value(json!({
+            "id": "abc",
+            "formats": [
+                {"format_id": "sb0", "protocol": "mhtml", "width": 80, "height": 45,
+                 "rows": 10, "columns": 10, "fps": 0.5, "fragments": [
+                    {"url": "https://i.ytimg.com/sb/abc/M0.jpg", "duration": 200.0},
+                    {"url": "https://i.ytimg.com/sb/abc/M1.jpg", "duration": 50.0}
+                ]},
+                {"format_id": "sb1", "protocol": "mhtml", "width": 48, "height": 27,
+                 "rows": 10, "columns": 10, "fragments": [
+                    {"url": "https://i.ytimg.com/sb/abc/L0.jpg", "duration": 250.0},
+                    {"url": "https://i.ytimg.com/sb/abc/L1.jpg", "duration": -1.0},
+                    {"url": "https://i.ytimg.com/sb/abc/L2.jpg"}
+                ]},
+                {"format_id": "sb2", "protocol": "mhtml", "width": -48, "height": 27,
+                 "rows": 10, "columns": 10, "fragments": [
+                    {"url": "https://i.ytimg.com/sb/abc/S0.jpg", "duration": 250.0}
+                ]},
+                {"format_id": "137", "protocol": "https", "width": 1920, "height": 1080}
+            ]
+        }))
+        .unwrap();
+
+        let storyboards = video.storyboards();
+        assert_eq!(storyboards.len(), 2);
+        assert_eq!(storyboards[0].format_id.as_deref(), Some("sb1"));
+        assert_eq!(storyboards[0].interval, 2.5);
+        assert_eq!(storyboards[0].sheets.len(), 1);
+
+        let storyboard = &storyboards[1];
+        assert_eq!(
+            storyboard.tile_at(25.0),
+            Some(Tile {
+                url: "https://i.ytimg.com/sb/abc/M0.jpg",
+                x: 160,
+                y: 45,
+                width: 80,
+                height: 45,
+            })
+        );
+        let tile = storyboard.tile_at(203.0).unwrap();
+        assert_eq!(tile.url, "https://i.ytimg.com/sb/abc/M1.jpg");
+        assert_eq!((tile.x, tile.y), (80, 0));
+        assert_eq!(storyboard.tile_at(250.0), None);
+        assert_eq!(storyboard.tile_at(f64::NAN), None);
+    }
+
+    #[test]
+    fn test_tile_at_large_sheets() {
+        let storyboard = Storyboard {
+            format_id: None,
+            width: u32::MAX,
+            height: 1,
+            rows: u32::MAX,
+            columns: u32::MAX,
+            interval: 1.0,
+            sheets: vec![StoryboardSheet {
+                url: "https://example.com/sheet.jpg".into(),
+                duration: 1e20,
+            }],
+        };
+        assert_eq!(storyboard.tile_at(0.0).unwrap().x, 0);
+        assert_eq!(storyboard.tile_at(1.0).unwrap().x, u32::MAX);
+        assert_eq!(storyboard.tile_at(2.0), None);
+        let tile = storyboard.tile_at(f64::from(u32::MAX) * 5.0).unwrap();
+        assert_eq!((tile.x, tile.y), (0, 5));
+    }
+}
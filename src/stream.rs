@@ -0,0 +1,120 @@
+//! Helpers for streaming audio directly from the source (e.g. for Discord music bots built on
+//! songbird), without downloading the file first.
+
+use std::collections::BTreeMap;
+
+use crate::{Error, Format, SingleVideo, YoutubeDl, YoutubeDlOutput};
+
+/// Direct URL of an audio stream, with the HTTP headers that must be sent when requesting it.
+#[derive(Clone, Debug)]
+pub struct AudioStream {
+    /// Direct URL of the audio stream
+    pub url: String,
+    /// HTTP headers required by the server (e.g. `User-Agent`)
+    pub http_headers: BTreeMap<String, String>,
+    /// The selected format
+    pub format: Format,
+}
+
+impl AudioStream {
+    /// Pick the best audio format of a video (falling back to the best format with audio).
+    pub fn from_video(video: &SingleVideo) -> Option<Self> {
+        let format = video
+            .select_formats("ba/ba*/b")?
+            .into_iter()
+            .next()?
+            .clone();
+        let url = format.url.clone()?;
+        let http_headers = format.http_headers.clone().unwrap_or_default();
+        Some(AudioStream {
+            url,
+            http_headers,
+            format,
+        })
+    }
+
+    fn from_output(output: YoutubeDlOutput) -> Result<Self, Error> {
+        // Searches like `ytsearch1:...` return a playlist.
+        let video = match output {
+            YoutubeDlOutput::SingleVideo(video) => Some(*video),
+            YoutubeDlOutput::Playlist(playlist) => {
+                playlist.entries.and_then(|e| e.into_iter().next())
+            }
+        };
+        video
+            .as_ref()
+            .and_then(AudioStream::from_video)
+            .ok_or(Error::NoMatchingFormat)
+    }
+}
+
+impl YoutubeDl {
+    /// Extract the metadata (without downloading) and return the direct URL of the best audio
+    /// format. For playlists and searches, the first entry is used.
+    pub fn audio_stream_url(&self) -> Result<AudioStream, Error> {
+        AudioStream::from_output(self.run()?)
+    }
+
+    /// Extract the metadata asynchronously and return the direct URL of the best audio format.
+    /// See `audio_stream_url` for details.
+    #[cfg(feature = "tokio")]
+    pub async fn audio_stream_url_async(&self) -> Result<AudioStream, Error> {
+        AudioStream::from_output(self.run_async().await?)
+    }
+}
+
+/// Return the direct URL of the best audio format of a video with the default options.
+/// Use `YoutubeDl::audio_stream_url` to pass options like cookies.
+pub fn fetch_audio_stream_url(url: impl Into<String>) -> Result<AudioStream, Error> {
+    YoutubeDl::new(url).audio_stream_url()
+}
+
+/// Return the direct URL of the best audio format of a video with the default options
+/// asynchronously.
+#[cfg(feature = "tokio")]
+pub async fn fetch_audio_stream_url_async(url: impl Into<String>) -> Result<AudioStream, Error> {
+    YoutubeDl::new(url).audio_stream_url_async().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AudioStream;
+    use crate::{Format, SingleVideo};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_best_audio_stream() {
+        let format = |id: &str, vcodec: Option<&str>, acodec: Option<&str>| Format {
+            format_id: Some(id.into()),
+            url: Some(format!("https://example.com/{}", id)),
+            vcodec: vcodec.map(String::from),
+            acodec: acodec.map(String::from),
+            http_headers: Some(BTreeMap::from([(
+                "User-Agent".to_string(),
+                "Mozilla/5.0".to_string(),
+            )])),
+            ..Default::default()
+        };
+        let mut video = SingleVideo {
+            formats: Some(vec![
+                format("18", Some("avc1"), Some("mp4a")),
+                format("140", None, Some("mp4a")),
+                format("251", None, Some("opus")),
+                format("137", Some("avc1"), None),
+            ]),
+            ..Default::default()
+        };
+
+        let stream = AudioStream::from_video(&video).unwrap();
+        assert_eq!(stream.url, "https://example.com/251");
+        assert_eq!(stream.http_headers.len(), 1);
+        assert_eq!(stream.http_headers["User-Agent"], "Mozilla/5.0");
+
+        video
+            .formats
+            .as_mut()
+            .unwrap()
+            .retain(|f| f.acodec.is_none());
+        assert!(AudioStream::from_video(&video).is_none());
+    }
+}
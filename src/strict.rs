@@ -0,0 +1,100 @@
+//! Detection of fields in yt-dlp's output that are missing from the model, see
+//! `YoutubeDl::strict_model`.
+
+use std::collections::BTreeSet;
+
+use serde::de::DeserializeOwned;
+use serde_ignored::Path;
+use serde_json::Value;
+
+use crate::{Playlist, SingleVideo};
+
+/// The paths (like `formats[].foo`) of all fields in `value` that are not part of the model.
+pub(crate) fn unmodeled_fields(value: &Value) -> Vec<String> {
+    let mut fields = BTreeSet::new();
+    if value["_type"] == "playlist" {
+        // Entries are parsed one by one, because invalid entries are removed from the playlist.
+        ignored_fields::<Playlist>(value, "", &mut fields);
+        for entry in value["entries"].as_array().into_iter().flatten() {
+            ignored_fields::<SingleVideo>(entry, "entries[]", &mut fields);
+        }
+    } else {
+        ignored_fields::<SingleVideo>(value, "", &mut fields);
+    }
+    fields.into_iter().collect()
+}
+
+/// Parse `value` into the model and collect the fields that serde ignored, including null values
+/// and the fields of every array element. Values that don't parse at all are skipped.
+fn ignored_fields<T: DeserializeOwned>(value: &Value, prefix: &str, fields: &mut BTreeSet<String>) {
+    let mut ignored = Vec::new();
+    let parsed: Result<T, _> = serde_ignored::deserialize(value, |path| {
+        let path = format_path(&path, prefix);
+        // Only used to tell videos and playlists apart.
+        if path != "_type" {
+            ignored.push(path);
+        }
+    });
+    if parsed.is_ok() {
+        fields.extend(ignored);
+    }
+}
+
+fn format_path(path: &Path<'_>, prefix: &str) -> String {
+    match path {
+        Path::Root => prefix.to_string(),
+        Path::Seq { parent, .. } => format!("{}[]", format_path(parent, prefix)),
+        Path::Map { parent, key } => {
+            let parent = format_path(parent, prefix);
+            if parent.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", parent, key)
+            }
+        }
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => format_path(parent, prefix),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unmodeled_fields;
+    use serde_json::json;
+
+    #[test]
+    fn test_unmodeled_fields() {
+        let video = json!({
+            "id": "a",
+            "title": "Video",
+            "new_field": 1,
+            "new_null_field": null,
+            "formats": [
+                {"format_id": "18"},
+                {"format_id": "22", "new_format_field": true}
+            ],
+            "http_headers": {"X-Anything": "is modeled as a map", "Cookie": null}
+        });
+        assert_eq!(
+            unmodeled_fields(&video),
+            ["formats[].new_format_field", "new_field", "new_null_field"]
+        );
+
+        let playlist = json!({
+            "_type": "playlist",
+            "id": "PL",
+            "playlist_field": 1,
+            "entries": [video, null]
+        });
+        assert_eq!(
+            unmodeled_fields(&playlist),
+            [
+                "entries[].formats[].new_format_field",
+                "entries[].new_field",
+                "entries[].new_null_field",
+                "playlist_field"
+            ]
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Helpers for picking subtitles.
+
+use std::collections::BTreeMap;
+
+use crate::{SingleVideo, Subtitle};
+
+/// Matches `text` against a pattern where `*` matches any number of characters.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            let text = match text.strip_prefix(prefix) {
+                Some(text) => text,
+                None => return false,
+            };
+            (0..=text.len())
+                .filter(|&i| text.is_char_boundary(i))
+                .any(|i| wildcard_match(rest, &text[i..]))
+        }
+    }
+}
+
+/// The language without the region, e.g. `en` for `en-US` or `pt_BR`.
+fn base_language(lang: &str) -> &str {
+    lang.split(['-', '_']).next().unwrap_or(lang)
+}
+
+/// The best subtitle of a language: yt-dlp lists them from worst to best.
+fn best_of(subtitles: &[Subtitle]) -> Option<&Subtitle> {
+    subtitles
+        .iter()
+        .rev()
+        .find(|subtitle| subtitle.url.is_some() || subtitle.data.is_some())
+}
+
+impl SingleVideo {
+    /// Pick a subtitle for the first of the languages that is available, like yt-dlp's
+    /// `--sub-langs`. A language matches exactly (ignoring case), as a pattern with `*`
+    /// wildcards (e.g. `en*`), or by falling back to the language without the region (`en-US`
+    /// matches `en`). Subtitles uploaded by the creator are preferred over automatic captions of
+    /// the same language, and automatic captions are only used if `allow_auto` is set. Live chat
+    /// replays are never returned.
+    pub fn best_subtitle(&self, langs: &[&str], allow_auto: bool) -> Option<&Subtitle> {
+        let subtitles: BTreeMap<String, &[Subtitle]> = self
+            .subtitles
+            .iter()
+            .flatten()
+            .filter_map(|(lang, subtitles)| Some((lang.to_lowercase(), subtitles.as_deref()?)))
+            .collect();
+        let automatic_captions: BTreeMap<String, &[Subtitle]> = self
+            .automatic_captions
+            .iter()
+            .flatten()
+            .filter(|_| allow_auto)
+            .map(|(lang, subtitles)| (lang.to_lowercase(), subtitles.as_slice()))
+            .collect();
+
+        let find = |matches: &dyn Fn(&str) -> bool| {
+            [&subtitles, &automatic_captions]
+                .into_iter()
+                .flat_map(|map| map.iter())
+                .filter(|(lang, _)| *lang != "live_chat" && matches(lang))
+                .find_map(|(_, subtitles)| best_of(subtitles))
+        };
+
+        langs.iter().find_map(|lang| {
+            let lang = lang.to_lowercase();
+            if lang.contains('*') {
+                return find(&|candidate| wildcard_match(&lang, candidate));
+            }
+            find(&|candidate| candidate == lang)
+                .or_else(|| find(&|candidate| candidate == base_language(&lang)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::wildcard_match;
+    use crate::{SingleVideo, Subtitle};
+
+    fn subtitle(url: &str) -> Vec<Subtitle> {
+        vec![
+            Subtitle {
+                ext: Some("json3".into()),
+                url: Some(format!("{}.json3", url)),
+                data: None,
+            },
+            Subtitle {
+                ext: Some("vtt".into()),
+                url: Some(format!("{}.vtt", url)),
+                data: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("en*", "en-gb"));
+        assert!(wildcard_match("*", "de"));
+        assert!(wildcard_match("*-orig", "en-orig"));
+        assert!(!wildcard_match("en*", "de"));
+        assert!(!wildcard_match("en", "en-gb"));
+    }
+
+    #[test]
+    fn test_best_subtitle() {
+        let video = SingleVideo {
+            subtitles: Some(
+                [
+                    ("en".to_string(), Some(subtitle("en"))),
+                    ("live_chat".to_string(), Some(subtitle("chat"))),
+                ]
+                .into(),
+            ),
+            automatic_captions: Some(
+                [
+                    ("en".to_string(), subtitle("auto-en")),
+                    ("de".to_string(), subtitle("auto-de")),
+                ]
+                .into(),
+            ),
+            ..Default::default()
+        };
+        let url = |subtitle: Option<&Subtitle>| subtitle.and_then(|s| s.url.clone());
+
+        assert_eq!(
+            url(video.best_subtitle(&["en-US"], true)).unwrap(),
+            "en.vtt"
+        );
+        assert_eq!(
+            url(video.best_subtitle(&["fr", "DE"], true)).unwrap(),
+            "auto-de.vtt"
+        );
+        assert_eq!(url(video.best_subtitle(&["fr", "de"], false)), None);
+        assert_eq!(url(video.best_subtitle(&["*"], false)).unwrap(), "en.vtt");
+        assert_eq!(
+            url(video.best_subtitle(&["d*"], true)).unwrap(),
+            "auto-de.vtt"
+        );
+    }
+}
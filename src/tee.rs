@@ -0,0 +1,117 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+
+/// Sink registered with `YoutubeDl::tee_stdout` or `YoutubeDl::tee_stderr`.
+#[derive(Clone)]
+pub(crate) struct Tee(Arc<Mutex<dyn Write + Send>>);
+
+impl fmt::Debug for Tee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Tee")
+    }
+}
+
+impl Tee {
+    pub(crate) fn new(writer: impl Write + Send + 'static) -> Self {
+        Tee(Arc::new(Mutex::new(writer)))
+    }
+
+    /// Copy the output to the sink. Errors are only logged, they must not affect parsing.
+    fn write(&self, output: &[u8]) {
+        if output.is_empty() {
+            return;
+        }
+        let mut writer = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        if let Err(err) = writer.write_all(output).and_then(|_| writer.flush()) {
+            log::warn!("failed to mirror yt-dlp output: {}", err);
+        }
+    }
+}
+
+/// Mirrors everything read from `inner` to the sink, if there is one.
+pub(crate) struct TeeReader<R> {
+    inner: R,
+    sink: Option<Tee>,
+}
+
+impl<R> TeeReader<R> {
+    pub(crate) fn new(inner: R, sink: Option<&Tee>) -> Self {
+        TeeReader {
+            inner,
+            sink: sink.cloned(),
+        }
+    }
+}
+
+impl<R: Read> Read for TeeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if let Some(sink) = &self.sink {
+            sink.write(&buf[..read]);
+        }
+        Ok(read)
+    }
+}
+
+/// Like `TeeReader`, but for async readers. The output is written to the sink on a separate
+/// thread, so a slow sink doesn't block the runtime.
+#[cfg(feature = "tokio")]
+pub(crate) struct AsyncTeeReader<R> {
+    inner: R,
+    sender: Option<std::sync::mpsc::Sender<Vec<u8>>>,
+    written: Option<tokio::sync::oneshot::Receiver<()>>,
+}
+
+#[cfg(feature = "tokio")]
+impl<R> AsyncTeeReader<R> {
+    pub(crate) fn new(inner: R, sink: Option<&Tee>) -> Self {
+        let (sender, written) = match sink {
+            Some(sink) => {
+                let sink = sink.clone();
+                let (sender, receiver) = std::sync::mpsc::channel::<Vec<u8>>();
+                let (done, written) = tokio::sync::oneshot::channel();
+                std::thread::spawn(move || {
+                    for output in receiver {
+                        sink.write(&output);
+                    }
+                    let _ = done.send(());
+                });
+                (Some(sender), Some(written))
+            }
+            None => (None, None),
+        };
+        AsyncTeeReader {
+            inner,
+            sender,
+            written,
+        }
+    }
+
+    /// Wait until everything read so far has been written to the sink.
+    pub(crate) async fn finish(mut self) {
+        self.sender = None;
+        if let Some(written) = self.written.take() {
+            let _ = written.await;
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: tokio::io::AsyncRead + Unpin> tokio::io::AsyncRead for AsyncTeeReader<R> {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let poll = std::pin::Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let (std::task::Poll::Ready(Ok(())), Some(sender)) = (&poll, &self.sender) {
+            let output = &buf.filled()[filled..];
+            if !output.is_empty() {
+                let _ = sender.send(output.to_vec());
+            }
+        }
+        poll
+    }
+}
@@ -0,0 +1,247 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tempfile::TempDir;
+
+use crate::{Error, RunReport, YoutubeDl};
+
+/// A download into a temporary directory managed by the crate, created by
+/// `YoutubeDl::download_to_temp`. Unless `persist` is called, the directory and all files in it
+/// (including partial downloads and leftover fragments) are deleted when this is dropped.
+#[derive(Debug)]
+pub struct TempDownload {
+    dir: TempDir,
+    report: RunReport,
+}
+
+impl TempDownload {
+    fn new() -> Result<Self, Error> {
+        let dir = tempfile::Builder::new().prefix("youtube-dl-").tempdir()?;
+        Ok(Self {
+            dir,
+            report: RunReport::default(),
+        })
+    }
+
+    /// The temporary directory the files are downloaded to.
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// What happened to each entry during the download. Note that the paths point into the
+    /// temporary directory, `persist` returns the new paths.
+    pub fn report(&self) -> &RunReport {
+        &self.report
+    }
+
+    /// The downloaded files in the temporary directory, from the report. Leftover partial
+    /// downloads (`.part` and `.ytdl` files) and intermediate files are not included.
+    pub fn files(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut files: Vec<PathBuf> = self
+            .report
+            .files()
+            .map(|file| self.dir.path().join(file))
+            .filter(|file| !is_partial(file) && file.is_file())
+            .collect();
+        files.sort();
+        files.dedup();
+        Ok(files)
+    }
+
+    /// Move the downloaded files to the `destination` directory (which is created if it doesn't
+    /// exist) and return their new paths. Files in subdirectories of the temporary directory
+    /// (created by the output template) keep their relative path. The temporary directory is
+    /// removed afterwards.
+    pub fn persist(self, destination: impl AsRef<Path>) -> Result<Vec<PathBuf>, Error> {
+        let destination = destination.as_ref();
+        fs::create_dir_all(destination)?;
+
+        let mut persisted = vec![];
+        for file in self.files()? {
+            let relative = match file.strip_prefix(self.dir.path()) {
+                Ok(relative) => relative,
+                Err(_) => Path::new(file.file_name().expect("file without name")),
+            };
+            let target = destination.join(relative);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            move_file(&file, &target)?;
+            persisted.push(target);
+        }
+        Ok(persisted)
+    }
+}
+
+/// Whether the file is a partial download left behind by yt-dlp.
+fn is_partial(file: &Path) -> bool {
+    file.extension()
+        .is_some_and(|extension| extension == "part" || extension == "ytdl")
+}
+
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    // Renaming fails when the temp directory is on a different file system.
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+impl YoutubeDl {
+    /// Download into a temporary directory managed by the crate. If the download fails or the
+    /// returned `TempDownload` is dropped without calling `persist`, all files are deleted.
+    pub fn download_to_temp(&self) -> Result<TempDownload, Error> {
+        let mut download = TempDownload::new()?;
+        download.report = self.download_to(download.path())?;
+        Ok(download)
+    }
+
+    /// Download into a temporary directory managed by the crate asynchronously.
+    /// See `download_to_temp` for details.
+    #[cfg(feature = "tokio")]
+    pub async fn download_to_temp_async(&self) -> Result<TempDownload, Error> {
+        let mut download = TempDownload::new()?;
+        download.report = self.download_to_async(download.path()).await?;
+        Ok(download)
+    }
+
+    /// With `isolated_temp`, a copy of the builder that uses its own directory for yt-dlp's
+    /// intermediate files and cache. A new temporary directory is removed when it is dropped,
+    /// with `resume_download` the directory next to the state file is kept for the next run.
+    pub(crate) fn isolate(&self) -> Result<Option<(YoutubeDl, Option<TempDir>)>, Error> {
+        if !self.isolated_temp {
+            return Ok(None);
+        }
+        let (path, dir) = match &self.resume_state {
+            Some(state_file) => {
+                let path = PathBuf::from(format!("{}.temp", state_file));
+                fs::create_dir_all(&path)?;
+                (path, None)
+            }
+            None => {
+                let dir = tempfile::Builder::new()
+                    .prefix("youtube-dl-rs-")
+                    .tempdir()?;
+                (dir.path().to_owned(), Some(dir))
+            }
+        };
+        let mut builder = self.clone();
+        builder.isolated_temp = false;
+        builder.temp_paths = Some((
+            format!("temp:{}", path.display()),
+            path.join("cache").display().to_string(),
+        ));
+        Ok(Some((builder, dir)))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::tests::fake_yt_dlp;
+    use crate::YoutubeDl;
+
+    #[test]
+    fn test_persist_and_cleanup() {
+        let bin = tempfile::tempdir().unwrap();
+        // `-P <folder>` is the first argument.
+        let path = fake_yt_dlp(
+            bin.path(),
+            r#"touch "$2/video.mp4" "$2/video.f137.mp4.part"
+echo "[download] Destination: $2/video.mp4""#,
+        );
+        let mut builder = YoutubeDl::new("https://example.com/video");
+        builder.youtube_dl_path(path);
+
+        let download = builder.download_to_temp().unwrap();
+        let temp_dir = download.path().to_owned();
+        assert_eq!(download.files().unwrap(), [temp_dir.join("video.mp4")]);
+        drop(download);
+        assert!(!temp_dir.exists());
+
+        let destination = tempfile::tempdir().unwrap();
+        let download = builder.download_to_temp().unwrap();
+        let temp_dir = download.path().to_owned();
+        let files = download.persist(destination.path().join("out")).unwrap();
+        assert_eq!(files, [destination.path().join("out/video.mp4")]);
+        assert!(files.iter().all(|f| f.is_file()));
+        assert!(!temp_dir.exists());
+    }
+
+    #[test]
+    fn test_persist_nested_output_template() {
+        let bin = tempfile::tempdir().unwrap();
+        // Like `-o %(playlist)s/%(title)s.%(ext)s`.
+        let path = fake_yt_dlp(
+            bin.path(),
+            r#"mkdir -p "$2/Playlist"
+touch "$2/Playlist/a.mp4" "$2/Playlist/b.mp4" "$2/Playlist/b.f137.mp4"
+echo "[info] a: Downloading 1 format(s): 18"
+echo "[download] Destination: $2/Playlist/a.mp4"
+echo "[info] b: Downloading 1 format(s): 137+140"
+echo "[download] Destination: $2/Playlist/b.f137.mp4"
+echo "[Merger] Merging formats into \"$2/Playlist/b.mp4\"""#,
+        );
+        let download = YoutubeDl::new("https://example.com/playlist")
+            .youtube_dl_path(path)
+            .download_to_temp()
+            .unwrap();
+
+        let destination = tempfile::tempdir().unwrap();
+        let files = download.persist(destination.path()).unwrap();
+        assert_eq!(
+            files,
+            [
+                destination.path().join("Playlist/a.mp4"),
+                destination.path().join("Playlist/b.mp4"),
+            ]
+        );
+        assert!(files.iter().all(|f| f.is_file()));
+        assert!(!destination.path().join("Playlist/b.f137.mp4").exists());
+    }
+
+    #[test]
+    fn test_isolated_temp() {
+        let bin = tempfile::tempdir().unwrap();
+        let log = bin.path().join("args");
+        let path = fake_yt_dlp(
+            bin.path(),
+            &format!("printf '%s\\n' \"$@\" > {}", log.display()),
+        );
+        let destination = tempfile::tempdir().unwrap();
+        let mut builder = YoutubeDl::new("https://example.com/video");
+        builder.youtube_dl_path(path).isolated_temp(true);
+        builder.download_to(destination.path()).unwrap();
+
+        let args = std::fs::read_to_string(log).unwrap();
+        let args: Vec<&str> = args.lines().collect();
+        let temp = args
+            .iter()
+            .find_map(|arg| arg.strip_prefix("temp:"))
+            .unwrap();
+        let cache = args[args.iter().position(|arg| *arg == "--cache-dir").unwrap() + 1];
+        assert_eq!(cache, format!("{}/cache", temp));
+        assert!(!std::path::Path::new(temp).exists());
+    }
+
+    #[test]
+    fn test_isolated_temp_with_resume() {
+        let bin = tempfile::tempdir().unwrap();
+        let path = fake_yt_dlp(bin.path(), "exit 1");
+        let state_file = bin.path().join("state.txt");
+        let mut builder = YoutubeDl::new("https://example.com/video");
+        builder
+            .youtube_dl_path(path)
+            .isolated_temp(true)
+            .resume_download(&state_file);
+
+        let temp = format!("{}.temp", state_file.display());
+        let (isolated, dir) = builder.isolate().unwrap().unwrap();
+        assert!(dir.is_none());
+        let temp_arg = format!("temp:{}", temp);
+        assert!(isolated.process_args().contains(&temp_arg.as_str()));
+        let _ = builder.download_to(bin.path());
+        assert!(std::path::Path::new(&temp).is_dir());
+    }
+}
@@ -0,0 +1,99 @@
+use crate::Progress;
+
+/// How to react when YouTube throttles a download, passed to `YoutubeDl::throttle_mitigation`.
+///
+/// yt-dlp re-extracts the video when the speed drops below `min_speed`. If the download was
+/// throttled anyway (yt-dlp warned about throttling, or the average speed stayed below
+/// `min_speed`), it is retried with each of the fallback player clients in turn.
+///
+/// ```rust,no_run
+/// use youtube_dl::{ThrottleMitigation, YoutubeDl};
+///
+/// let mut throttle = ThrottleMitigation::new(100 * 1024);
+/// throttle.fallback_client("tv").fallback_client("web_safari");
+/// let report = YoutubeDl::new("https://www.youtube.com/watch?v=VFbhKZFzbzk")
+///     .throttle_mitigation(throttle)
+///     .download_to("downloads");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThrottleMitigation {
+    min_speed: u64,
+    rate: String,
+    fallback_clients: Vec<String>,
+}
+
+/// Progress updates needed before a low average speed counts as throttling.
+const MIN_SAMPLES: usize = 5;
+
+impl ThrottleMitigation {
+    /// Treat downloads slower than `min_speed` bytes per second as throttled.
+    pub fn new(min_speed: u64) -> Self {
+        ThrottleMitigation {
+            min_speed,
+            rate: min_speed.to_string(),
+            fallback_clients: vec![],
+        }
+    }
+
+    /// Add a player client (like `tv` or `web_safari`) to retry a throttled download with,
+    /// passed as `--extractor-args youtube:player_client=<client>`.
+    pub fn fallback_client<S: Into<String>>(&mut self, client: S) -> &mut Self {
+        self.fallback_clients.push(client.into());
+        self
+    }
+
+    /// The value of `--throttled-rate`.
+    pub(crate) fn rate(&self) -> &str {
+        &self.rate
+    }
+
+    pub(crate) fn fallback_clients(&self) -> &[String] {
+        &self.fallback_clients
+    }
+
+    /// Whether yt-dlp's output shows that the download was throttled.
+    pub(crate) fn is_throttled(&self, stdout: &str, stderr: &str) -> bool {
+        let warned = stderr.lines().any(|line| {
+            line.starts_with("WARNING:")
+                && (line.contains("experience throttling") || line.contains("throttled"))
+        });
+        if warned {
+            return true;
+        }
+
+        let speeds: Vec<f64> = stdout
+            .lines()
+            .filter_map(Progress::parse_line)
+            .filter(|progress| progress.status == "downloading")
+            .filter_map(|progress| progress.speed)
+            .collect();
+        speeds.len() >= MIN_SAMPLES
+            && speeds.iter().sum::<f64>() / (speeds.len() as f64) < self.min_speed as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ThrottleMitigation;
+
+    fn progress(speed: u64) -> String {
+        format!(
+            "[youtube_dl_rs:progress] downloading 1000 100000 NA {} 10 abc\n",
+            speed
+        )
+    }
+
+    #[test]
+    fn test_is_throttled() {
+        let throttle = ThrottleMitigation::new(50_000);
+        let slow: String = (0..10).map(|_| progress(20_000)).collect();
+        let fast: String = (0..10).map(|_| progress(2_000_000)).collect();
+        assert!(throttle.is_throttled(&slow, ""));
+        assert!(!throttle.is_throttled(&fast, ""));
+        assert!(!throttle.is_throttled(&progress(20_000), ""));
+        assert!(throttle.is_throttled(
+            "",
+            "WARNING: [youtube] abc: nsig extraction failed: You may experience throttling for some formats\n"
+        ));
+    }
+}
@@ -0,0 +1,144 @@
+//! Helpers for picking thumbnails.
+
+use crate::Thumbnail;
+
+/// Image format of a thumbnail, detected from the file extension of its URL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ThumbnailFormat {
+    /// JPEG image
+    Jpeg,
+    /// PNG image
+    Png,
+    /// WebP image
+    Webp,
+    /// GIF image
+    Gif,
+    /// AVIF image
+    Avif,
+    /// Unknown or missing extension
+    Unknown,
+}
+
+impl ThumbnailFormat {
+    /// Detect the format from a file extension (e.g. `jpg`).
+    pub fn from_extension(ext: &str) -> Self {
+        match ext.to_ascii_lowercase().as_str() {
+            "jpg" | "jpeg" => ThumbnailFormat::Jpeg,
+            "png" => ThumbnailFormat::Png,
+            "webp" => ThumbnailFormat::Webp,
+            "gif" => ThumbnailFormat::Gif,
+            "avif" => ThumbnailFormat::Avif,
+            _ => ThumbnailFormat::Unknown,
+        }
+    }
+}
+
+impl Thumbnail {
+    /// The image format, detected from the extension of the URL.
+    pub fn format(&self) -> ThumbnailFormat {
+        let extension = self.url.as_deref().and_then(|url| {
+            let path = url.split(['?', '#']).next()?;
+            let file_name = path.rsplit('/').next()?;
+            file_name.rsplit_once('.').map(|(_, ext)| ext)
+        });
+        extension.map_or(ThumbnailFormat::Unknown, ThumbnailFormat::from_extension)
+    }
+
+    /// Whether this is a storyboard (a sprite sheet of frames for seeking previews) instead of
+    /// a cover image.
+    pub fn is_storyboard(&self) -> bool {
+        let url = self.url.as_deref().unwrap_or_default();
+        let id = self.id.as_deref().unwrap_or_default();
+        url.contains("/sb/") || url.contains("storyboard") || id.contains("storyboard")
+    }
+
+    fn area(&self) -> f64 {
+        self.width.unwrap_or(0.0) * self.height.unwrap_or(0.0)
+    }
+}
+
+/// Helpers for the thumbnails of a video or playlist (e.g. `SingleVideo::thumbnails`).
+pub trait Thumbnails {
+    /// All thumbnails, best first: by `preference` (as set by yt-dlp), then by size.
+    fn sorted_by_preference(&self) -> Vec<&Thumbnail>;
+
+    /// The largest thumbnail (by width × height) that is not a storyboard.
+    fn largest(&self) -> Option<&Thumbnail>;
+
+    /// All thumbnails except storyboards.
+    fn without_storyboards(&self) -> Vec<&Thumbnail>;
+}
+
+impl Thumbnails for [Thumbnail] {
+    fn sorted_by_preference(&self) -> Vec<&Thumbnail> {
+        let mut thumbnails: Vec<_> = self.iter().collect();
+        thumbnails.sort_by(|a, b| {
+            b.preference
+                .unwrap_or(i64::MIN)
+                .cmp(&a.preference.unwrap_or(i64::MIN))
+                .then(b.area().total_cmp(&a.area()))
+        });
+        thumbnails
+    }
+
+    fn largest(&self) -> Option<&Thumbnail> {
+        self.iter()
+            .filter(|t| !t.is_storyboard())
+            .max_by(|a, b| a.area().total_cmp(&b.area()))
+    }
+
+    fn without_storyboards(&self) -> Vec<&Thumbnail> {
+        self.iter().filter(|t| !t.is_storyboard()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ThumbnailFormat, Thumbnails};
+    use crate::{SingleVideo, Thumbnail};
+
+    fn thumbnail(url: &str, preference: i64, size: f64) -> Thumbnail {
+        Thumbnail {
+            url: Some(url.into()),
+            preference: Some(preference),
+            width: Some(size),
+            height: Some(size),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_thumbnails() {
+        let video = SingleVideo {
+            thumbnails: Some(vec![
+                thumbnail("https://i.ytimg.com/vi/abc/default.jpg", -10, 120.0),
+                thumbnail(
+                    "https://i.ytimg.com/vi_webp/abc/maxresdefault.webp?v=1",
+                    -1,
+                    1280.0,
+                ),
+                thumbnail(
+                    "https://i.ytimg.com/sb/abc/storyboard3_L2/M0.jpg",
+                    -1,
+                    1600.0,
+                ),
+                thumbnail("https://i.ytimg.com/vi/abc/hqdefault.jpg", -5, 480.0),
+            ]),
+            ..Default::default()
+        };
+        let thumbnails = video.thumbnails.as_deref().unwrap();
+
+        let largest = thumbnails.largest().unwrap();
+        assert_eq!(largest.format(), ThumbnailFormat::Webp);
+        assert_eq!(largest.width, Some(1280.0));
+
+        assert_eq!(thumbnails.without_storyboards().len(), 3);
+        let sorted: Vec<_> = thumbnails
+            .sorted_by_preference()
+            .into_iter()
+            .map(|t| t.width.unwrap())
+            .collect();
+        assert_eq!(sorted, [1600.0, 1280.0, 480.0, 120.0]);
+        assert_eq!(thumbnails[0].format(), ThumbnailFormat::Jpeg);
+    }
+}
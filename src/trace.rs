@@ -0,0 +1,138 @@
+//! Instrumentation of yt-dlp invocations. With the `tracing` feature, this emits spans and
+//! events via `tracing`, otherwise it falls back to the `log` crate (or does nothing), so the
+//! runner code does not need `cfg` attributes everywhere.
+
+use std::time::Duration;
+
+#[cfg(feature = "tracing")]
+pub(crate) use tracing::Span;
+
+#[cfg(feature = "tracing")]
+pub(crate) use tracing::Instrument;
+
+#[cfg(feature = "tracing")]
+pub(crate) fn process_span(url: &str, args: &[&str]) -> Span {
+    tracing::info_span!(
+        "yt_dlp",
+        url = %url,
+        args_hash = %format_args!("{:016x}", crate::fixtures::args_hash(args)),
+        exit_code = tracing::field::Empty,
+        stdout_bytes = tracing::field::Empty,
+        duration_ms = tracing::field::Empty,
+    )
+}
+
+#[cfg(feature = "tracing")]
+pub(crate) fn spawn_span() -> Span {
+    tracing::debug_span!("spawn")
+}
+
+#[cfg(feature = "tracing")]
+pub(crate) fn wait_span() -> Span {
+    tracing::debug_span!("wait")
+}
+
+#[cfg(feature = "tracing")]
+pub(crate) fn parse_span(stdout_bytes: usize) -> Span {
+    tracing::debug_span!("parse", stdout_bytes)
+}
+
+#[cfg(feature = "tracing")]
+pub(crate) fn args(args: &[&str]) {
+    let args = crate::args::redact(args);
+    tracing::debug!(?args, "youtube-dl arguments");
+}
+
+#[cfg(feature = "tracing")]
+pub(crate) fn split_extra_arg(arg: &str) {
+    tracing::warn!(
+        arg,
+        "extra argument contains whitespace, use `extra_arg_pair` instead"
+    );
+}
+
+#[cfg(feature = "tracing")]
+pub(crate) fn finished(exit_code: Option<i32>, stdout_bytes: usize, duration: Duration) {
+    let span = Span::current();
+    if let Some(code) = exit_code {
+        span.record("exit_code", code);
+    }
+    span.record("stdout_bytes", stdout_bytes);
+    span.record("duration_ms", duration.as_millis() as u64);
+}
+
+#[cfg(feature = "tracing")]
+pub(crate) fn stderr(stderr: &[u8]) {
+    for line in String::from_utf8_lossy(stderr).lines() {
+        if let Some(message) = line.strip_prefix("WARNING: ") {
+            tracing::warn!(message, "yt-dlp warning");
+        }
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Span;
+
+#[cfg(not(feature = "tracing"))]
+impl Span {
+    pub(crate) fn in_scope<F: FnOnce() -> T, T>(&self, f: F) -> T {
+        f()
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) trait Instrument: Sized {
+    fn instrument(self, _span: Span) -> Self {
+        self
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+impl<T: Sized> Instrument for T {}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn process_span(_url: &str, _args: &[&str]) -> Span {
+    Span
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn spawn_span() -> Span {
+    Span
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn wait_span() -> Span {
+    Span
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn parse_span(_stdout_bytes: usize) -> Span {
+    Span
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn args(args: &[&str]) {
+    log::debug!("youtube-dl arguments: {:?}", crate::args::redact(args));
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn split_extra_arg(arg: &str) {
+    log::warn!(
+        "extra argument {:?} contains whitespace, use `extra_arg_pair` instead",
+        arg
+    );
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn finished(exit_code: Option<i32>, stdout_bytes: usize, duration: Duration) {
+    log::debug!(
+        "youtube-dl exited with {:?} after {:?}, {} bytes of output",
+        exit_code,
+        duration,
+        stdout_bytes
+    );
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn stderr(_stderr: &[u8]) {}
@@ -0,0 +1,117 @@
+use std::time::{Duration, SystemTime};
+
+use crate::{Error, SingleVideo};
+
+impl SingleVideo {
+    /// Fails with `Error::NotYetAvailable` if the video is an upcoming live stream or premiere
+    /// (`live_status` is `is_upcoming`, e.g. when fetched with `ignore_no_formats_error`).
+    pub fn check_available(&self) -> Result<(), Error> {
+        if self.live_status.as_deref() != Some("is_upcoming") {
+            return Ok(());
+        }
+        let starts_at = self
+            .release_timestamp
+            .and_then(|timestamp| Duration::try_from_secs_f64(timestamp).ok())
+            .and_then(|since_epoch| SystemTime::UNIX_EPOCH.checked_add(since_epoch));
+        Err(Error::NotYetAvailable { starts_at })
+    }
+}
+
+/// Recognizes yt-dlp's errors for upcoming live streams and premieres, like
+/// `ERROR: [youtube] abc: Premieres in 5 hours`. The start time is estimated from the relative
+/// time in the message.
+pub(crate) fn parse_error(stderr: &str) -> Option<Error> {
+    stderr
+        .lines()
+        .filter_map(|line| line.strip_prefix("ERROR: "))
+        .find_map(|message| {
+            if message.contains("will begin shortly") || message.contains("in a few moments") {
+                return Some(Error::NotYetAvailable {
+                    starts_at: Some(SystemTime::now()),
+                });
+            }
+            let (_, relative) = message
+                .split_once("will begin in ")
+                .or_else(|| message.split_once("Premieres in "))?;
+            Some(Error::NotYetAvailable {
+                starts_at: parse_duration(relative)
+                    .and_then(|duration| SystemTime::now().checked_add(duration)),
+            })
+        })
+}
+
+/// Parse durations like `5 hours` or `1 day.`
+fn parse_duration(text: &str) -> Option<Duration> {
+    let mut words = text.split_whitespace();
+    let amount: u64 = words.next()?.parse().ok()?;
+    let unit = words.next()?.trim_end_matches('.');
+    let seconds = match unit.trim_end_matches('s') {
+        "second" => 1,
+        "minute" => 60,
+        "hour" => 60 * 60,
+        "day" => 24 * 60 * 60,
+        "week" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(amount.checked_mul(seconds)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::parse_error;
+    use crate::{Error, SingleVideo};
+
+    fn starts_in(stderr: &str) -> Option<Duration> {
+        match parse_error(stderr) {
+            Some(Error::NotYetAvailable { starts_at }) => Some(
+                starts_at?
+                    .duration_since(SystemTime::now())
+                    .unwrap_or_default(),
+            ),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let starts = starts_in("ERROR: [youtube] abc: Premieres in 5 hours\n").unwrap();
+        assert!(starts > Duration::from_secs(4 * 3600) && starts <= Duration::from_secs(5 * 3600));
+        let starts =
+            starts_in("ERROR: [youtube] abc: This live event will begin in 20 minutes.").unwrap();
+        assert!(starts > Duration::from_secs(19 * 60));
+        assert!(matches!(
+            parse_error("ERROR: [youtube] abc: This live event will begin in a few moments."),
+            Some(Error::NotYetAvailable { starts_at: Some(_) })
+        ));
+        assert!(parse_error("ERROR: [youtube] abc: Video unavailable").is_none());
+        assert!(matches!(
+            parse_error("ERROR: [youtube] abc: Premieres in 99999999999999999 weeks"),
+            Some(Error::NotYetAvailable { starts_at: None })
+        ));
+    }
+
+    #[test]
+    fn test_check_available() {
+        let mut video = SingleVideo {
+            live_status: Some("is_upcoming".into()),
+            release_timestamp: Some(1_900_000_000.0),
+            ..Default::default()
+        };
+        match video.check_available() {
+            Err(Error::NotYetAvailable { starts_at }) => assert_eq!(
+                starts_at,
+                Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_900_000_000))
+            ),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        video.release_timestamp = Some(1e300);
+        assert!(matches!(
+            video.check_available(),
+            Err(Error::NotYetAvailable { starts_at: None })
+        ));
+        video.live_status = Some("not_live".into());
+        assert!(video.check_available().is_ok());
+    }
+}
@@ -0,0 +1,232 @@
+use crate::{Error, YoutubeDl};
+
+/// Hosts of YouTube, without the `www.`, `m.` or `music.` prefix.
+const YOUTUBE_HOSTS: &[&str] = &["youtube.com", "youtube-nocookie.com", "youtu.be"];
+
+/// Path prefixes that are followed by the video ID.
+const VIDEO_PATHS: &[&str] = &["shorts/", "embed/", "live/", "v/", "e/"];
+
+/// The parts of a YouTube URL: the host without the subdomain, the path without the leading
+/// slash, and the query string.
+pub(crate) fn split_youtube_url(url: &str) -> Option<(&str, &str, &str)> {
+    let url = url.trim();
+    let url = url.split('#').next().unwrap_or(url);
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url);
+    let (host, rest) = match rest.find(['/', '?']) {
+        Some(index) => rest.split_at(index),
+        None => (rest, ""),
+    };
+    let host = host.to_ascii_lowercase();
+    let bare = ["www.", "m.", "music."]
+        .iter()
+        .find_map(|prefix| host.strip_prefix(prefix))
+        .unwrap_or(&host);
+    let host = YOUTUBE_HOSTS
+        .iter()
+        .copied()
+        .find(|youtube| *youtube == bare)?;
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    Some((host, path.trim_start_matches('/'), query))
+}
+
+/// The value of the parameter in a query string.
+fn query_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+pub(crate) fn is_video_id(id: &str) -> bool {
+    id.len() == 11
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+/// Extract the ID of the video from a YouTube URL, e.g. `dQw4w9WgXcQ` from
+/// `https://youtu.be/dQw4w9WgXcQ?t=42`. Handles `watch` URLs (on `www.`, `m.` and `music.`),
+/// `youtu.be` links, Shorts, embeds and live streams. Returns `None` for other URLs, including
+/// playlists and channels.
+///
+/// ```rust
+/// use youtube_dl::urls::extract_video_id;
+///
+/// assert_eq!(
+///     extract_video_id("https://m.youtube.com/shorts/dQw4w9WgXcQ?feature=share"),
+///     Some("dQw4w9WgXcQ")
+/// );
+/// assert_eq!(extract_video_id("https://example.com/watch?v=dQw4w9WgXcQ"), None);
+/// ```
+pub fn extract_video_id(url: &str) -> Option<&str> {
+    let (host, path, query) = split_youtube_url(url)?;
+    let id = if host == "youtu.be" {
+        path.split('/').next()?
+    } else if path == "watch" {
+        query_param(query, "v")?
+    } else {
+        let id = VIDEO_PATHS
+            .iter()
+            .find_map(|prefix| path.strip_prefix(prefix))?;
+        id.split('/').next()?
+    };
+    is_video_id(id).then_some(id)
+}
+
+/// Whether the URL is a YouTube Short (`youtube.com/shorts/<id>`). Shorts are regular videos
+/// to yt-dlp, but `canonicalize_youtube` turns their URLs into `watch` URLs.
+pub fn is_shorts(url: &str) -> bool {
+    split_youtube_url(url).is_some_and(|(_, path, _)| path.starts_with("shorts/"))
+}
+
+/// Whether the URL is a YouTube clip (`youtube.com/clip/<clip id>`), a section of a video.
+/// yt-dlp only downloads the section, which is described by `SingleVideo::section_start` and
+/// `SingleVideo::section_end`. See `YoutubeDl::treat_clip_as_full_video`.
+pub fn is_clip(url: &str) -> bool {
+    split_youtube_url(url).is_some_and(|(_, path, _)| path.starts_with("clip/"))
+}
+
+/// Rewrite a YouTube URL to its canonical form, e.g. to compare URLs or use them as keys:
+/// `https://www.youtube.com/watch?v=<id>` for videos, dropping the timestamp, playlist and
+/// tracking parameters, and `https://www.youtube.com/playlist?list=<id>` for playlists.
+/// Returns `None` for other URLs.
+///
+/// ```rust
+/// use youtube_dl::urls::canonicalize_youtube;
+///
+/// assert_eq!(
+///     canonicalize_youtube("https://youtu.be/dQw4w9WgXcQ?t=42&si=abc").as_deref(),
+///     Some("https://www.youtube.com/watch?v=dQw4w9WgXcQ")
+/// );
+/// ```
+pub fn canonicalize_youtube(url: &str) -> Option<String> {
+    if let Some(id) = extract_video_id(url) {
+        return Some(format!("https://www.youtube.com/watch?v={}", id));
+    }
+    let (_, path, query) = split_youtube_url(url)?;
+    let list = query_param(query, "list").filter(|list| !list.is_empty())?;
+    (path == "playlist").then(|| format!("https://www.youtube.com/playlist?list={}", list))
+}
+
+impl YoutubeDl {
+    /// With `treat_clip_as_full_video`, a copy of the builder for the video of a clip URL. The
+    /// clip has to be extracted first to find the video.
+    pub(crate) fn resolve_clip(&self) -> Result<Option<YoutubeDl>, Error> {
+        if !self.clip_as_full_video || !is_clip(&self.url) {
+            return Ok(None);
+        }
+        let mut resolver = self.clone();
+        resolver.clip_as_full_video = false;
+        let info = resolver.run_raw()?;
+        Ok(clip_video_url(&info).map(|url| self.with_url(url)))
+    }
+
+    /// Asynchronous version of `resolve_clip`.
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn resolve_clip_async(&self) -> Result<Option<YoutubeDl>, Error> {
+        if !self.clip_as_full_video || !is_clip(&self.url) {
+            return Ok(None);
+        }
+        let mut resolver = self.clone();
+        resolver.clip_as_full_video = false;
+        let info = resolver.run_raw_async().await?;
+        Ok(clip_video_url(&info).map(|url| self.with_url(url)))
+    }
+}
+
+/// The URL of the whole video, from the extracted clip. yt-dlp reports the clip's ID as `id`,
+/// but the `webpage_url` of the video.
+fn clip_video_url(info: &serde_json::Value) -> Option<&str> {
+    info["webpage_url"]
+        .as_str()
+        .filter(|url| extract_video_id(url).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{canonicalize_youtube, extract_video_id, is_clip, is_shorts};
+
+    #[test]
+    fn test_extract_video_id() {
+        for url in [
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "http://youtube.com/watch?feature=share&v=dQw4w9WgXcQ&t=1m2s",
+            "www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL123&index=2",
+            "https://m.youtube.com/watch?v=dQw4w9WgXcQ#comments",
+            "https://music.youtube.com/watch?v=dQw4w9WgXcQ&si=abc",
+            "https://youtu.be/dQw4w9WgXcQ?t=42",
+            "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+            "https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ?start=10",
+            "https://www.youtube.com/live/dQw4w9WgXcQ?feature=shared",
+            "https://WWW.YouTube.com/watch?v=dQw4w9WgXcQ",
+        ] {
+            assert_eq!(extract_video_id(url), Some("dQw4w9WgXcQ"), "{}", url);
+        }
+        for url in [
+            "https://www.youtube.com/playlist?list=PL123",
+            "https://www.youtube.com/@channel",
+            "https://www.youtube.com/watch?v=short",
+            "https://notyoutube.com/watch?v=dQw4w9WgXcQ",
+            "https://vimeo.com/123456",
+        ] {
+            assert_eq!(extract_video_id(url), None, "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_youtube() {
+        assert_eq!(
+            canonicalize_youtube("https://music.youtube.com/watch?v=dQw4w9WgXcQ&list=RD123")
+                .as_deref(),
+            Some("https://www.youtube.com/watch?v=dQw4w9WgXcQ")
+        );
+        assert_eq!(
+            canonicalize_youtube("https://m.youtube.com/playlist?list=PL123&si=abc").as_deref(),
+            Some("https://www.youtube.com/playlist?list=PL123")
+        );
+        assert_eq!(
+            canonicalize_youtube("https://www.youtube.com/@channel"),
+            None
+        );
+        assert_eq!(canonicalize_youtube("https://vimeo.com/123456"), None);
+    }
+
+    #[test]
+    fn test_shorts_and_clips() {
+        assert!(is_shorts(
+            "https://youtube.com/shorts/dQw4w9WgXcQ?feature=share"
+        ));
+        assert!(!is_shorts("https://www.youtube.com/watch?v=dQw4w9WgXcQ"));
+        let clip = "https://www.youtube.com/clip/UgkxU2HSeGL_NvmDJ-nQJrlLwllwMDBdGZFs";
+        assert!(is_clip(clip));
+        assert_eq!(extract_video_id(clip), None);
+        assert!(!is_clip("https://vimeo.com/clip/123"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_clip() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = r#"echo '{"id": "UgkxU2HSeGL_NvmDJ-nQJrlLwllwMDBdGZFs",
+            "webpage_url": "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "section_start": 12.0, "section_end": 42.0}'"#;
+        let path = crate::tests::fake_yt_dlp(dir.path(), script);
+        let mut builder = crate::YoutubeDl::new(
+            "https://www.youtube.com/clip/UgkxU2HSeGL_NvmDJ-nQJrlLwllwMDBdGZFs",
+        );
+        builder.youtube_dl_path(path);
+        assert!(builder.resolve_clip().unwrap().is_none());
+
+        builder.treat_clip_as_full_video(true);
+        let resolved = builder.resolve_clip().unwrap().unwrap();
+        assert_eq!(resolved.url, "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+
+        let video = builder.run().unwrap().into_single_video().unwrap();
+        assert_eq!(video.section_start, Some(12.0));
+        assert_eq!(video.section_end, Some(42.0));
+    }
+}